@@ -0,0 +1,73 @@
+// FFI counterpart of the server's `/events` stream: the frontend registers a callback
+// and gets every event as a JSON string, instead of polling for download results.
+//
+// Events are emitted in the middle of library calls (e.g. while downloading pages), when
+// calling back into the frontend isn't safe. They're queued instead, and sent to the
+// callback when the frontend calls `rakuyomi_dispatch_events`.
+
+use std::collections::VecDeque;
+use std::ffi::{c_char, CString};
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+/// How many events are kept until the frontend dispatches them. Older ones are dropped.
+const PENDING_EVENTS_LIMIT: usize = 256;
+
+/// Receives a null-terminated JSON string, only valid for the duration of the call.
+pub type EventCallback = extern "C" fn(event_json: *const c_char);
+
+static EVENT_CALLBACK: Mutex<Option<EventCallback>> = Mutex::new(None);
+static PENDING_EVENTS: Mutex<VecDeque<Value>> = Mutex::new(VecDeque::new());
+
+/// Register the callback that receives events, or pass NULL to stop receiving them.
+/// The callback is only invoked by `rakuyomi_dispatch_events`, on the calling thread.
+#[no_mangle]
+pub extern "C" fn rakuyomi_set_event_callback(callback: Option<EventCallback>) {
+    if let Ok(mut current) = EVENT_CALLBACK.lock() {
+        *current = callback;
+    }
+
+    if callback.is_none() {
+        if let Ok(mut pending) = PENDING_EVENTS.lock() {
+            pending.clear();
+        }
+    }
+}
+
+/// Send the queued events to the registered callback, on the calling thread.
+#[no_mangle]
+pub extern "C" fn rakuyomi_dispatch_events() {
+    let Some(callback) = EVENT_CALLBACK.lock().ok().and_then(|callback| *callback) else {
+        return;
+    };
+
+    let pending = match PENDING_EVENTS.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return,
+    };
+
+    for event in pending {
+        if let Ok(event_json) = CString::new(event.to_string()) {
+            callback(event_json.as_ptr());
+        }
+    }
+}
+
+/// Queue an event for the registered callback, if any.
+/// The event must be an object with a `type` field, like the server's events.
+pub(crate) fn emit(event: Value) {
+    let has_callback = EVENT_CALLBACK
+        .lock()
+        .is_ok_and(|callback| callback.is_some());
+    if !has_callback {
+        return;
+    }
+
+    if let Ok(mut pending) = PENDING_EVENTS.lock() {
+        if pending.len() >= PENDING_EVENTS_LIMIT {
+            pending.pop_front();
+        }
+        pending.push_back(event);
+    }
+}
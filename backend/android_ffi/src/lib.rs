@@ -11,6 +11,9 @@ use serde::{Deserialize, Serialize};
 mod sources;
 pub use sources::*;
 
+mod events;
+pub use events::{rakuyomi_dispatch_events, rakuyomi_set_event_callback};
+
 // Global state
 struct AppState {
     config_dir: PathBuf,
@@ -1151,6 +1154,12 @@ pub unsafe extern "C" fn rakuyomi_create_cbz(
     // Synchronous download
     match download_pages_sync(&output_dir_str, &urls) {
         Ok(count) => {
+            events::emit(serde_json::json!({
+                "type": if count > 0 { "download_finished" } else { "download_failed" },
+                "folder": output_dir_str,
+                "images": count
+            }));
+
             let result_json = serde_json::json!({
                 "success": count > 0,
                 "path": format!("{}/001.jpg", output_dir_str),
@@ -1160,6 +1169,12 @@ pub unsafe extern "C" fn rakuyomi_create_cbz(
             string_to_c_str(result_json.to_string())
         }
         Err(e) => {
+            events::emit(serde_json::json!({
+                "type": "download_failed",
+                "folder": output_dir_str,
+                "error": e
+            }));
+
             string_to_c_str(format!(r#"{{"success":false,"error":"{}"}}"#, e))
         }
     }
@@ -1172,6 +1187,9 @@ fn download_pages_sync(output_dir: &str, urls: &[String]) -> Result<usize, Strin
         .map_err(|e| format!("Failed to create directory: {}", e))?;
     
     let mut downloaded = 0;
+    let mut failed = 0;
+    let mut bytes_downloaded = 0u64;
+    let started_at = std::time::Instant::now();
     
     for (i, url) in urls.iter().enumerate() {
         let filename = format!("{:03}.jpg", i + 1);
@@ -1180,18 +1198,34 @@ fn download_pages_sync(output_dir: &str, urls: &[String]) -> Result<usize, Strin
         // Check if already exists
         if std::path::Path::new(&filepath).exists() {
             downloaded += 1;
-            continue;
-        }
-        
-        // Use curl via system command
-        match std::process::Command::new("curl")
-            .args(&["-sL", "-o", &filepath, "-m", "30", url])
-            .status() {
-            Ok(status) if status.success() => {
-                downloaded += 1;
+        } else {
+            // Use curl via system command
+            match std::process::Command::new("curl")
+                .args(&["-sL", "-o", &filepath, "-m", "30", url])
+                .status() {
+                Ok(status) if status.success() => {
+                    downloaded += 1;
+                    bytes_downloaded += std::fs::metadata(&filepath).map(|m| m.len()).unwrap_or(0);
+                }
+                _ => {
+                    failed += 1;
+                }
             }
-            _ => {}
         }
+
+        // Same shape as the server's `DownloadProgress`
+        let elapsed = started_at.elapsed().as_secs_f64();
+        events::emit(serde_json::json!({
+            "type": "download_progress",
+            "folder": output_dir,
+            "progress": {
+                "pages_done": i + 1,
+                "pages_total": urls.len(),
+                "pages_failed": failed,
+                "bytes_downloaded": bytes_downloaded,
+                "bytes_per_second": if elapsed > 0.0 { (bytes_downloaded as f64 / elapsed) as u64 } else { 0 },
+            }
+        }));
     }
     
     Ok(downloaded)
@@ -0,0 +1,21 @@
+use axum::extract::State as StateExtractor;
+use axum::routing::get;
+use axum::{Json, Router};
+use shared::usecases::{self, get_connectivity::Connectivity};
+
+use crate::state::State;
+use crate::AppError;
+
+pub fn routes() -> Router<State> {
+    Router::new().route("/connectivity", get(get_connectivity))
+}
+
+async fn get_connectivity(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+) -> Result<Json<Connectivity>, AppError> {
+    let database = database.lock().await;
+
+    let connectivity = usecases::get_connectivity(&database).await?;
+
+    Ok(Json(connectivity))
+}
@@ -0,0 +1,121 @@
+use axum::extract::{Path, State as StateExtractor};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use shared::model::ChapterId;
+use shared::usecases::{self, get_download_queue::DownloadQueue};
+
+use crate::state::State;
+use crate::AppError;
+
+pub fn routes() -> Router<State> {
+    Router::new()
+        .route("/download-queue", get(get_download_queue))
+        .route("/download-queue", post(enqueue_chapter_downloads))
+        .route("/download-queue/pause", post(pause_download_queue))
+        .route("/download-queue/resume", post(resume_download_queue))
+        .route("/download-queue/{id}", delete(remove_queued_download))
+        .route("/download-queue/{id}/pause", post(pause_queued_download))
+        .route("/download-queue/{id}/resume", post(resume_queued_download))
+}
+
+async fn get_download_queue(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+) -> Result<Json<DownloadQueue>, AppError> {
+    let database = database.lock().await;
+
+    let queue = usecases::get_download_queue(&database).await?;
+
+    Ok(Json(queue))
+}
+
+#[derive(Deserialize)]
+struct EnqueueChapterDownloadsBody {
+    source_id: String,
+    manga_id: String,
+    chapter_ids: Vec<String>,
+    #[serde(default)]
+    priority: i64,
+}
+
+async fn enqueue_chapter_downloads(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Json(body): Json<EnqueueChapterDownloadsBody>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+    let chapter_ids: Vec<_> = body
+        .chapter_ids
+        .into_iter()
+        .map(|chapter_id| {
+            ChapterId::from_strings(body.source_id.clone(), body.manga_id.clone(), chapter_id)
+        })
+        .collect();
+
+    usecases::enqueue_chapter_downloads(&database, &chapter_ids, body.priority).await?;
+
+    Ok(Json(()))
+}
+
+async fn pause_download_queue(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+
+    usecases::set_download_queue_paused(&database, true).await?;
+
+    Ok(Json(()))
+}
+
+async fn resume_download_queue(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+
+    usecases::set_download_queue_paused(&database, false).await?;
+
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct QueuedDownloadParams {
+    id: i64,
+}
+
+async fn remove_queued_download(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(QueuedDownloadParams { id }): Path<QueuedDownloadParams>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+
+    if !usecases::remove_queued_download(&database, id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(()))
+}
+
+async fn pause_queued_download(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(QueuedDownloadParams { id }): Path<QueuedDownloadParams>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+
+    if !usecases::set_queued_download_paused(&database, id, true).await? {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(()))
+}
+
+async fn resume_queued_download(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(QueuedDownloadParams { id }): Path<QueuedDownloadParams>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+
+    if !usecases::set_queued_download_paused(&database, id, false).await? {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(()))
+}
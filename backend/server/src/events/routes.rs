@@ -0,0 +1,43 @@
+use std::convert::Infallible;
+
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::routing::get;
+use axum::Router;
+use futures::{stream, Stream};
+use log::warn;
+use shared::events;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::state::State;
+
+pub fn routes() -> Router<State> {
+    Router::new().route("/events", get(get_events))
+}
+
+/// Streams every background event (see `shared::events::Event`) as it happens. Each SSE
+/// message is named after the event type and carries the event as JSON.
+async fn get_events() -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let receiver = events::subscribe();
+
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let Ok(data) = serde_json::to_value(&event) else {
+                        continue;
+                    };
+                    let name = data["type"].as_str().unwrap_or("message").to_owned();
+                    let sse_event = SseEvent::default().event(name).data(data.to_string());
+
+                    return Some((Ok(sse_event), receiver));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("events stream lagged behind, skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
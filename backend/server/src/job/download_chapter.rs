@@ -1,6 +1,11 @@
 use shared::{
-    chapter_downloader::DownloadError, chapter_storage::ChapterStorage, database::Database,
-    model::ChapterId, source_collection::SourceCollection, source_manager::SourceManager, usecases,
+    chapter_downloader::{DownloadError, DownloadProgress, ProgressCallback},
+    chapter_storage::ChapterStorage,
+    database::Database,
+    model::ChapterId,
+    source_collection::SourceCollection,
+    source_manager::SourceManager,
+    usecases,
 };
 use std::{path::PathBuf, sync::Arc};
 use tokio::sync::watch;
@@ -18,6 +23,7 @@ type JobReceiver =
 pub struct DownloadChapterJob {
     tx: JobSender,
     rx: JobReceiver,
+    progress_rx: watch::Receiver<DownloadProgress>,
     handle: JoinHandle<()>,
     cancellation_token: CancellationToken,
 }
@@ -34,6 +40,7 @@ impl DownloadChapterJob {
         let (tx, rx) = watch::channel::<
             Option<Result<Arc<(PathBuf, Vec<DownloadError>)>, ErrorResponse>>,
         >(None);
+        let (progress_tx, progress_rx) = watch::channel(DownloadProgress::default());
 
         let cancellation_token = CancellationToken::new();
         let tx_clone = tx.clone();
@@ -47,6 +54,7 @@ impl DownloadChapterJob {
                 chapter_id,
                 concurrent_requests_pages,
                 optimize_image,
+                progress_tx,
             )
             .await
             .map(Arc::new);
@@ -57,11 +65,13 @@ impl DownloadChapterJob {
         Self {
             tx,
             rx,
+            progress_rx,
             handle,
             cancellation_token,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn do_job(
         cancellation_token: CancellationToken,
         source_manager: Arc<tokio::sync::Mutex<SourceManager>>,
@@ -70,6 +80,7 @@ impl DownloadChapterJob {
         chapter_id: ChapterId,
         concurrent_requests_pages: usize,
         optimize_image: bool,
+        progress_tx: watch::Sender<DownloadProgress>,
     ) -> Result<(PathBuf, Vec<DownloadError>), ErrorResponse> {
         let source = {
             let mgr = source_manager.lock().await;
@@ -78,6 +89,9 @@ impl DownloadChapterJob {
                 .ok_or(AppError::SourceNotFound)?
         };
         let db: tokio::sync::MutexGuard<'_, Database> = { db.lock().await };
+        let on_progress: ProgressCallback = Arc::new(move |progress| {
+            progress_tx.send_replace(progress);
+        });
 
         Ok(usecases::fetch_manga_chapter(
             &cancellation_token,
@@ -87,6 +101,7 @@ impl DownloadChapterJob {
             &chapter_id,
             concurrent_requests_pages,
             optimize_image,
+            Some(on_progress),
         )
         .await
         .map_err(AppError::from)?)
@@ -94,7 +109,9 @@ impl DownloadChapterJob {
 }
 
 impl Job for DownloadChapterJob {
-    type Progress = ();
+    const KIND: &'static str = "download_chapter";
+
+    type Progress = DownloadProgress;
     type Output = Arc<(PathBuf, Vec<DownloadError>)>;
     type Error = ErrorResponse;
 
@@ -111,7 +128,7 @@ impl Job for DownloadChapterJob {
 
     async fn poll(&self) -> JobState<Self::Progress, Self::Output, Self::Error> {
         match self.rx.borrow().as_ref() {
-            None => JobState::InProgress(()),
+            None => JobState::InProgress(*self.progress_rx.borrow()),
             Some(Ok(path)) => JobState::Completed(path.clone()),
             Some(Err(e)) => JobState::Errored(e.clone()),
         }
@@ -1,5 +1,6 @@
 use serde::Serialize;
 use shared::{
+    chapter_downloader::DownloadProgress,
     chapter_storage::ChapterStorage,
     database::Database,
     model::MangaId,
@@ -18,7 +19,11 @@ use crate::{AppError, ErrorResponse};
 #[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "type")]
 pub enum SerializableProgress {
     Initializing,
-    Downloading { downloaded: usize, total: usize },
+    Downloading {
+        downloaded: usize,
+        total: usize,
+        chapter: Option<DownloadProgress>,
+    },
 }
 
 pub struct DownloadScanlatorChaptersJob {
@@ -83,9 +88,16 @@ impl DownloadScanlatorChaptersJob {
 
             while let Some(progress_report) = pinned_stream.next().await {
                 match progress_report {
-                    ProgressReport::Progressing { downloaded, total } => {
-                        *progress_clone.lock().await =
-                            SerializableProgress::Downloading { downloaded, total };
+                    ProgressReport::Progressing {
+                        downloaded,
+                        total,
+                        chapter,
+                    } => {
+                        *progress_clone.lock().await = SerializableProgress::Downloading {
+                            downloaded,
+                            total,
+                            chapter,
+                        };
                     }
                     ProgressReport::Finished => {
                         *output_clone.lock().await = Some(Ok(()));
@@ -114,6 +126,8 @@ impl DownloadScanlatorChaptersJob {
 }
 
 impl Job for DownloadScanlatorChaptersJob {
+    const KIND: &'static str = "download_scanlator_chapters";
+
     type Progress = SerializableProgress;
     type Output = ();
     type Error = ErrorResponse;
@@ -4,6 +4,7 @@ use anyhow::anyhow;
 use futures::{lock::Mutex, pin_mut, StreamExt};
 use serde::Serialize;
 use shared::{
+    chapter_downloader::DownloadProgress,
     chapter_storage::ChapterStorage,
     database::Database,
     model::MangaId,
@@ -30,7 +31,11 @@ enum Status {
 #[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "type")]
 pub enum Progress {
     Initializing,
-    Downloading { downloaded: usize, total: usize },
+    Downloading {
+        downloaded: usize,
+        total: usize,
+        chapter: Option<DownloadProgress>,
+    },
 }
 
 pub struct DownloadUnreadChaptersJob {
@@ -99,6 +104,8 @@ impl DownloadUnreadChaptersJob {
 }
 
 impl Job for DownloadUnreadChaptersJob {
+    const KIND: &'static str = "download_unread_chapters";
+
     type Progress = Progress;
     type Output = ();
     type Error = ErrorResponse;
@@ -115,12 +122,15 @@ impl Job for DownloadUnreadChaptersJob {
         match status {
             Status::Initializing => JobState::InProgress(Progress::Initializing),
             Status::Initialized(report) => match report {
-                ProgressReport::Progressing { downloaded, total } => {
-                    JobState::InProgress(Progress::Downloading {
-                        downloaded: *downloaded,
-                        total: *total,
-                    })
-                }
+                ProgressReport::Progressing {
+                    downloaded,
+                    total,
+                    chapter,
+                } => JobState::InProgress(Progress::Downloading {
+                    downloaded: *downloaded,
+                    total: *total,
+                    chapter: *chapter,
+                }),
                 ProgressReport::Finished => JobState::Completed(()),
                 // FIXME this is weird as fuck
                 ProgressReport::Errored(e) => {
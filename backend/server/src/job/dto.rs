@@ -1,14 +1,11 @@
 use serde::Serialize;
 use serde_json::Value;
+use shared::model::MangaId;
+use uuid::Uuid;
 
-use super::{
-    download_chapter::DownloadChapterJob,
-    download_scanlator_chapters::DownloadScanlatorChaptersJob,
-    download_unread_chapters::DownloadUnreadChaptersJob,
-    state::{Job, JobState, RunningJob},
-};
+use super::state::{JobEntry, JobState};
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "type", content = "data")]
 pub enum JobDetail {
     Pending(Value),
@@ -17,52 +14,56 @@ pub enum JobDetail {
 }
 
 impl JobDetail {
-    pub async fn from_job(job: RunningJob) -> (Self, Option<RunningJob>) {
-        match job {
-            RunningJob::Chapter(job) => Self::from_download_chapter_job(job).await,
-            RunningJob::UnreadChapters(job) => Self::from_download_unread_chapters_job(job).await,
-            RunningJob::ScanlatorChapters(job) => {
-                Self::from_download_scanlator_chapters_job(job).await
-            }
+    /// Jobs completing without an output (`Output = ()`), like the unread and scanlator chapter
+    /// downloads, complete with `null` data, as they always did.
+    pub fn from_state<P, O, E>(state: JobState<P, O, E>) -> Self
+    where
+        P: Serialize,
+        O: Serialize,
+        E: Serialize,
+    {
+        match state {
+            JobState::InProgress(v) => JobDetail::Pending(serde_json::to_value(v).unwrap()),
+            JobState::Completed(v) => JobDetail::Completed(serde_json::to_value(v).unwrap()),
+            JobState::Errored(v) => JobDetail::Error(serde_json::to_value(v).unwrap()),
         }
     }
+}
 
-    async fn from_download_chapter_job(job: DownloadChapterJob) -> (Self, Option<RunningJob>) {
-        match job.poll().await {
-            JobState::InProgress(v) => (
-                JobDetail::Pending(serde_json::to_value(v).unwrap()),
-                Some(RunningJob::Chapter(job)),
-            ),
-            JobState::Completed(v) => {
-                (JobDetail::Completed(serde_json::to_value(v).unwrap()), None)
-            }
-            JobState::Errored(v) => (JobDetail::Error(serde_json::to_value(v).unwrap()), None),
-        }
-    }
+#[derive(Serialize)]
+pub struct JobSummary {
+    pub id: Uuid,
+    pub kind: &'static str,
+    pub manga_id: Option<MangaId>,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+    pub detail: JobDetail,
+}
 
-    async fn from_download_unread_chapters_job(
-        job: DownloadUnreadChaptersJob,
-    ) -> (Self, Option<RunningJob>) {
-        match job.poll().await {
-            JobState::InProgress(v) => (
-                JobDetail::Pending(serde_json::to_value(v).unwrap()),
-                Some(RunningJob::UnreadChapters(job)),
-            ),
-            JobState::Completed(_) => (JobDetail::Completed(().into()), None),
-            JobState::Errored(v) => (JobDetail::Error(serde_json::to_value(v).unwrap()), None),
+impl JobSummary {
+    pub async fn from_entry(id: Uuid, entry: &JobEntry) -> Self {
+        Self {
+            id,
+            kind: entry.job.kind(),
+            manga_id: entry.manga_id.clone(),
+            created_at: entry.created_at,
+            finished_at: entry.finished_at,
+            detail: entry.detail().await,
         }
     }
+}
 
-    async fn from_download_scanlator_chapters_job(
-        job: DownloadScanlatorChaptersJob,
-    ) -> (Self, Option<RunningJob>) {
-        match job.poll().await {
-            JobState::InProgress(v) => (
-                JobDetail::Pending(serde_json::to_value(v).unwrap()),
-                Some(RunningJob::ScanlatorChapters(job)),
-            ),
-            JobState::Completed(_) => (JobDetail::Completed(().into()), None),
-            JobState::Errored(v) => (JobDetail::Error(serde_json::to_value(v).unwrap()), None),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_without_output_complete_with_null() {
+        let detail = JobDetail::from_state(JobState::<(), (), ()>::Completed(()));
+
+        assert_eq!(
+            serde_json::to_value(detail).unwrap(),
+            serde_json::json!({ "type": "COMPLETED", "data": null })
+        );
     }
 }
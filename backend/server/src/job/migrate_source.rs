@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use futures::lock::Mutex;
+use serde::Serialize;
+use shared::{
+    chapter_storage::ChapterStorage,
+    database::Database,
+    model::{MangaId, SourceId},
+    source_manager::SourceManager,
+    usecases::{
+        self,
+        migrate_manga::{MigrationReport, MigrationTarget},
+    },
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{AppError, ErrorResponse};
+
+use super::state::{Job, JobState};
+
+#[derive(Default)]
+enum Status {
+    #[default]
+    Initializing,
+    Migrating {
+        migrated: usize,
+        total: usize,
+    },
+    Finished(Vec<MangaMigrationResult>),
+    Errored(ErrorResponse),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "type")]
+pub enum Progress {
+    Initializing,
+    Migrating { migrated: usize, total: usize },
+}
+
+#[derive(Clone, Serialize)]
+pub struct MangaMigrationResult {
+    pub manga_id: MangaId,
+    pub report: Option<MigrationReport>,
+    pub error: Option<String>,
+}
+
+/// Migrates every manga of the library from a source to the most similar manga found in the
+/// target sources. Mangas without a similar enough match are left where they are.
+pub struct MigrateSourceJob {
+    cancellation_token: CancellationToken,
+    status: Arc<Mutex<Status>>,
+}
+
+impl MigrateSourceJob {
+    pub fn spawn_new(
+        source_manager: Arc<tokio::sync::Mutex<SourceManager>>,
+        database: Arc<tokio::sync::Mutex<Database>>,
+        chapter_storage: ChapterStorage,
+        source_id: SourceId,
+        target_source_ids: Vec<SourceId>,
+        redownload: bool,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let cancellation_token_clone = cancellation_token.clone();
+
+        let status: Arc<Mutex<Status>> = Default::default();
+        let status_clone = status.clone();
+
+        tokio::spawn(async move {
+            let status = status_clone;
+            let cancellation_token = cancellation_token_clone;
+            let source_manager = source_manager.lock().await.clone();
+            let database = database.lock().await.clone();
+
+            let manga_ids: Vec<_> = match database.get_manga_library().await {
+                Ok(manga_ids) => manga_ids
+                    .into_iter()
+                    .filter(|manga_id| manga_id.source_id() == &source_id)
+                    .collect(),
+                Err(e) => {
+                    *status.lock().await = Status::Errored(AppError::from(e).into());
+                    return;
+                }
+            };
+
+            let total = manga_ids.len();
+            let mut results = Vec::with_capacity(total);
+            for manga_id in manga_ids {
+                if cancellation_token.is_cancelled() {
+                    break;
+                }
+
+                *status.lock().await = Status::Migrating {
+                    migrated: results.len(),
+                    total,
+                };
+
+                let result = usecases::migrate_manga(
+                    &source_manager,
+                    &database,
+                    &chapter_storage,
+                    cancellation_token.child_token(),
+                    manga_id.clone(),
+                    MigrationTarget::BestMatch(target_source_ids.clone()),
+                    redownload,
+                )
+                .await;
+
+                results.push(match result {
+                    Ok(report) => MangaMigrationResult {
+                        manga_id,
+                        report: Some(report),
+                        error: None,
+                    },
+                    Err(e) => MangaMigrationResult {
+                        manga_id,
+                        report: None,
+                        error: Some(format!("{e:#}")),
+                    },
+                });
+            }
+
+            *status.lock().await = Status::Finished(results);
+        });
+
+        Self {
+            cancellation_token,
+            status,
+        }
+    }
+}
+
+impl Job for MigrateSourceJob {
+    const KIND: &'static str = "migrate_source";
+
+    type Progress = Progress;
+    type Output = Vec<MangaMigrationResult>;
+    type Error = ErrorResponse;
+
+    async fn cancel(&self) -> Result<(), AppError> {
+        self.cancellation_token.cancel();
+
+        Ok(())
+    }
+
+    async fn poll(&self) -> JobState<Self::Progress, Self::Output, Self::Error> {
+        match &*self.status.lock().await {
+            Status::Initializing => JobState::InProgress(Progress::Initializing),
+            Status::Migrating { migrated, total } => JobState::InProgress(Progress::Migrating {
+                migrated: *migrated,
+                total: *total,
+            }),
+            Status::Finished(results) => JobState::Completed(results.clone()),
+            Status::Errored(error) => JobState::Errored(error.clone()),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use futures::lock::Mutex;
+use serde::Serialize;
+use shared::{chapter_storage::ChapterStorage, database::Database, source::Source, usecases};
+use tokio_util::sync::CancellationToken;
+
+use crate::{AppError, ErrorResponse};
+
+use super::state::{Job, JobState};
+
+#[derive(Default)]
+enum Status {
+    #[default]
+    Migrating,
+    Finished(usize),
+    Errored(ErrorResponse),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "type")]
+pub enum Progress {
+    Migrating,
+}
+
+/// Migrates the keys of the mangas and chapters of a source which was just updated (see
+/// `usecases::migrate_source_keys`). Completes with how many keys changed.
+pub struct MigrateSourceKeysJob {
+    cancellation_token: CancellationToken,
+    status: Arc<Mutex<Status>>,
+}
+
+impl MigrateSourceKeysJob {
+    pub fn spawn_new(
+        database: Arc<tokio::sync::Mutex<Database>>,
+        chapter_storage: ChapterStorage,
+        source: Source,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let cancellation_token_clone = cancellation_token.clone();
+
+        let status: Arc<Mutex<Status>> = Default::default();
+        let status_clone = status.clone();
+
+        tokio::spawn(async move {
+            let result = usecases::migrate_source_keys(
+                &database,
+                &chapter_storage,
+                &source,
+                cancellation_token_clone,
+            )
+            .await;
+
+            *status_clone.lock().await = match result {
+                Ok(migrated) => Status::Finished(migrated),
+                Err(e) => Status::Errored(AppError::from(e).into()),
+            };
+        });
+
+        Self {
+            cancellation_token,
+            status,
+        }
+    }
+}
+
+impl Job for MigrateSourceKeysJob {
+    const KIND: &'static str = "migrate_source_keys";
+
+    type Progress = Progress;
+    type Output = usize;
+    type Error = ErrorResponse;
+
+    async fn cancel(&self) -> Result<(), AppError> {
+        self.cancellation_token.cancel();
+
+        Ok(())
+    }
+
+    async fn poll(&self) -> JobState<Self::Progress, Self::Output, Self::Error> {
+        match &*self.status.lock().await {
+            Status::Migrating => JobState::InProgress(Progress::Migrating),
+            Status::Finished(migrated) => JobState::Completed(*migrated),
+            Status::Errored(error) => JobState::Errored(error.clone()),
+        }
+    }
+}
@@ -2,8 +2,11 @@ mod download_chapter;
 mod download_scanlator_chapters;
 mod download_unread_chapters;
 mod dto;
+mod migrate_source;
+mod migrate_source_keys;
 mod routes;
 mod state;
 
-pub use routes::routes;
+pub use migrate_source_keys::MigrateSourceKeysJob;
+pub use routes::{job_retention, routes};
 pub use state::State;
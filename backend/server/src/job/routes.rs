@@ -7,21 +7,22 @@ use axum::{
 };
 use serde::Deserialize;
 use shared::{
-    model::{ChapterId, MangaId},
+    model::{ChapterId, MangaId, SourceId},
+    settings::Settings,
     source_collection::SourceCollection,
     usecases::fetch_manga_chapters_in_batch::Filter as ChaptersToDownloadFilter,
 };
+use std::time::Duration;
 use uuid::Uuid;
 
-use crate::job::dto::JobDetail;
-use crate::job::state::RunningJob;
+use crate::job::dto::{JobDetail, JobSummary};
 use crate::state::State as AppState;
 
 use super::{
     download_chapter::DownloadChapterJob,
     download_scanlator_chapters::{DownloadScanlatorChaptersJob, ScanlatorFilter},
     download_unread_chapters::DownloadUnreadChaptersJob,
-    state::Job,
+    migrate_source::MigrateSourceJob,
 };
 
 pub fn routes() -> Router<AppState> {
@@ -35,6 +36,8 @@ pub fn routes() -> Router<AppState> {
             "/jobs/download-scanlator-chapters",
             post(create_download_scanlator_chapters_job),
         )
+        .route("/jobs/migrate-source", post(create_migrate_source_job))
+        .route("/jobs", get(list_jobs))
         .route("/jobs/{id}", get(get_job))
         .route("/jobs/{id}", delete(cancel_job))
 }
@@ -61,26 +64,27 @@ async fn create_download_chapter_job(
         chapter_storage,
         ..
     }): StateExtractor<AppState>,
-    StateExtractor(State { job_registry }): StateExtractor<State>,
+    StateExtractor(job_state): StateExtractor<State>,
     Json(body): Json<CreateDownloadChapterJobBody>,
 ) -> Result<Json<Uuid>, AppError> {
     let id = Uuid::new_v4();
     // let chapter_num = body.chapter_num;
+    let chapter_id = ChapterId::from(body);
+    let manga_id = chapter_id.manga_id().clone();
     let chapter_storage = chapter_storage.lock().await.clone();
     let settings = settings.lock().await;
     let job = DownloadChapterJob::spawn_new(
         source_manager,
         database,
         chapter_storage,
-        body.into(),
+        chapter_id,
         settings.concurrent_requests_pages.unwrap_or(4),
         settings.optimize_image,
     );
 
-    job_registry
-        .lock()
-        .await
-        .insert(id, RunningJob::Chapter(job));
+    job_state
+        .register(id, Some(manga_id), job, job_retention(&settings))
+        .await;
 
     Ok(Json(id))
 }
@@ -107,7 +111,7 @@ async fn create_download_unread_chapters_job(
         settings,
         ..
     }): StateExtractor<AppState>,
-    StateExtractor(State { job_registry }): StateExtractor<State>,
+    StateExtractor(job_state): StateExtractor<State>,
     Json(body): Json<CreateDownloadUnreadChaptersJobBody>,
 ) -> Result<Json<Uuid>, AppError> {
     let langs = body.langs.clone().unwrap_or_default();
@@ -131,17 +135,16 @@ async fn create_download_unread_chapters_job(
         source,
         database,
         chapter_storage,
-        manga_id,
+        manga_id.clone(),
         filter,
         langs,
         settings.concurrent_requests_pages.unwrap_or(4),
         settings.optimize_image,
     );
 
-    job_registry
-        .lock()
-        .await
-        .insert(id, RunningJob::UnreadChapters(job));
+    job_state
+        .register(id, Some(manga_id), job, job_retention(&settings))
+        .await;
 
     Ok(Json(id))
 }
@@ -169,7 +172,7 @@ async fn create_download_scanlator_chapters_job(
         settings,
         ..
     }): StateExtractor<AppState>,
-    StateExtractor(State { job_registry }): StateExtractor<State>,
+    StateExtractor(job_state): StateExtractor<State>,
     Json(body): Json<CreateDownloadScanlatorChaptersJobBody>,
 ) -> Result<Json<Uuid>, AppError> {
     let langs = body.langs.clone().unwrap_or_default();
@@ -193,17 +196,57 @@ async fn create_download_scanlator_chapters_job(
         source,
         database,
         chapter_storage,
-        manga_id,
+        manga_id.clone(),
         scanlator_filter,
         langs,
         settings.concurrent_requests_pages.unwrap_or(4),
         settings.optimize_image,
     );
 
-    job_registry
-        .lock()
-        .await
-        .insert(id, RunningJob::ScanlatorChapters(job));
+    job_state
+        .register(id, Some(manga_id), job, job_retention(&settings))
+        .await;
+
+    Ok(Json(id))
+}
+
+#[derive(Deserialize)]
+struct CreateMigrateSourceJobBody {
+    source_id: String,
+    target_source_ids: Vec<String>,
+    #[serde(default)]
+    redownload: bool,
+}
+
+async fn create_migrate_source_job(
+    StateExtractor(AppState {
+        source_manager,
+        database,
+        chapter_storage,
+        settings,
+        ..
+    }): StateExtractor<AppState>,
+    StateExtractor(job_state): StateExtractor<State>,
+    Json(body): Json<CreateMigrateSourceJobBody>,
+) -> Result<Json<Uuid>, AppError> {
+    let id = Uuid::new_v4();
+    let chapter_storage = chapter_storage.lock().await.clone();
+    let settings = settings.lock().await;
+    let job = MigrateSourceJob::spawn_new(
+        source_manager,
+        database,
+        chapter_storage,
+        SourceId::new(body.source_id),
+        body.target_source_ids
+            .into_iter()
+            .map(SourceId::new)
+            .collect(),
+        body.redownload,
+    );
+
+    job_state
+        .register(id, None, job, job_retention(&settings))
+        .await;
 
     Ok(Json(id))
 }
@@ -213,23 +256,30 @@ struct GetJobParams {
     id: Uuid,
 }
 
+async fn list_jobs(
+    StateExtractor(State { job_registry }): StateExtractor<State>,
+) -> Json<Vec<JobSummary>> {
+    let job_registry = job_registry.lock().await;
+
+    let mut jobs = Vec::with_capacity(job_registry.len());
+    for (id, entry) in job_registry.iter() {
+        jobs.push(JobSummary::from_entry(*id, entry).await);
+    }
+    jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+
+    Json(jobs)
+}
+
 async fn get_job(
     StateExtractor(State { job_registry }): StateExtractor<State>,
     Path(GetJobParams { id }): Path<GetJobParams>,
 ) -> Result<Json<JobDetail>, AppError> {
-    let job = job_registry
-        .lock()
-        .await
-        .remove(&id)
+    let job_registry = job_registry.lock().await;
+    let entry = job_registry
+        .get(&id)
         .ok_or_else(|| anyhow!("couldn't find job"))?;
 
-    let (detail, incomplete_job) = JobDetail::from_job(job).await;
-
-    if let Some(incomplete_job) = incomplete_job {
-        job_registry.lock().await.insert(id, incomplete_job);
-    }
-
-    Ok(Json(detail))
+    Ok(Json(entry.detail().await))
 }
 
 async fn cancel_job(
@@ -237,15 +287,15 @@ async fn cancel_job(
     Path(GetJobParams { id }): Path<GetJobParams>,
 ) -> Result<Json<()>, AppError> {
     let job_registry = job_registry.lock().await;
-    let job = job_registry
+    let entry = job_registry
         .get(&id)
         .ok_or_else(|| anyhow!("couldn't find job"))?;
 
-    match job {
-        RunningJob::Chapter(job) => job.cancel().await?,
-        RunningJob::UnreadChapters(job) => job.cancel().await?,
-        RunningJob::ScanlatorChapters(job) => job.cancel().await?,
-    };
+    entry.job.cancel().await?;
 
     Ok(Json(()))
 }
+
+pub fn job_retention(settings: &Settings) -> Duration {
+    Duration::from_secs(settings.job_history_retention_minutes * 60)
+}
@@ -1,15 +1,21 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use futures::lock::Mutex;
+use futures::{future::BoxFuture, lock::Mutex, FutureExt};
+use serde::Serialize;
+use shared::{
+    events::{self, Event},
+    model::MangaId,
+};
 use uuid::Uuid;
 
 use crate::AppError;
 
-use super::{
-    download_chapter::DownloadChapterJob,
-    download_scanlator_chapters::DownloadScanlatorChaptersJob,
-    download_unread_chapters::DownloadUnreadChaptersJob,
-};
+use super::dto::JobDetail;
 
 pub enum JobState<Progress, Output, Error> {
     InProgress(Progress),
@@ -17,22 +23,141 @@ pub enum JobState<Progress, Output, Error> {
     Errored(Error),
 }
 
-pub trait Job {
-    type Progress;
-    type Output;
-    type Error;
+pub trait Job: Send + Sync + 'static {
+    /// Identifies the kind of job in listings, e.g. `download_chapter`.
+    const KIND: &'static str;
+
+    type Progress: Serialize;
+    type Output: Serialize;
+    type Error: Serialize;
 
-    async fn cancel(&self) -> Result<(), AppError>;
-    async fn poll(&self) -> JobState<Self::Progress, Self::Output, Self::Error>;
+    fn cancel(&self) -> impl Future<Output = Result<(), AppError>> + Send;
+    fn poll(
+        &self,
+    ) -> impl Future<Output = JobState<Self::Progress, Self::Output, Self::Error>> + Send;
 }
 
-pub enum RunningJob {
-    Chapter(DownloadChapterJob),
-    UnreadChapters(DownloadUnreadChaptersJob),
-    ScanlatorChapters(DownloadScanlatorChaptersJob),
+/// Object-safe view of a `Job`, so that jobs of any kind can be kept in the same registry.
+/// Implemented for every `Job`.
+pub trait RunningJob: Send + Sync {
+    fn kind(&self) -> &'static str;
+    fn cancel(&self) -> BoxFuture<'_, Result<(), AppError>>;
+    fn detail(&self) -> BoxFuture<'_, JobDetail>;
+}
+
+impl<T: Job> RunningJob for T {
+    fn kind(&self) -> &'static str {
+        T::KIND
+    }
+
+    fn cancel(&self) -> BoxFuture<'_, Result<(), AppError>> {
+        Job::cancel(self).boxed()
+    }
+
+    fn detail(&self) -> BoxFuture<'_, JobDetail> {
+        async move { JobDetail::from_state(self.poll().await) }.boxed()
+    }
 }
 
+pub struct JobEntry {
+    pub job: Box<dyn RunningJob>,
+    /// The manga the job works on, if any.
+    pub manga_id: Option<MangaId>,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+    /// Final detail of the job, kept once it finishes.
+    finished_detail: Option<JobDetail>,
+}
+
+impl JobEntry {
+    pub async fn detail(&self) -> JobDetail {
+        match &self.finished_detail {
+            Some(detail) => detail.clone(),
+            None => self.job.detail().await,
+        }
+    }
+}
+
+pub type JobRegistry = Arc<Mutex<HashMap<Uuid, JobEntry>>>;
+
 #[derive(Default, Clone)]
 pub struct State {
-    pub job_registry: Arc<Mutex<HashMap<Uuid, RunningJob>>>,
+    pub job_registry: JobRegistry,
+}
+
+// How often running jobs are polled to publish their progress as events.
+const JOB_EVENTS_INTERVAL: Duration = Duration::from_millis(500);
+
+impl State {
+    /// Adds a job to the registry and starts publishing its progress as events. Once the
+    /// job finishes, it's kept in the registry for `retention` before being dropped.
+    pub async fn register(
+        &self,
+        id: Uuid,
+        manga_id: Option<MangaId>,
+        job: impl Job,
+        retention: Duration,
+    ) {
+        let entry = JobEntry {
+            job: Box::new(job),
+            manga_id,
+            created_at: unix_timestamp(),
+            finished_at: None,
+            finished_detail: None,
+        };
+        self.job_registry.lock().await.insert(id, entry);
+
+        tokio::spawn(watch_job(self.job_registry.clone(), id, retention));
+    }
+}
+
+async fn watch_job(job_registry: JobRegistry, id: Uuid, retention: Duration) {
+    let mut last_detail = None;
+
+    loop {
+        tokio::time::sleep(JOB_EVENTS_INTERVAL).await;
+
+        let mut job_registry_guard = job_registry.lock().await;
+        let Some(entry) = job_registry_guard.get_mut(&id) else {
+            return;
+        };
+
+        let detail = entry.job.detail().await;
+        let finished = !matches!(detail, JobDetail::Pending(_));
+        let Ok(detail_value) = serde_json::to_value(&detail) else {
+            return;
+        };
+
+        if finished {
+            entry.finished_at = Some(unix_timestamp());
+            entry.finished_detail = Some(detail);
+            drop(job_registry_guard);
+
+            events::publish(Event::JobFinished {
+                job_id: id.to_string(),
+                detail: detail_value,
+            });
+            break;
+        }
+
+        drop(job_registry_guard);
+
+        if last_detail.as_ref() != Some(&detail_value) {
+            events::publish(Event::JobProgress {
+                job_id: id.to_string(),
+                detail: detail_value.clone(),
+            });
+            last_detail = Some(detail_value);
+        }
+    }
+
+    tokio::time::sleep(retention).await;
+    job_registry.lock().await.remove(&id);
+}
+
+fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
 }
@@ -1,3 +1,6 @@
+mod connectivity;
+mod download_queue;
+mod events;
 mod job;
 mod manga;
 mod model;
@@ -5,6 +8,7 @@ mod settings;
 mod source;
 mod source_extractor;
 mod state;
+mod storage;
 mod update;
 
 use anyhow::Context;
@@ -27,12 +31,16 @@ use clap::Parser;
 use serde::Serialize;
 use shared::chapter_storage::ChapterStorage;
 use shared::database::Database;
-use shared::settings::Settings;
+use shared::eviction_policy::DatabaseEvictionPolicy;
 use shared::source_manager::SourceManager;
 use shared::usecases::{
     fetch_manga_chapter::Error as FetchMangaChaptersError,
     search_mangas::Error as SearchMangasError,
 };
+use shared::{
+    connectivity::connectivity, download_scheduler::download_scheduler, http_cache::http_cache,
+    settings::Settings, source::limits::source_limits,
+};
 use tokio::sync::Mutex;
 
 #[derive(Parser, Debug)]
@@ -68,12 +76,19 @@ async fn main() -> anyhow::Result<()> {
     let database_path = args.home_path.join("database.db");
     let default_downloads_folder_path = args.home_path.join("downloads");
     let settings_path = args.home_path.join("settings.json");
+    let http_cache_path = args.home_path.join("http-cache");
 
     let database = Database::new(&database_path)
         .await
         .context("couldn't open database file")?;
     let settings = Settings::from_file(&settings_path)
         .with_context(|| format!("couldn't read settings file at {}", settings_path.display()))?;
+    download_scheduler().configure(&settings.download_limits);
+    http_cache().set_folder(http_cache_path);
+    http_cache().configure(&settings.http_cache);
+    connectivity().set_forced_offline(settings.offline_mode);
+    connectivity().configure(&settings.connectivity);
+    source_limits().configure(&settings.source_limits);
     let source_manager = SourceManager::from_folder(sources_path, settings.clone())
         .context("couldn't create source manager")?;
 
@@ -83,7 +98,8 @@ async fn main() -> anyhow::Result<()> {
         .unwrap_or(default_downloads_folder_path);
 
     let chapter_storage = ChapterStorage::new(downloads_folder_path, settings.storage_size_limit.0)
-        .context("couldn't initialize chapter storage")?;
+        .context("couldn't initialize chapter storage")?
+        .with_eviction_policy(Arc::new(DatabaseEvictionPolicy::new(database.clone())));
 
     if settings.enabled_cron_check_mangas_update {
         let db_clone = database.clone();
@@ -113,12 +129,30 @@ async fn main() -> anyhow::Result<()> {
             .context("couldn't load sources")?;
     }
 
+    tokio::spawn(shared::download_queue::run_download_queue(
+        state.database.clone(),
+        state.chapter_storage.clone(),
+        state.source_manager.clone(),
+        state.settings.clone(),
+    ));
+
+    tokio::spawn(shared::pending_actions::run_pending_actions(
+        state.database.clone(),
+        state.chapter_storage.clone(),
+        state.source_manager.clone(),
+        state.settings.clone(),
+    ));
+
     let app = Router::new()
         .route("/health-check", get(health_check))
+        .merge(connectivity::routes())
+        .merge(download_queue::routes())
+        .merge(events::routes())
         .merge(manga::routes())
         .merge(job::routes())
         .merge(settings::routes())
         .merge(source::routes())
+        .merge(storage::routes())
         .merge(update::routes())
         .with_state(state);
 
@@ -174,6 +208,7 @@ impl AppError {
     fn from_search_mangas_error(value: SearchMangasError) -> Self {
         match value {
             SearchMangasError::SourceError(e) => Self::NetworkFailure(e),
+            SearchMangasError::DatabaseError(e) => Self::Other(e),
         }
     }
 
@@ -6,11 +6,19 @@ use axum::{Json, Router};
 use futures::Future;
 use log::warn;
 use serde::{Deserialize, Serialize};
-use shared::model::{ChapterId, MangaId, NotificationInformation};
-use shared::usecases;
+use shared::model::{
+    AutoDownloadMode, ChapterId, MangaId, NotificationGroup, NotificationInformation, SourceId,
+    SourceUpdateHealth, UpdateRun, UpdateRunTrigger,
+};
+use shared::update_policy::{self, DeviceConditions};
+use shared::usecases::{
+    self,
+    get_update_schedule::UpdateSchedule,
+    migrate_manga::{MigrationReport, MigrationTarget},
+};
 use tokio_util::sync::CancellationToken;
 
-use crate::model::{Chapter, Manga};
+use crate::model::{Chapter, Manga, MigrationCandidate};
 use crate::source_extractor::SourceExtractor;
 use crate::state::State;
 use crate::AppError;
@@ -22,9 +30,20 @@ pub fn routes() -> Router<State> {
         .route("/delete-file", post(delete_file))
         .route("/sync-database", post(sync_database))
         .route("/check-mangas-update", post(check_mangas_update))
+        .route("/update-runs", get(get_update_runs))
+        .route("/update-runs/{id}", get(get_update_run))
+        .route("/update-health", get(get_update_health))
+        .route("/update-schedule", get(get_update_schedule))
+        .route(
+            "/update-schedule/device-conditions",
+            post(set_device_conditions),
+        )
         .route("/count-notifications", get(get_count_notifications))
         .route("/notifications", get(get_notifications))
         .route("/notifications/{id}", delete(delete_notification))
+        .route("/notifications/{id}/read", post(mark_notification_as_read))
+        .route("/notifications/read-all", post(mark_notifications_as_read))
+        .route("/notification-groups", get(get_notification_groups))
         .route("/clear-notifications", post(clear_notifications))
         .route(
             "/{source_id}/handle-source-notification/{key}",
@@ -78,6 +97,14 @@ pub fn routes() -> Router<State> {
             "/mangas/{source_id}/{manga_id}/chapters/{chapter_id}/update-last-read",
             post(update_last_read),
         )
+        .route(
+            "/mangas/{source_id}/{manga_id}/chapters/{chapter_id}/prefetch",
+            post(prefetch_manga_chapters),
+        )
+        .route(
+            "/mangas/{source_id}/{manga_id}/prefetch",
+            delete(cancel_manga_prefetch),
+        )
         .route(
             "/mangas/{source_id}/{manga_id}/preferred-scanlator",
             get(get_manga_preferred_scanlator),
@@ -86,6 +113,34 @@ pub fn routes() -> Router<State> {
             "/mangas/{source_id}/{manga_id}/preferred-scanlator",
             post(set_manga_preferred_scanlator),
         )
+        .route(
+            "/mangas/{source_id}/{manga_id}/auto-download",
+            get(get_manga_auto_download),
+        )
+        .route(
+            "/mangas/{source_id}/{manga_id}/auto-download",
+            post(set_manga_auto_download),
+        )
+        .route(
+            "/mangas/{source_id}/{manga_id}/pinned",
+            get(get_manga_pinned),
+        )
+        .route(
+            "/mangas/{source_id}/{manga_id}/pinned",
+            post(set_manga_pinned),
+        )
+        .route(
+            "/mangas/{source_id}/{manga_id}/chapters/{chapter_id}/pinned",
+            post(set_chapter_pinned),
+        )
+        .route(
+            "/mangas/{source_id}/{manga_id}/migration-candidates",
+            get(get_migration_candidates),
+        )
+        .route(
+            "/mangas/{source_id}/{manga_id}/migrate",
+            post(migrate_manga),
+        )
 }
 
 async fn get_manga_library(
@@ -194,6 +249,7 @@ async fn check_mangas_update(
         database,
         chapter_storage,
         source_manager,
+        settings,
         cancel_token_store,
         ..
     }): StateExtractor<State>,
@@ -202,14 +258,91 @@ async fn check_mangas_update(
     let database = database.lock().await;
     let chapter_storage = chapter_storage.lock().await;
     let source_manager = source_manager.lock().await;
+    let settings = settings.lock().await.clone();
     let token = create_token(cancel_token_store, cancel_id).await;
 
-    let _ =
-        usecases::check_mangas_update(&token.0, &database, &chapter_storage, &source_manager).await;
+    let _ = usecases::check_mangas_update(
+        &token.0,
+        &database,
+        &chapter_storage,
+        &source_manager,
+        &settings,
+        UpdateRunTrigger::Manual,
+    )
+    .await;
 
     Ok(Json(()))
 }
 
+#[derive(Deserialize)]
+struct GetUpdateRunsQuery {
+    limit: Option<i64>,
+}
+
+async fn get_update_runs(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Query(GetUpdateRunsQuery { limit }): Query<GetUpdateRunsQuery>,
+) -> Result<Json<Vec<UpdateRun>>, AppError> {
+    let database = database.lock().await;
+
+    let runs = usecases::get_update_runs(&database, limit.unwrap_or(20)).await?;
+
+    Ok(Json(runs))
+}
+
+#[derive(Deserialize)]
+struct UpdateRunParams {
+    id: i64,
+}
+
+async fn get_update_run(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(UpdateRunParams { id }): Path<UpdateRunParams>,
+) -> Result<Json<UpdateRun>, AppError> {
+    let database = database.lock().await;
+
+    let run = usecases::get_update_run(&database, id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(run))
+}
+
+#[derive(Deserialize)]
+struct GetUpdateHealthQuery {
+    min_failures: Option<i64>,
+}
+
+async fn get_update_health(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Query(GetUpdateHealthQuery { min_failures }): Query<GetUpdateHealthQuery>,
+) -> Result<Json<Vec<SourceUpdateHealth>>, AppError> {
+    let database = database.lock().await;
+
+    let health = usecases::get_update_health(&database, min_failures.unwrap_or(3)).await?;
+
+    Ok(Json(health))
+}
+
+async fn get_update_schedule(
+    StateExtractor(State {
+        database, settings, ..
+    }): StateExtractor<State>,
+) -> Result<Json<UpdateSchedule>, AppError> {
+    let database = database.lock().await;
+    let settings = settings.lock().await.clone();
+
+    let schedule = usecases::get_update_schedule(&database, &settings).await?;
+
+    Ok(Json(schedule))
+}
+
+async fn set_device_conditions(Json(conditions): Json<DeviceConditions>) -> Json<()> {
+    update_policy::set_device_conditions(conditions);
+
+    Json(())
+}
+
 #[derive(Deserialize)]
 struct GetCleanerQuery {
     invalid: String,
@@ -345,21 +478,79 @@ async fn get_count_notifications(
     Ok(Json(count))
 }
 
+#[derive(Deserialize)]
+struct GetNotificationsQuery {
+    #[serde(default)]
+    include_read: bool,
+}
+
 async fn get_notifications(
     StateExtractor(State {
         database,
         chapter_storage,
         ..
     }): StateExtractor<State>,
+    Query(GetNotificationsQuery { include_read }): Query<GetNotificationsQuery>,
 ) -> Result<Json<Vec<NotificationInformation>>, AppError> {
     let database = database.lock().await;
     let chapter_storage = chapter_storage.lock().await;
 
-    let rows = usecases::get_notifications(&database, &chapter_storage).await?;
+    let rows = usecases::get_notifications(&database, &chapter_storage, include_read).await?;
 
     Ok(Json(rows))
 }
 
+async fn get_notification_groups(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        ..
+    }): StateExtractor<State>,
+) -> Result<Json<Vec<NotificationGroup>>, AppError> {
+    let database = database.lock().await;
+    let chapter_storage = chapter_storage.lock().await;
+
+    let groups = usecases::get_notification_groups(&database, &chapter_storage).await?;
+
+    Ok(Json(groups))
+}
+
+async fn mark_notification_as_read(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(params): Path<NotificationParams>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+
+    if !usecases::mark_notification_as_read(&database, params.id.into()).await? {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct MarkNotificationsAsReadQuery {
+    source_id: Option<String>,
+    manga_id: Option<String>,
+}
+
+async fn mark_notifications_as_read(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Query(MarkNotificationsAsReadQuery {
+        source_id,
+        manga_id,
+    }): Query<MarkNotificationsAsReadQuery>,
+) -> Result<Json<()>, AppError> {
+    let database = database.lock().await;
+    let manga_id = source_id
+        .zip(manga_id)
+        .map(|(source_id, manga_id)| MangaId::from_strings(source_id, manga_id));
+
+    usecases::mark_notifications_as_read(&database, manga_id).await?;
+
+    Ok(Json(()))
+}
+
 async fn delete_notification(
     StateExtractor(State { database, .. }): StateExtractor<State>,
     Path(params): Path<NotificationParams>,
@@ -572,6 +763,7 @@ async fn download_manga_chapter(
         &chapter_id,
         concurrent_requests_pages,
         settings.optimize_image,
+        None,
     )
     .await
     .map_err(AppError::from_fetch_manga_chapters_error)?;
@@ -601,7 +793,11 @@ struct MarkChapterAsReadBody {
     state: Option<bool>,
 }
 async fn mark_chapter_as_read(
-    StateExtractor(State { database, .. }): StateExtractor<State>,
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        ..
+    }): StateExtractor<State>,
     SourceExtractor(_source): SourceExtractor,
     Path(params): Path<DownloadMangaChapterParams>,
     Json(MarkChapterAsReadBody { state }): Json<MarkChapterAsReadBody>,
@@ -611,11 +807,19 @@ async fn mark_chapter_as_read(
 
     usecases::mark_chapter_as_read(&database, &chapter_id, state).await?;
 
+    if state.unwrap_or(true) {
+        chapter_storage.lock().await.clear_prefetched(&chapter_id)?;
+    }
+
     Ok(Json(()))
 }
 
 async fn update_last_read(
-    StateExtractor(State { database, .. }): StateExtractor<State>,
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        ..
+    }): StateExtractor<State>,
     SourceExtractor(_source): SourceExtractor,
     Path(params): Path<DownloadMangaChapterParams>,
 ) -> Result<Json<()>, AppError> {
@@ -624,6 +828,53 @@ async fn update_last_read(
 
     usecases::update_last_read_chapter(&database, &chapter_id).await?;
 
+    // Once opened, a prefetched chapter can be evicted like any other one
+    chapter_storage.lock().await.clear_prefetched(&chapter_id)?;
+
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct PrefetchMangaChaptersBody {
+    #[serde(default)]
+    languages: Vec<String>,
+}
+
+async fn prefetch_manga_chapters(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        settings,
+        ..
+    }): StateExtractor<State>,
+    SourceExtractor(source): SourceExtractor,
+    Path(params): Path<DownloadMangaChapterParams>,
+    Json(PrefetchMangaChaptersBody { languages }): Json<PrefetchMangaChaptersBody>,
+) -> Result<Json<()>, AppError> {
+    let chapter_id = ChapterId::from(params);
+    let settings = settings.lock().await.clone();
+
+    shared::prefetch::start_prefetch(
+        database,
+        chapter_storage,
+        source,
+        &settings,
+        &chapter_id,
+        languages,
+    )
+    .await?;
+
+    Ok(Json(()))
+}
+
+async fn cancel_manga_prefetch(
+    SourceExtractor(_source): SourceExtractor,
+    Path(params): Path<MangaChaptersPathParams>,
+) -> Result<Json<()>, AppError> {
+    let manga_id = MangaId::from(params);
+
+    shared::prefetch::cancel_prefetch(&manga_id);
+
     Ok(Json(()))
 }
 
@@ -660,6 +911,180 @@ async fn set_manga_preferred_scanlator(
     Ok(Json(()))
 }
 
+async fn get_manga_auto_download(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(_source): SourceExtractor,
+    Path(params): Path<MangaChaptersPathParams>,
+) -> Result<Json<AutoDownloadMode>, AppError> {
+    let manga_id = MangaId::from(params);
+    let database = database.lock().await;
+
+    let auto_download = usecases::get_manga_auto_download(&database, &manga_id).await?;
+
+    Ok(Json(auto_download))
+}
+
+async fn set_manga_auto_download(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(_source): SourceExtractor,
+    Path(params): Path<MangaChaptersPathParams>,
+    Json(auto_download): Json<AutoDownloadMode>,
+) -> Result<Json<()>, AppError> {
+    let manga_id = MangaId::from(params);
+    let database = database.lock().await;
+
+    usecases::set_manga_auto_download(&database, manga_id, auto_download).await?;
+
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct SetPinnedBody {
+    pinned: bool,
+}
+
+async fn get_manga_pinned(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(_source): SourceExtractor,
+    Path(params): Path<MangaChaptersPathParams>,
+) -> Result<Json<bool>, AppError> {
+    let manga_id = MangaId::from(params);
+    let database = database.lock().await;
+
+    let pinned = usecases::get_manga_pinned(&database, &manga_id).await?;
+
+    Ok(Json(pinned))
+}
+
+async fn set_manga_pinned(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(_source): SourceExtractor,
+    Path(params): Path<MangaChaptersPathParams>,
+    Json(SetPinnedBody { pinned }): Json<SetPinnedBody>,
+) -> Result<Json<()>, AppError> {
+    let manga_id = MangaId::from(params);
+    let database = database.lock().await;
+
+    usecases::set_manga_pinned(&database, &manga_id, pinned).await?;
+
+    Ok(Json(()))
+}
+
+async fn set_chapter_pinned(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(_source): SourceExtractor,
+    Path(params): Path<DownloadMangaChapterParams>,
+    Json(SetPinnedBody { pinned }): Json<SetPinnedBody>,
+) -> Result<Json<()>, AppError> {
+    let chapter_id = ChapterId::from(params);
+    let database = database.lock().await;
+
+    usecases::set_chapter_pinned(&database, &chapter_id, pinned).await?;
+
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct GetMigrationCandidatesQuery {
+    cancel_id: Option<usize>,
+    /// Comma-separated IDs of the sources to search.
+    source_ids: String,
+}
+
+// The source of the manga isn't required to be installed, as migrating is how mangas are moved
+// away from sources that were removed.
+async fn get_migration_candidates(
+    StateExtractor(State {
+        database,
+        source_manager,
+        cancel_token_store,
+        ..
+    }): StateExtractor<State>,
+    Path(params): Path<MangaChaptersPathParams>,
+    Query(GetMigrationCandidatesQuery {
+        cancel_id,
+        source_ids,
+    }): Query<GetMigrationCandidatesQuery>,
+) -> Result<Json<Vec<MigrationCandidate>>, AppError> {
+    let manga_id = MangaId::from(params);
+    let source_manager = { &*source_manager.lock().await };
+    let database = { database.lock().await };
+    let token = create_token(cancel_token_store, cancel_id).await;
+    let source_ids = parse_source_ids(&source_ids);
+
+    let candidates = cancel_after(&token.0, Duration::from_secs(59), |token| {
+        usecases::find_migration_candidates(
+            source_manager,
+            &database,
+            token,
+            &manga_id,
+            &source_ids,
+        )
+    })
+    .await?;
+
+    Ok(Json(candidates.into_iter().map(From::from).collect()))
+}
+
+#[derive(Deserialize)]
+struct MigrateMangaBody {
+    cancel_id: Option<usize>,
+    /// The manga to migrate to. When missing, the most similar manga from `source_ids` is
+    /// picked.
+    target_source_id: Option<String>,
+    target_manga_id: Option<String>,
+    #[serde(default)]
+    source_ids: Vec<String>,
+    #[serde(default)]
+    redownload: bool,
+}
+
+async fn migrate_manga(
+    StateExtractor(State {
+        database,
+        source_manager,
+        chapter_storage,
+        cancel_token_store,
+        ..
+    }): StateExtractor<State>,
+    Path(params): Path<MangaChaptersPathParams>,
+    Json(body): Json<MigrateMangaBody>,
+) -> Result<Json<MigrationReport>, AppError> {
+    let manga_id = MangaId::from(params);
+    let target = match (body.target_source_id, body.target_manga_id) {
+        (Some(source_id), Some(manga_id)) => {
+            MigrationTarget::Manga(MangaId::from_strings(source_id, manga_id))
+        }
+        _ => MigrationTarget::BestMatch(body.source_ids.into_iter().map(SourceId::new).collect()),
+    };
+    let source_manager = { &*source_manager.lock().await };
+    let chapter_storage = &*chapter_storage.lock().await;
+    let database = { database.lock().await };
+    let token = create_token(cancel_token_store, body.cancel_id).await;
+
+    let report = usecases::migrate_manga(
+        source_manager,
+        &database,
+        chapter_storage,
+        token.0.clone(),
+        manga_id,
+        target,
+        body.redownload,
+    )
+    .await?;
+
+    Ok(Json(report))
+}
+
+fn parse_source_ids(source_ids: &str) -> Vec<SourceId> {
+    source_ids
+        .split(',')
+        .map(str::trim)
+        .filter(|source_id| !source_id.is_empty())
+        .map(|source_id| SourceId::new(source_id.to_owned()))
+        .collect()
+}
+
 type CancelTokenStore =
     std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<usize, CancellationToken>>>;
 struct TokenGuard(CancellationToken, CancelTokenStore, Option<usize>);
@@ -1,7 +1,11 @@
 use serde::Serialize;
 
-use shared::model::{
-    Chapter as DomainChapter, Manga as DomainManga, SourceInformation as DomainSourceInformation,
+use shared::{
+    model::{
+        Chapter as DomainChapter, Manga as DomainManga,
+        SourceInformation as DomainSourceInformation,
+    },
+    usecases::migrate_manga::MigrationCandidate as DomainMigrationCandidate,
 };
 
 #[derive(Serialize)]
@@ -60,6 +64,7 @@ pub struct Chapter {
     read: bool,
     last_read: Option<i64>,
     downloaded: bool,
+    pinned: bool,
     locked: bool,
     lang: Option<String>,
 }
@@ -70,6 +75,7 @@ impl From<DomainChapter> for Chapter {
             information: chapter_information,
             state,
             downloaded,
+            pinned,
         }: DomainChapter,
     ) -> Self {
         Self {
@@ -84,8 +90,24 @@ impl From<DomainChapter> for Chapter {
             read: state.read,
             last_read: state.last_read,
             downloaded,
+            pinned,
             locked: chapter_information.locked.unwrap_or_default(),
             lang: chapter_information.lang,
         }
     }
 }
+
+#[derive(Serialize)]
+pub struct MigrationCandidate {
+    manga: Manga,
+    similarity: f32,
+}
+
+impl From<DomainMigrationCandidate> for MigrationCandidate {
+    fn from(value: DomainMigrationCandidate) -> Self {
+        Self {
+            manga: value.manga.into(),
+            similarity: value.similarity,
+        }
+    }
+}
@@ -4,11 +4,14 @@ use axum::extract::{Path, State as StateExtractor};
 use axum::routing::{delete, get, post};
 use axum::{Json, Router};
 use serde::Deserialize;
-use shared::model::SourceId;
+use shared::model::{SourceCrashes, SourceId, SourcePolicyReport};
 use shared::settings::SourceSettingValue;
 use shared::source::model::SettingDefinition;
+use shared::source::request_log::LoggedRequest;
 use shared::usecases;
+use uuid::Uuid;
 
+use crate::job::{self, MigrateSourceKeysJob};
 use crate::model::SourceInformation;
 use crate::source_extractor::{SourceExtractor, SourceParams};
 use crate::state::State;
@@ -22,6 +25,7 @@ pub fn routes() -> Router<State> {
             post(install_source),
         )
         .route("/installed-sources", get(list_installed_sources))
+        .route("/source-crashes", get(get_source_crashes))
         .route("/installed-sources/{source_id}", delete(uninstall_source))
         .route(
             "/installed-sources/{source_id}/setting-definitions",
@@ -35,6 +39,14 @@ pub fn routes() -> Router<State> {
             "/installed-sources/{source_id}/stored-settings",
             post(set_source_stored_settings),
         )
+        .route(
+            "/installed-sources/{source_id}/policy",
+            get(get_source_policy),
+        )
+        .route(
+            "/installed-sources/{source_id}/requests",
+            get(get_source_requests),
+        )
 }
 
 async fn list_available_sources(
@@ -57,14 +69,17 @@ struct InstallSourceParams {
 
 async fn install_source(
     StateExtractor(State {
+        database,
+        chapter_storage,
         source_manager,
         settings,
         ..
     }): StateExtractor<State>,
+    StateExtractor(job_state): StateExtractor<job::State>,
     Path(InstallSourceParams { source_id }): Path<InstallSourceParams>,
     Json(source_of_source): Json<String>,
 ) -> Result<Json<()>, AppError> {
-    usecases::install_source(
+    let updated_source = usecases::install_source(
         &mut *source_manager.lock().await,
         &source_manager,
         &settings.lock().await.source_lists,
@@ -73,6 +88,22 @@ async fn install_source(
     )
     .await?;
 
+    // Sources may save their settings while migrating, which needs the source manager, so it
+    // must not be locked anymore
+    if let Some(source) = updated_source {
+        let chapter_storage = chapter_storage.lock().await.clone();
+        let job = MigrateSourceKeysJob::spawn_new(database, chapter_storage, source);
+
+        job_state
+            .register(
+                Uuid::new_v4(),
+                None,
+                job,
+                job::job_retention(&*settings.lock().await),
+            )
+            .await;
+    }
+
     Ok(Json(()))
 }
 
@@ -87,6 +118,12 @@ async fn list_installed_sources(
     Json(installed_sources)
 }
 
+async fn get_source_crashes(
+    StateExtractor(State { source_manager, .. }): StateExtractor<State>,
+) -> Json<Vec<SourceCrashes>> {
+    Json(usecases::get_source_crashes(&*source_manager.lock().await))
+}
+
 async fn uninstall_source(
     StateExtractor(State { source_manager, .. }): StateExtractor<State>,
     Path(SourceParams { source_id }): Path<SourceParams>,
@@ -102,6 +139,14 @@ async fn get_source_setting_definitions(
     Json(usecases::get_source_setting_definitions(&source))
 }
 
+async fn get_source_policy(SourceExtractor(source): SourceExtractor) -> Json<SourcePolicyReport> {
+    Json(usecases::get_source_policy(&source))
+}
+
+async fn get_source_requests(SourceExtractor(source): SourceExtractor) -> Json<Vec<LoggedRequest>> {
+    Json(usecases::get_source_requests(&source))
+}
+
 async fn get_source_stored_settings(
     StateExtractor(State { settings, .. }): StateExtractor<State>,
     Path(SourceParams { source_id }): Path<SourceParams>,
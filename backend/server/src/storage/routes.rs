@@ -0,0 +1,43 @@
+use axum::extract::State as StateExtractor;
+use axum::routing::get;
+use axum::{Json, Router};
+use shared::usecases::{self, get_pinned::Pinned, get_storage_report::StorageReport};
+
+use crate::state::State;
+use crate::AppError;
+
+pub fn routes() -> Router<State> {
+    Router::new()
+        .route("/storage", get(get_storage_report))
+        .route("/pinned", get(get_pinned))
+}
+
+async fn get_storage_report(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        ..
+    }): StateExtractor<State>,
+) -> Result<Json<StorageReport>, AppError> {
+    let database = database.lock().await;
+    let chapter_storage = chapter_storage.lock().await;
+
+    let report = usecases::get_storage_report(&database, &chapter_storage).await?;
+
+    Ok(Json(report))
+}
+
+async fn get_pinned(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        ..
+    }): StateExtractor<State>,
+) -> Result<Json<Pinned>, AppError> {
+    let database = database.lock().await;
+    let chapter_storage = chapter_storage.lock().await;
+
+    let pinned = usecases::get_pinned(&database, &chapter_storage).await?;
+
+    Ok(Json(pinned))
+}
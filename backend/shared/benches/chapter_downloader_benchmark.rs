@@ -33,7 +33,7 @@ pub fn chapter_downloader_benchmark(c: &mut Criterion) {
     let source = Source::from_aix_file(source_path.as_ref(), &manager, &arc_manager).unwrap();
     let pages = executor::block_on(source.get_page_list(
         CancellationToken::new(),
-        manga_id,
+        manga_id.clone(),
         chapter_id,
         Some(0.0),
     ))
@@ -48,8 +48,11 @@ pub fn chapter_downloader_benchmark(c: &mut Criterion) {
                 io::Cursor::new(Vec::new()),
                 metadata.clone(),
                 &source,
+                &manga_id,
                 pages.clone(),
                 4,
+                false,
+                None,
             )
             .await;
         })
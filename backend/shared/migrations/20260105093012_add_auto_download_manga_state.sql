@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE manga_state
+ADD COLUMN auto_download_mode INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE manga_state
+ADD COLUMN auto_download_languages TEXT NULL; -- JSON array of strings
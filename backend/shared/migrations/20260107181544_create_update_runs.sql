@@ -0,0 +1,20 @@
+-- Add migration script here
+CREATE TABLE update_runs (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    trigger         TEXT NOT NULL,
+    started_at      INTEGER NOT NULL,
+    finished_at     INTEGER NULL
+);
+
+CREATE TABLE update_run_results (
+    run_id          INTEGER NOT NULL REFERENCES update_runs(id) ON DELETE CASCADE,
+    source_id       TEXT NOT NULL,
+    manga_id        TEXT NOT NULL,
+    new_chapters    INTEGER NOT NULL,
+    error           TEXT NULL,
+    duration_ms     INTEGER NOT NULL,
+    checked_at      INTEGER NOT NULL
+);
+
+CREATE INDEX update_run_results_manga ON update_run_results (source_id, manga_id, checked_at);
+CREATE INDEX update_run_results_run ON update_run_results (run_id);
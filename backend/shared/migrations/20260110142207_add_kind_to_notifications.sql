@@ -0,0 +1,20 @@
+-- Add migration script here
+CREATE TABLE notifications_new (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind            TEXT NOT NULL DEFAULT 'new_chapter',
+    source_id       TEXT NULL,
+    manga_id        TEXT NULL,
+    chapter_id      TEXT NULL,
+    message         TEXT NULL,
+    created_at      INTEGER NOT NULL,
+    is_read         INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO notifications_new (id, source_id, manga_id, chapter_id, created_at, is_read)
+SELECT id, source_id, manga_id, chapter_id, created_at, is_read FROM notifications;
+
+DROP TABLE notifications;
+
+ALTER TABLE notifications_new RENAME TO notifications;
+
+CREATE INDEX notifications_source_manga ON notifications (source_id, manga_id);
@@ -0,0 +1,23 @@
+-- Add migration script here
+CREATE TABLE download_queue (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_id       TEXT NOT NULL,
+    manga_id        TEXT NOT NULL,
+    chapter_id      TEXT NOT NULL,
+    priority        INTEGER NOT NULL DEFAULT 0,
+    status          TEXT NOT NULL DEFAULT 'queued',
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    last_error      TEXT NULL,
+    created_at      INTEGER NOT NULL,
+    updated_at      INTEGER NOT NULL,
+    UNIQUE (source_id, manga_id, chapter_id)
+) STRICT;
+
+CREATE INDEX download_queue_next ON download_queue (status, priority DESC, created_at);
+
+CREATE TABLE download_queue_state (
+    id              INTEGER PRIMARY KEY CHECK (id = 0),
+    paused          INTEGER NOT NULL DEFAULT 0
+) STRICT;
+
+INSERT INTO download_queue_state (id, paused) VALUES (0, 0);
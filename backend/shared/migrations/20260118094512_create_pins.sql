@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE pinned_mangas (
+    source_id       TEXT NOT NULL,
+    manga_id        TEXT NOT NULL,
+    pinned_at       INTEGER NOT NULL,
+    PRIMARY KEY (source_id, manga_id)
+) STRICT;
+
+CREATE TABLE pinned_chapters (
+    source_id       TEXT NOT NULL,
+    manga_id        TEXT NOT NULL,
+    chapter_id      TEXT NOT NULL,
+    pinned_at       INTEGER NOT NULL,
+    PRIMARY KEY (source_id, manga_id, chapter_id)
+) STRICT;
@@ -0,0 +1,5 @@
+-- Add migration script here
+CREATE TABLE pending_actions (
+    action          TEXT NOT NULL PRIMARY KEY,
+    queued_at       INTEGER NOT NULL
+) STRICT;
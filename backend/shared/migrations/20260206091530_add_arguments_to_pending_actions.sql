@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE pending_actions ADD COLUMN arguments TEXT NULL;
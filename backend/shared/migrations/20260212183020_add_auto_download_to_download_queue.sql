@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE download_queue ADD COLUMN auto_download INTEGER NOT NULL DEFAULT 0;
@@ -1,9 +1,11 @@
 use dom_query::Document;
 use futures::{stream, StreamExt, TryStreamExt};
-use reqwest::{redirect::Policy, Client};
+use reqwest::Client;
 use std::{
     io::{Cursor, Seek, Write},
     path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
 };
 use tempfile::NamedTempFile;
 use tokio_util::sync::CancellationToken;
@@ -17,8 +19,13 @@ use epub_builder::{EpubBuilder, EpubContent, ReferenceType, ZipLibrary};
 use crate::{
     cbz_metadata::ComicInfo,
     chapter_storage::ChapterStorage,
+    download_scheduler::download_scheduler,
     model::{ChapterInformation, MangaInformation},
-    source::{model::Page, Source},
+    source::{
+        model::Page,
+        request_observer::{self, ObservedRequest, RequestOperation},
+        Source,
+    },
     unscrable_image::{unscrable_image, Block},
     util::{
         create_xhtml, download_all_images, generate_error_image, get_image_src, into_html,
@@ -34,6 +41,54 @@ pub struct DownloadError {
     pub attempts: usize,
 }
 
+/// Progress of a single chapter download, reported every time a page finishes.
+/// For novels, every image embedded in the chapter counts as a page.
+#[derive(serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct DownloadProgress {
+    pub pages_done: usize,
+    pub pages_total: usize,
+    pub pages_failed: usize,
+    pub bytes_downloaded: u64,
+    /// Average download speed since the chapter started downloading.
+    pub bytes_per_second: u64,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(DownloadProgress) + Send + Sync>;
+
+/// Builds up the `DownloadProgress` of a chapter as its pages finish downloading.
+pub(crate) struct ProgressTracker {
+    started_at: Instant,
+    progress: DownloadProgress,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(pages_total: usize) -> Self {
+        Self {
+            started_at: Instant::now(),
+            progress: DownloadProgress {
+                pages_total,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub(crate) fn page_done(&mut self, bytes: usize, failed: bool) -> DownloadProgress {
+        self.progress.pages_done += 1;
+        if failed {
+            self.progress.pages_failed += 1;
+        }
+        self.progress.bytes_downloaded += bytes as u64;
+
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.progress.bytes_per_second =
+                (self.progress.bytes_downloaded as f64 / elapsed) as u64;
+        }
+
+        self.progress
+    }
+}
+
 pub async fn ensure_chapter_is_in_storage(
     token: &CancellationToken,
     chapter_storage: &ChapterStorage,
@@ -42,7 +97,7 @@ pub async fn ensure_chapter_is_in_storage(
     chapter: &ChapterInformation,
     concurrent_requests_pages: usize,
     optimize_image: bool,
-    #[cfg(not(feature = "all"))] on_progress: fn(f32, f32),
+    on_progress: Option<ProgressCallback>,
 ) -> Result<(PathBuf, Vec<DownloadError>), Error> {
     if let Some(output) = chapter_storage.get_stored_chapter_and_errors(&chapter.id)? {
         return Ok((
@@ -96,7 +151,6 @@ pub async fn ensure_chapter_is_in_storage(
             source,
             pages,
             chapter,
-            #[cfg(not(feature = "all"))]
             on_progress,
         )
         .await
@@ -110,10 +164,10 @@ pub async fn ensure_chapter_is_in_storage(
             &temporary_file,
             metadata,
             source,
+            chapter.id.manga_id().value(),
             pages,
             concurrent_requests_pages,
             optimize_image,
-            #[cfg(not(feature = "all"))]
             on_progress,
         )
         .await
@@ -154,18 +208,16 @@ pub async fn download_chapter_pages_as_cbz<W>(
     output: W,
     metadata: ComicInfo,
     source: &Source,
+    manga_id: &str,
     pages: Vec<Page>,
     concurrent_requests_pages: usize,
     optimize_image: bool,
-    #[cfg(not(feature = "all"))] on_progress: fn(f32, f32),
+    on_progress: Option<ProgressCallback>,
 ) -> anyhow::Result<Vec<DownloadError>, anyhow::Error>
 where
     W: Write + Seek,
 {
-    #[cfg(not(feature = "all"))]
-    let total = pages.len() as f32;
-    #[cfg(not(feature = "all"))]
-    let mut processed = 0f32;
+    let mut progress_tracker = ProgressTracker::new(pages.len());
 
     let mut writer = ZipWriter::new(output);
     let file_options: zip::write::FileOptions<'_, ()> =
@@ -175,13 +227,14 @@ where
     writer.start_file("ComicInfo.xml", file_options)?;
     writer.write_all(metadata.to_xml()?.as_bytes())?;
 
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .danger_accept_invalid_hostnames(true)
-        .redirect(Policy::none())
-        .build()?;
+    // Pages are downloaded through the shared scheduler, which enforces the global, per-source
+    // and per-host limits on top of `concurrent_requests_pages`.
+    let client = download_scheduler().client();
+    let source_id = source.manifest().info.id;
+    let source_rate_limit = source.declared_rate_limit().await?;
 
-    let (tx, mut rx) = mpsc::channel::<(usize, String, Vec<u8>, Option<DownloadError>)>(
+    // (page index, filename, image data, bytes downloaded, error)
+    let (tx, mut rx) = mpsc::channel::<(usize, String, Vec<u8>, usize, Option<DownloadError>)>(
         concurrent_requests_pages * 2,
     );
 
@@ -189,6 +242,7 @@ where
     tokio::spawn({
         let client = client.clone();
         let source = source.clone();
+        let manga_id = manga_id.to_owned();
         let cancel_token = cancel_token.clone();
 
         async move {
@@ -197,6 +251,8 @@ where
                     let tx = tx.clone();
                     let client = client.clone();
                     let source = source.clone();
+                    let source_id = source_id.clone();
+                    let manga_id = manga_id.clone();
                     let cancel_token = cancel_token.clone();
 
                     async move {
@@ -221,16 +277,38 @@ where
                                 eprintln!("Failed WASM modify request {err}");
                                 err
                             })?;
+                        let req_method = request.method().clone();
                         let req_url = request.url().clone();
                         let req_headers = request.headers().clone();
+                        let permit = tokio::select! {
+                            permit = download_scheduler()
+                                .acquire(&source_id, &req_url, source_rate_limit) => permit,
+                            _ = cancel_token.cancelled() => anyhow::bail!("download cancelled"),
+                        };
+                        let started_at = Instant::now();
                         let response =
-                            request_with_forced_referer_from_request(&client, request, 10)
-                                .await
-                                .inspect_err(|err| {
-                                    eprintln!("Request error: {err}");
-                                })?;
-
-                        let (final_bytes, error_info) = {
+                            request_with_forced_referer_from_request(&client, request, 10).await;
+                        request_observer::notify(ObservedRequest {
+                            source_id: source_id.clone(),
+                            method: Some(req_method),
+                            url: Some(req_url.clone()),
+                            status: response.as_ref().ok().map(|response| response.status()),
+                            size: response
+                                .as_ref()
+                                .ok()
+                                .and_then(|response| response.content_length())
+                                .map(|size| size as usize),
+                            elapsed: started_at.elapsed(),
+                            error: response.as_ref().err().map(|error| format!("{error:#}")),
+                            operation: RequestOperation::PageImage,
+                            manga_id: Some(manga_id),
+                            chapter_id: Some(page.chapter_id.clone()),
+                        });
+                        let response = response.inspect_err(|err| {
+                            eprintln!("Request error: {err}");
+                        })?;
+
+                        let (final_bytes, downloaded_bytes, error_info) = {
                             if !response.status().is_success() {
                                 let err = DownloadError {
                                     page_index: page.index,
@@ -251,6 +329,7 @@ where
                                         500,
                                         667,
                                     )?,
+                                    0,
                                     Some(err),
                                 )
                             } else {
@@ -258,6 +337,9 @@ where
                                 let headers = response.headers().clone();
 
                                 let response_bytes = response.bytes().await?;
+                                let downloaded_bytes = response_bytes.len();
+                                download_scheduler().consume_bandwidth(downloaded_bytes).await;
+                                drop(permit);
 
                                 let response_bytes = if source.1.process_page_image {
                                     source
@@ -340,13 +422,19 @@ where
                                     response_bytes.to_vec()
                                 };
 
-                                (final_image, None)
+                                (final_image, downloaded_bytes, None)
                             }
                         };
 
                         // Send result
                         let _ = tx
-                            .send((page.index, filename, final_bytes, error_info))
+                            .send((
+                                page.index,
+                                filename,
+                                final_bytes,
+                                downloaded_bytes,
+                                error_info,
+                            ))
                             .await;
 
                         Ok::<_, anyhow::Error>(())
@@ -365,18 +453,18 @@ where
     let mut errors = Vec::<DownloadError>::new();
 
     // Writer task
-    while let Some((_index, filename, data, err)) = rx.recv().await {
+    while let Some((_index, filename, data, downloaded_bytes, err)) = rx.recv().await {
+        let progress = progress_tracker.page_done(downloaded_bytes, err.is_some());
         if let Some(e) = err {
             errors.push(e);
         }
 
         writer.start_file(filename, file_options)?;
-        #[cfg(not(feature = "all"))]
-        {
-            processed += 1.0;
-            on_progress(processed, total);
-        }
         writer.write_all(&data)?;
+
+        if let Some(on_progress) = &on_progress {
+            on_progress(progress);
+        }
     }
 
     Ok(errors)
@@ -389,20 +477,11 @@ pub async fn download_chapter_novel_as_epub<W>(
     source: &Source,
     pages: Vec<Page>,
     chapter: &ChapterInformation,
-    #[cfg(not(feature = "all"))] on_progress: fn(f32, f32),
+    on_progress: Option<ProgressCallback>,
 ) -> anyhow::Result<()>
 where
     W: Write + Seek,
 {
-    #[cfg(not(feature = "all"))]
-    let total = pages.len() as f32;
-    #[cfg(not(feature = "all"))]
-    let stored_process_images = std::sync::Arc::new(std::sync::Mutex::new(
-        std::collections::HashMap::<usize, f32>::new(),
-    ));
-    #[cfg(not(feature = "all"))]
-    let stored_process_images_clone = stored_process_images.clone();
-
     let client = Client::builder().build()?;
 
     let cover_url = chapter.thumbnail.clone();
@@ -432,14 +511,9 @@ where
         pages.clone(),
         source,
         token,
-        #[cfg(not(feature = "all"))]
-        move |idx, done, total| {
-            let percent = done / total;
-            let progress = percent * 0.8;
-
-            if let Ok(mut stored) = stored_process_images.lock() {
-                stored.insert(idx, progress);
-                on_progress(stored.values().copied().sum(), total);
+        move |progress| {
+            if let Some(on_progress) = &on_progress {
+                on_progress(progress);
             }
         },
     )
@@ -493,12 +567,6 @@ where
                 };
                 index_image += 1;
 
-                #[cfg(not(feature = "all"))]
-                if let Ok(mut stored) = stored_process_images_clone.lock() {
-                    stored.insert(idx, 1.0);
-                    on_progress(stored.values().copied().sum(), total);
-                }
-
                 epub.add_content(
                     EpubContent::new(
                         format!("pages/page_{}.xhtml", idx + 1),
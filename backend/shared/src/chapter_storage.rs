@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::os::unix::fs::MetadataExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use std::{fs, future::Future};
 
 use anyhow::{anyhow, Context, Result};
 use base64::{engine::general_purpose, Engine as _};
-use log::debug;
+use futures::future::BoxFuture;
+use log::{debug, warn};
 use sha2::{Digest, Sha256};
 use size::Size;
 use tempfile::NamedTempFile;
@@ -18,11 +22,54 @@ use reqwest::Request;
 use crate::model::ChapterId;
 
 const CHAPTER_FILE_EXTENSION: [&str; 2] = ["cbz", "epub"];
+const SIDECAR_FILE_EXTENSION: [&str; 2] = ["errors", "prefetched"];
+const POSTERS_FOLDER: &str = ".posters";
+
+/// Decides which files can be evicted when the storage is full, and in which order.
+pub trait EvictionPolicy: Send + Sync {
+    /// Returns the stored chapters and posters that may be evicted, the first ones being
+    /// evicted first. Anything left out of the list is kept.
+    fn eviction_order<'a>(
+        &'a self,
+        chapter_storage: &'a ChapterStorage,
+    ) -> BoxFuture<'a, Result<Vec<PathBuf>>>;
+}
+
+/// A chapter file found in the storage.
+#[derive(Clone, Debug)]
+pub struct StoredChapterFile {
+    pub path: PathBuf,
+    /// The chapter stored in the file, if it's one of the chapters we were asked about.
+    pub chapter_id: Option<ChapterId>,
+    pub size: u64,
+    pub modified: SystemTime,
+    pub prefetched: bool,
+}
+
+/// A cached poster found in the storage.
+#[derive(Clone, Debug)]
+pub struct StoredPosterFile {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// A chapter didn't fit in the storage, and evicting other files to make room for it wasn't
+/// allowed (see `ChapterStorage::without_eviction`).
+#[derive(thiserror::Error, Debug)]
+#[error("not enough space left in the storage for a chapter of {size}")]
+pub struct StorageFullError {
+    pub size: Size,
+}
 
 #[derive(Clone)]
 pub struct ChapterStorage {
     downloads_folder_path: PathBuf,
     storage_size_limit: Size,
+    eviction_policy: Option<Arc<dyn EvictionPolicy>>,
+    evict: bool,
+    // Size of the files managed by the storage, shared between all clones. It's kept up to
+    // date by the storage itself, and computed again when unknown.
+    cached_size: Arc<Mutex<Option<u64>>>,
 }
 
 impl ChapterStorage {
@@ -33,9 +80,32 @@ impl ChapterStorage {
         Ok(Self {
             downloads_folder_path,
             storage_size_limit,
+            eviction_policy: None,
+            evict: true,
+            cached_size: Default::default(),
         })
     }
 
+    /// Uses `eviction_policy` to make room for new chapters. Without one, the least recently
+    /// modified chapters are evicted first.
+    pub fn with_eviction_policy(mut self, eviction_policy: Arc<dyn EvictionPolicy>) -> Self {
+        self.eviction_policy = Some(eviction_policy);
+
+        self
+    }
+
+    /// Never evicts anything to make room for new chapters: persisting a chapter which doesn't
+    /// fit fails with `StorageFullError` instead.
+    pub fn without_eviction(mut self) -> Self {
+        self.evict = false;
+
+        self
+    }
+
+    pub fn storage_size_limit(&self) -> Size {
+        self.storage_size_limit
+    }
+
     pub fn collect_all_files(&self, depth: usize) -> std::collections::HashSet<PathBuf> {
         WalkDir::new(&self.downloads_folder_path)
             .max_depth(depth)
@@ -55,15 +125,21 @@ impl ChapterStorage {
 
     pub async fn delete_filename(&self, filename: String) -> std::io::Result<()> {
         let file_path = self.downloads_folder_path.join(filename);
-        tokio::fs::remove_file(file_path).await
+        self.remove_file(&file_path).await
     }
 
-    pub async fn cache_poster(&self, url: &url::Url) -> Result<Option<PathBuf>> {
+    /// Name of the cached poster for `url`, without its extension.
+    pub fn poster_file_stem(url: &url::Url) -> String {
         let mut hasher = Sha256::new();
         hasher.update(url.as_str().as_bytes());
-        let encoded_hash = URL_SAFE_NO_PAD.encode(hasher.finalize());
 
-        let poster_dir = self.downloads_folder_path.join(".posters");
+        URL_SAFE_NO_PAD.encode(hasher.finalize())
+    }
+
+    pub async fn cache_poster(&self, url: &url::Url) -> Result<Option<PathBuf>> {
+        let encoded_hash = Self::poster_file_stem(url);
+
+        let poster_dir = self.downloads_folder_path.join(POSTERS_FOLDER);
 
         let meta_path = poster_dir.join(format!(".{encoded_hash}"));
 
@@ -82,6 +158,25 @@ impl ChapterStorage {
         Ok(None)
     }
 
+    /// Returns the poster downloaded for `url`, if any, without downloading it.
+    pub async fn stored_poster(&self, url: &url::Url) -> Result<Option<PathBuf>> {
+        let encoded_hash = Self::poster_file_stem(url);
+        let poster_dir = self.downloads_folder_path.join(POSTERS_FOLDER);
+        let meta_path = poster_dir.join(format!(".{encoded_hash}"));
+
+        if !meta_path.exists() {
+            return Ok(None);
+        }
+
+        let mut f = tokio::fs::File::open(&meta_path).await?;
+        let mut ext = String::new();
+        f.read_to_string(&mut ext).await?;
+
+        let cached_path = poster_dir.join(format!("{encoded_hash}.{}", ext));
+
+        Ok(cached_path.exists().then_some(cached_path))
+    }
+
     pub async fn cached_poster<F, Fut>(
         &self,
         token: &CancellationToken,
@@ -93,12 +188,10 @@ impl ChapterStorage {
         Fut: Future<Output = Result<Request>>,
     {
         // --- Hash URL for stable filename ---
-        let mut hasher = Sha256::new();
-        hasher.update(url.as_str().as_bytes());
-        let encoded_hash = URL_SAFE_NO_PAD.encode(hasher.finalize());
+        let encoded_hash = Self::poster_file_stem(url);
 
         // --- Directory for posters ---
-        let poster_dir = self.downloads_folder_path.join(".posters");
+        let poster_dir = self.downloads_folder_path.join(POSTERS_FOLDER);
         tokio::fs::create_dir_all(&poster_dir).await?;
 
         // --- Sidecar: stores only extension ---
@@ -107,16 +200,8 @@ impl ChapterStorage {
         // ============================================================
         // FAST PATH → Use existing cache without doing HTTP requests
         // ============================================================
-        if meta_path.exists() {
-            let mut f = tokio::fs::File::open(&meta_path).await?;
-            let mut ext = String::new();
-            f.read_to_string(&mut ext).await?;
-
-            let cached_path = poster_dir.join(format!("{encoded_hash}.{}", ext));
-
-            if cached_path.exists() {
-                return Ok(cached_path);
-            }
+        if let Some(cached_path) = self.stored_poster(url).await? {
+            return Ok(cached_path);
         }
 
         // ============================================================
@@ -145,7 +230,9 @@ impl ChapterStorage {
         tokio::fs::write(&poster_path, &bytes).await?;
 
         // --- Save metadata sidecar (.hash → ext) ---
-        tokio::fs::write(&meta_path, ext).await?;
+        tokio::fs::write(&meta_path, &ext).await?;
+
+        self.adjust_cached_size((bytes.len() + ext.len()) as u64, 0);
 
         Ok(poster_path)
     }
@@ -201,6 +288,16 @@ impl ChapterStorage {
         }
     }
 
+    /// Every path the chapter may be stored at, in the current and in the legacy format.
+    pub fn chapter_paths(&self, id: &ChapterId) -> [PathBuf; 4] {
+        [
+            self.path_for_chapter(id, false),
+            self.path_for_chapter(id, true),
+            self.path_for_chapter_legacy(id, false),
+            self.path_for_chapter_legacy(id, true),
+        ]
+    }
+
     pub fn get_path_to_store_chapter(&self, id: &ChapterId, is_novel: bool) -> PathBuf {
         // New chapters should always use the new path format
         self.path_for_chapter(id, is_novel)
@@ -222,6 +319,136 @@ impl ChapterStorage {
         Ok(parent.join(meta_name))
     }
 
+    /// Marks a stored chapter as prefetched, which keeps it from being evicted until the
+    /// mark is cleared (usually when the chapter is read).
+    pub fn mark_prefetched(&self, id: &ChapterId) -> Result<()> {
+        if let Some(path) = self.get_stored_chapter(id) {
+            fs::write(self.prefetched_marker_path(&path)?, [])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn clear_prefetched(&self, id: &ChapterId) -> Result<()> {
+        let Some(path) = self.get_stored_chapter(id) else {
+            return Ok(());
+        };
+
+        match fs::remove_file(self.prefetched_marker_path(&path)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn prefetched_marker_path(&self, path: &Path) -> Result<PathBuf> {
+        Ok(self.errors_source_path(path)?.with_extension("prefetched"))
+    }
+
+    /// Removes a stored chapter, along with its sidecar files. Returns whether the chapter was
+    /// stored at all.
+    pub async fn remove_chapter(&self, id: &ChapterId) -> Result<bool> {
+        let Some(path) = self.get_stored_chapter(id) else {
+            return Ok(false);
+        };
+
+        self.remove_stored_file(&path).await?;
+
+        Ok(true)
+    }
+
+    /// Moves a stored chapter, along with its sidecar files, to where it would be stored as
+    /// `to`. Returns whether the chapter was stored at all.
+    pub fn rename_chapter(&self, from: &ChapterId, to: &ChapterId) -> Result<bool> {
+        let Some(path) = self.get_stored_chapter(from) else {
+            return Ok(false);
+        };
+        let is_novel = path
+            .extension()
+            .is_some_and(|extension| extension == "epub");
+        let new_path = self.path_for_chapter(to, is_novel);
+
+        for (old_sidecar, new_sidecar) in [
+            (
+                self.errors_source_path(&path)?,
+                self.errors_source_path(&new_path)?,
+            ),
+            (
+                self.prefetched_marker_path(&path)?,
+                self.prefetched_marker_path(&new_path)?,
+            ),
+        ] {
+            match fs::rename(&old_sidecar, &new_sidecar) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        fs::rename(&path, &new_path).with_context(|| {
+            format!(
+                "failed to move {} to {}",
+                path.display(),
+                new_path.display()
+            )
+        })?;
+
+        Ok(true)
+    }
+
+    /// Lists the chapter files in the storage. As the file names are hashed, the files are
+    /// matched against `known_chapters` to tell which chapter each of them holds.
+    pub fn stored_chapter_files<'a>(
+        &self,
+        known_chapters: impl IntoIterator<Item = &'a ChapterId>,
+    ) -> Vec<StoredChapterFile> {
+        let mut chapters_by_path = HashMap::new();
+        for id in known_chapters {
+            for is_novel in [false, true] {
+                chapters_by_path.insert(self.path_for_chapter(id, is_novel), id);
+                chapters_by_path.insert(self.path_for_chapter_legacy(id, is_novel), id);
+            }
+        }
+
+        self.chapter_files_iterator()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let path = entry.into_path();
+                let prefetched = self
+                    .prefetched_marker_path(&path)
+                    .is_ok_and(|marker| marker.exists());
+
+                Some(StoredChapterFile {
+                    chapter_id: chapters_by_path.get(&path).map(|id| (*id).clone()),
+                    size: metadata.size(),
+                    modified: metadata.modified().ok()?,
+                    prefetched,
+                    path,
+                })
+            })
+            .collect()
+    }
+
+    pub fn stored_poster_files(&self) -> Vec<StoredPosterFile> {
+        let Ok(entries) = fs::read_dir(self.downloads_folder_path.join(POSTERS_FOLDER)) else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+
+                // The `.{hash}` files only hold the extension of the poster
+                if !metadata.is_file() || entry.file_name().to_string_lossy().starts_with('.') {
+                    return None;
+                }
+
+                Some(StoredPosterFile {
+                    path: entry.path(),
+                    size: metadata.size(),
+                })
+            })
+            .collect()
+    }
+
     // FIXME depending on `NamedTempFile` here is pretty ugly
     pub async fn persist_chapter(
         &self,
@@ -230,39 +457,26 @@ impl ChapterStorage {
         temporary_file: NamedTempFile,
         errors: &Vec<crate::chapter_downloader::DownloadError>,
     ) -> Result<PathBuf> {
-        let mut current_size = self.calculate_storage_size();
-        let persisted_chapter_size = Size::from_bytes(temporary_file.as_file().metadata()?.size());
+        let persisted_chapter_size = temporary_file.as_file().metadata()?.size();
 
-        while current_size + persisted_chapter_size > self.storage_size_limit {
-            debug!(
-                "persist_chapter: current storage is {current_size}/{}, new persisted chapter is \
-                {persisted_chapter_size}, attempting to evict",
-                self.storage_size_limit
-            );
-
-            self.evict_least_recently_modified_chapter()
-                .await
-                .with_context(|| format!(
-                    "while attempting to bring the storage size under the {} limit (current size: {}, persisted chapter size: {})",
-                    self.storage_size_limit,
-                    current_size,
-                    persisted_chapter_size,
-                ))?;
-
-            current_size = self.calculate_storage_size();
-        }
+        self.make_room_for(Size::from_bytes(persisted_chapter_size))
+            .await?;
 
         // Persist using the new path format
         let path = self.path_for_chapter(id, is_novel);
+        let replaced_size = fs::metadata(&path)
+            .map(|metadata| metadata.size())
+            .unwrap_or(0);
         temporary_file.persist(&path)?;
+        self.adjust_cached_size(persisted_chapter_size, replaced_size);
 
+        let errors_path = self.errors_source_path(&path)?;
+        let _ = self.remove_file(&errors_path).await;
         if !errors.is_empty() {
-            let _ = std::fs::write(
-                &self.errors_source_path(&path)?,
-                serde_json::to_vec(&errors)?,
-            );
-        } else {
-            let _ = std::fs::remove_file(&self.errors_source_path(&path)?);
+            let errors = serde_json::to_vec(&errors)?;
+            if fs::write(&errors_path, &errors).is_ok() {
+                self.adjust_cached_size(errors.len() as u64, 0);
+            }
         }
 
         Ok(path)
@@ -273,58 +487,155 @@ impl ChapterStorage {
             .with_context(|| "while trying to ensure chapter storage exists")?;
 
         self.downloads_folder_path = path;
+        // The size of the previous folder is still used by the other clones
+        self.cached_size = Default::default();
 
         Ok(())
     }
 
-    // cache this function
-    fn calculate_storage_size(&self) -> Size {
-        let size_in_bytes: u64 = self
-            .chapter_files_iterator()
-            .filter_map(|entry| entry.metadata().ok().map(|metadata| metadata.size()))
-            .sum();
+    /// Whether the stored files already take up all of the configured storage size limit.
+    pub fn is_full(&self) -> bool {
+        self.storage_size() >= self.storage_size_limit
+    }
+
+    /// Size of the stored chapters, their sidecar files and the cached posters.
+    pub fn storage_size(&self) -> Size {
+        let mut cached_size = self.cached_size.lock().unwrap();
+        let size = *cached_size.get_or_insert_with(|| self.calculate_storage_size());
+
+        Size::from_bytes(size)
+    }
+
+    fn calculate_storage_size(&self) -> u64 {
+        WalkDir::new(&self.downloads_folder_path)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| self.is_managed_file(entry.path()))
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.size())
+            .sum()
+    }
 
-        Size::from_bytes(size_in_bytes)
+    fn adjust_cached_size(&self, added: u64, removed: u64) {
+        if let Some(size) = self.cached_size.lock().unwrap().as_mut() {
+            *size = (*size + added).saturating_sub(removed);
+        }
     }
 
-    async fn evict_least_recently_modified_chapter(&self) -> Result<()> {
-        let chapter_to_evict = self
-            .find_least_recently_modified_chapter()?
-            .ok_or_else(|| anyhow!("couldn't find any chapters to evict from storage"))?;
+    /// Evicts files, in the order given by the eviction policy, until `size` more bytes fit
+    /// in the storage.
+    async fn make_room_for(&self, size: Size) -> Result<()> {
+        let mut current_size = self.storage_size();
+        if current_size + size <= self.storage_size_limit {
+            return Ok(());
+        }
+        if !self.evict {
+            return Err(StorageFullError { size }.into());
+        }
+
+        let mut files_to_evict = match &self.eviction_policy {
+            Some(eviction_policy) => eviction_policy.eviction_order(self).await?,
+            None => self.least_recently_modified_chapters(),
+        }
+        .into_iter();
+
+        while current_size + size > self.storage_size_limit {
+            debug!(
+                "make_room_for: current storage is {current_size}/{}, new persisted chapter is \
+                {size}, attempting to evict",
+                self.storage_size_limit
+            );
+
+            let file_to_evict = files_to_evict
+                .next()
+                .ok_or_else(|| anyhow!("couldn't find any chapters to evict from storage"))
+                .with_context(|| format!(
+                    "while attempting to bring the storage size under the {} limit (current size: {}, persisted chapter size: {})",
+                    self.storage_size_limit,
+                    current_size,
+                    size,
+                ))?;
 
-        debug!(
-            "evict_least_recently_modified_chapter: evicting {}",
-            chapter_to_evict.display()
-        );
+            debug!("make_room_for: evicting {}", file_to_evict.display());
 
-        let cloned_path = chapter_to_evict.clone();
-        let _ = match tokio::fs::remove_file(chapter_to_evict).await {
-            Ok(_) => Ok(()),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()), // Already deleted
-            Err(e) => Err(anyhow!(
-                "Failed to delete file {}: {}",
-                cloned_path.display(),
-                e
-            )),
+            if let Err(e) = self.remove_stored_file(&file_to_evict).await {
+                warn!("make_room_for: {e}");
+            }
+
+            current_size = self.storage_size();
+        }
+
+        Ok(())
+    }
+
+    fn least_recently_modified_chapters(&self) -> Vec<PathBuf> {
+        let mut chapters: Vec<_> = self
+            .stored_chapter_files(std::iter::empty())
+            .into_iter()
+            // Prefetched chapters are kept until they're read
+            .filter(|chapter| !chapter.prefetched)
+            .collect();
+
+        chapters.sort_by_key(|chapter| chapter.modified);
+
+        chapters.into_iter().map(|chapter| chapter.path).collect()
+    }
+
+    /// Removes a stored chapter or poster, along with the files that go with it.
+    async fn remove_stored_file(&self, path: &Path) -> Result<()> {
+        let sidecars = if self.is_poster_file(path) {
+            let stem = path
+                .file_stem()
+                .ok_or_else(|| anyhow!("poster file has no filename stem"))?;
+
+            vec![path.with_file_name(format!(".{}", stem.to_string_lossy()))]
+        } else {
+            vec![
+                self.errors_source_path(path)?,
+                self.prefetched_marker_path(path)?,
+            ]
         };
 
+        match self.remove_file(path).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {} // Already deleted
+            Err(e) => return Err(anyhow!("Failed to delete file {}: {}", path.display(), e)),
+        }
+
+        for sidecar in sidecars {
+            let _ = self.remove_file(&sidecar).await;
+        }
+
         Ok(())
     }
 
-    fn find_least_recently_modified_chapter(&self) -> Result<Option<PathBuf>> {
-        let chapter_path = self
-            .chapter_files_iterator()
-            .filter_map(|entry| {
-                let path = entry.path().to_owned();
-                let modified = entry.metadata().ok()?.modified().ok()?;
+    async fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+        let size = tokio::fs::metadata(path).await?.size();
+        tokio::fs::remove_file(path).await?;
 
-                Some((path, modified))
-            })
-            // FIXME i dont think we need to clone here
-            .min_by_key(|(_, modified)| *modified)
-            .map(|(path, _)| path.to_owned());
+        if self.is_managed_file(path) {
+            self.adjust_cached_size(0, size);
+        }
 
-        Ok(chapter_path)
+        Ok(())
+    }
+
+    fn is_poster_file(&self, path: &Path) -> bool {
+        path.parent()
+            .is_some_and(|parent| parent.ends_with(POSTERS_FOLDER))
+    }
+
+    // Whether the file counts towards the storage size.
+    fn is_managed_file(&self, path: &Path) -> bool {
+        if self.is_poster_file(path) {
+            return true;
+        }
+
+        matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some(ext) if CHAPTER_FILE_EXTENSION.contains(&ext) || SIDECAR_FILE_EXTENSION.contains(&ext)
+        )
     }
 
     fn chapter_files_iterator(&self) -> impl Iterator<Item = DirEntry> {
@@ -335,13 +646,13 @@ impl ChapterStorage {
                 let extension = entry.path().extension()?;
                 let metadata = entry.metadata().ok()?;
 
-                if !metadata.is_file() || !matches!(extension.to_str(), Some(ext) if CHAPTER_FILE_EXTENSION.contains(&ext))
+                if !metadata.is_file()
+                    || !matches!(extension.to_str(), Some(ext) if CHAPTER_FILE_EXTENSION.contains(&ext))
                 {
                     return None;
                 }
 
                 Some(entry)
-
             })
     }
 
@@ -373,3 +684,78 @@ impl ChapterStorage {
         self.downloads_folder_path.join(output_filename)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn chapter_id(key: &str) -> ChapterId {
+        ChapterId::from_strings("source".into(), "manga".into(), key.into())
+    }
+
+    fn chapter_file(storage: &ChapterStorage, size: usize) -> NamedTempFile {
+        let mut file = NamedTempFile::new_in(&storage.downloads_folder_path).unwrap();
+        file.write_all(&vec![0; size]).unwrap();
+
+        file
+    }
+
+    #[tokio::test]
+    async fn persisting_evicts_older_chapters() {
+        let folder = tempfile::tempdir().unwrap();
+        let storage =
+            ChapterStorage::new(folder.path().to_path_buf(), Size::from_bytes(150)).unwrap();
+
+        let old = chapter_id("old");
+        let file = chapter_file(&storage, 100);
+        storage
+            .persist_chapter(&old, false, file, &vec![])
+            .await
+            .unwrap();
+
+        let file = chapter_file(&storage, 100);
+        storage
+            .persist_chapter(&chapter_id("new"), false, file, &vec![])
+            .await
+            .unwrap();
+
+        assert!(storage.get_stored_chapter(&old).is_none());
+    }
+
+    #[tokio::test]
+    async fn persisting_without_eviction_fails_when_full() {
+        let folder = tempfile::tempdir().unwrap();
+        let storage = ChapterStorage::new(folder.path().to_path_buf(), Size::from_bytes(150))
+            .unwrap()
+            .without_eviction();
+
+        let old = chapter_id("old");
+        let file = chapter_file(&storage, 100);
+        storage
+            .persist_chapter(&old, false, file, &vec![])
+            .await
+            .unwrap();
+
+        // Fits next to the stored chapter
+        let small = chapter_id("small");
+        let file = chapter_file(&storage, 40);
+        storage
+            .persist_chapter(&small, false, file, &vec![])
+            .await
+            .unwrap();
+
+        let new = chapter_id("new");
+        let file = chapter_file(&storage, 100);
+        let error = storage
+            .persist_chapter(&new, false, file, &vec![])
+            .await
+            .unwrap_err();
+
+        assert!(error.downcast_ref::<StorageFullError>().is_some());
+        assert!(storage.get_stored_chapter(&old).is_some());
+        assert!(storage.get_stored_chapter(&small).is_some());
+        assert!(storage.get_stored_chapter(&new).is_none());
+    }
+}
@@ -0,0 +1,201 @@
+// Whether rakuyomi is offline. Offline mode is either forced through the `offline_mode`
+// setting, or entered automatically when a connectivity check fails: requests made by sources
+// check for a connection before going out, and the server probes it periodically while
+// offline, so that going back online is noticed even when nothing is being requested.
+//
+// Connectivity is checked with the probes from the `connectivity` settings (TCP connections,
+// HEAD requests or DNS lookups), all at once: we're online as soon as one of them succeeds.
+// Results are reused for a few seconds, so that a burst of requests only checks once.
+//
+// While offline, everything is served from the database and the chapter storage, and actions
+// that need the network are queued (see `pending_actions`) until the connection comes back.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use futures::future::select_ok;
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::{
+    net::{lookup_host, TcpStream},
+    sync::{watch, Mutex},
+};
+use url::Url;
+
+use crate::settings::{ConnectivityProbe, ConnectivitySettings};
+
+static CONNECTIVITY: Lazy<Connectivity> = Lazy::new(|| Connectivity {
+    forced_offline: AtomicBool::new(false),
+    detected_offline: watch::Sender::new(false),
+    settings: RwLock::new(ConnectivitySettings::default()),
+    source_urls: RwLock::new(Vec::new()),
+    last_check: Mutex::new(None),
+});
+
+pub fn connectivity() -> &'static Connectivity {
+    &CONNECTIVITY
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConnectivityState {
+    pub offline: bool,
+    /// Whether offline mode was forced through the settings.
+    pub forced: bool,
+    pub last_check: Option<ConnectivityCheck>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConnectivityCheck {
+    /// Seconds since the epoch.
+    pub checked_at: i64,
+    pub online: bool,
+    /// The probe which reached the network, if any.
+    pub reached: Option<String>,
+    #[serde(skip)]
+    instant: Instant,
+}
+
+pub struct Connectivity {
+    forced_offline: AtomicBool,
+    detected_offline: watch::Sender<bool>,
+    settings: RwLock<ConnectivitySettings>,
+    source_urls: RwLock<Vec<Url>>,
+    // Held while probing, so that concurrent checks wait for the same result
+    last_check: Mutex<Option<ConnectivityCheck>>,
+}
+
+impl Connectivity {
+    pub fn set_forced_offline(&self, forced_offline: bool) {
+        self.forced_offline.store(forced_offline, Ordering::SeqCst);
+    }
+
+    pub fn configure(&self, settings: &ConnectivitySettings) {
+        *self.settings.write().unwrap() = settings.clone();
+    }
+
+    /// Sets the base URLs of the installed sources, probed when `probe_source_urls` is set.
+    pub fn set_source_urls(&self, urls: Vec<Url>) {
+        *self.source_urls.write().unwrap() = urls;
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.forced_offline.load(Ordering::SeqCst) || *self.detected_offline.borrow()
+    }
+
+    pub async fn state(&self) -> ConnectivityState {
+        ConnectivityState {
+            offline: self.is_offline(),
+            forced: self.forced_offline.load(Ordering::SeqCst),
+            last_check: self.last_check.lock().await.clone(),
+        }
+    }
+
+    /// Checks for a connection, and records the result. Always fails without checking when
+    /// offline mode is forced.
+    pub async fn check(&self) -> bool {
+        if self.forced_offline.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let settings = self.settings.read().unwrap().clone();
+        let mut last_check = self.last_check.lock().await;
+        if let Some(check) = last_check.as_ref().filter(|check| {
+            check.instant.elapsed() < Duration::from_secs(settings.cache_ttl_seconds)
+        }) {
+            return check.online;
+        }
+
+        let reached = self.probe(&settings).await;
+        let online = reached.is_some();
+        *last_check = Some(ConnectivityCheck {
+            checked_at: chrono::Utc::now().timestamp(),
+            online,
+            reached,
+            instant: Instant::now(),
+        });
+
+        self.detected_offline.send_if_modified(|offline| {
+            let changed = *offline == online;
+            *offline = !online;
+
+            changed
+        });
+
+        online
+    }
+
+    /// Notifies whenever the detected state changes, with `true` meaning offline.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.detected_offline.subscribe()
+    }
+
+    // Runs every probe at once, returning the description of the first one to succeed.
+    async fn probe(&self, settings: &ConnectivitySettings) -> Option<String> {
+        let mut probes = settings.probes.clone();
+        if settings.probe_source_urls {
+            probes.extend(
+                self.source_urls
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .map(|url| ConnectivityProbe::HttpHead { url: url.clone() }),
+            );
+        }
+
+        // Without any probe there's no way to tell, so assume we're online
+        if probes.is_empty() {
+            return Some("no probes configured".to_owned());
+        }
+
+        let timeout = Duration::from_secs(settings.probe_timeout_seconds);
+        let client = reqwest::Client::builder().timeout(timeout).build().ok()?;
+        let probes = probes.into_iter().map(|probe| {
+            let client = client.clone();
+
+            async move {
+                tokio::time::timeout(timeout, run_probe(&client, &probe))
+                    .await
+                    .context("timed out")??;
+
+                Ok::<_, anyhow::Error>(describe(&probe))
+            }
+            .boxed()
+        });
+
+        select_ok(probes).await.ok().map(|(reached, _)| reached)
+    }
+}
+
+async fn run_probe(client: &reqwest::Client, probe: &ConnectivityProbe) -> Result<()> {
+    match probe {
+        ConnectivityProbe::TcpConnect { host, port } => {
+            TcpStream::connect((host.as_str(), *port)).await?;
+        }
+        ConnectivityProbe::HttpHead { url } => {
+            client.head(url.clone()).send().await?;
+        }
+        ConnectivityProbe::Dns { host } => {
+            lookup_host((host.as_str(), 0))
+                .await?
+                .next()
+                .ok_or_else(|| anyhow!("no address found for {host}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn describe(probe: &ConnectivityProbe) -> String {
+    match probe {
+        ConnectivityProbe::TcpConnect { host, port } => format!("tcp {host}:{port}"),
+        ConnectivityProbe::HttpHead { url } => format!("head {url}"),
+        ConnectivityProbe::Dns { host } => format!("dns {host}"),
+    }
+}
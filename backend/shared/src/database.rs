@@ -10,9 +10,14 @@ use sqlx::{sqlite::SqliteConnectOptions, Error, Pool, QueryBuilder, Sqlite};
 use url::Url;
 
 use crate::{
+    events::{self, Event},
     model::{
-        Chapter, ChapterId, ChapterInformation, ChapterState, Manga, MangaId, MangaInformation,
-        MangaState, NotificationInformation, SourceId, SourceInformation,
+        AutoDownloadMode, Chapter, ChapterId, ChapterInformation, ChapterState,
+        ChapterStorageEntry, DownloadQueueItem, DownloadQueueStatus, FailingManga, Manga, MangaId,
+        MangaInformation, MangaState, MangaUpdateSchedule, NotificationInformation,
+        NotificationKind, NotificationSubject, PendingAction, PinnedChapter, PinnedManga,
+        QueuedAction, QueuedDownload, SourceId, SourceInformation, UpdateRun, UpdateRunResult,
+        UpdateRunSourceSummary, UpdateRunTrigger,
     },
     source::model::PublishingStatus,
     source_collection::SourceCollection,
@@ -25,6 +30,28 @@ pub struct Database {
 }
 
 const BIND_LIMIT: usize = 32766;
+const UPDATE_RUN_RETENTION_SECS: i64 = 30 * 24 * 60 * 60;
+const READ_NOTIFICATION_RETENTION_SECS: i64 = 14 * 24 * 60 * 60;
+// Failed downloads are retried after this delay, doubled with each further attempt.
+const DOWNLOAD_RETRY_DELAY_SECS: i64 = 30;
+
+// The tables keyed by the key of a manga of a source, and the ones also keyed by the key of a
+// chapter (see `rewrite_source_keys`). `manga_details` names its manga key column `id`.
+const MANGA_KEY_TABLES: [&str; 6] = [
+    "manga_informations",
+    "manga_library",
+    "manga_state",
+    "last_check_update",
+    "pinned_mangas",
+    "update_run_results",
+];
+const CHAPTER_KEY_TABLES: [&str; 5] = [
+    "chapter_informations",
+    "chapter_state",
+    "pinned_chapters",
+    "download_queue",
+    "notifications",
+];
 
 // FIXME add proper error handling
 impl Database {
@@ -794,25 +821,37 @@ impl Database {
             )
             .fetch(&self.pool);
 
+            let mut kept_chapters = HashSet::new();
             while let Some(row) = stream.try_next().await? {
-                let id = ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id);
+                kept_chapters.insert(ChapterId::from_strings(
+                    row.source_id,
+                    row.manga_id,
+                    row.chapter_id,
+                ));
+            }
 
-                for is_novel in [false, true] {
-                    let path = chapter_storage.get_path_to_store_chapter(&id, is_novel);
-                    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
-                        remaining.remove(&path);
-                    }
+            // Pinned chapters are kept even if the source doesn't list them anymore, and so
+            // are the chapters of pinned manga, which are then only known by their state
+            kept_chapters.extend(self.find_pinned_chapter_ids(None).await?);
+            let rows = sqlx::query!(
+                r#"
+                SELECT cs.source_id, cs.manga_id, cs.chapter_id
+                FROM chapter_state cs
+                JOIN pinned_mangas pm
+                    ON pm.source_id = cs.source_id AND pm.manga_id = cs.manga_id
+                "#
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            kept_chapters.extend(
+                rows.into_iter().map(|row| {
+                    ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id)
+                }),
+            );
 
-                    let Some(path_file_errors) = chapter_storage.errors_source_path(&path).ok()
-                    else {
-                        continue;
-                    };
-                    if tokio::fs::try_exists(&path_file_errors)
-                        .await
-                        .unwrap_or(false)
-                    {
-                        remaining.remove(&path_file_errors);
-                    }
+            for id in &kept_chapters {
+                for path in chapter_storage.chapter_paths(id) {
+                    remaining.remove(&path);
                 }
             }
 
@@ -822,9 +861,17 @@ impl Database {
 
             let mut stream = sqlx::query!(
                 r#"
-                SELECT source_id, manga_id, chapter_id
-                FROM chapter_state
-                WHERE read = 1
+                SELECT cs.source_id, cs.manga_id, cs.chapter_id
+                FROM chapter_state cs
+                WHERE cs.read = 1
+                AND NOT EXISTS (
+                    SELECT 1 FROM pinned_chapters pc
+                    WHERE pc.source_id = cs.source_id AND pc.manga_id = cs.manga_id AND pc.chapter_id = cs.chapter_id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM pinned_mangas pm
+                    WHERE pm.source_id = cs.source_id AND pm.manga_id = cs.manga_id
+                )
                 "#
             )
             .fetch(&self.pool);
@@ -910,6 +957,8 @@ impl Database {
     ) -> Result<Vec<Chapter>> {
         let source_id = manga_id.source_id().value();
         let manga_id_val = manga_id.value();
+        let manga_pinned = self.is_manga_pinned(manga_id).await?;
+        let pinned_chapter_ids = self.find_pinned_chapter_ids(Some(manga_id)).await?;
 
         let rows = sqlx::query!(
             r#"
@@ -972,11 +1021,13 @@ impl Database {
                 };
 
                 let downloaded = chapter_storage.get_stored_chapter(&id).is_some();
+                let pinned = manga_pinned || pinned_chapter_ids.contains(&id);
 
                 Chapter {
                     information,
                     state,
                     downloaded,
+                    pinned,
                 }
             })
             .collect())
@@ -1245,16 +1296,11 @@ impl Database {
         let source_id = manga_id.source_id().value();
         let manga_id = manga_id.value();
 
-        let maybe_row = sqlx::query_as!(
-            MangaStateRow,
-            r#"
-                SELECT source_id, manga_id, preferred_scanlator 
+        let maybe_row = sqlx::query_as!(MangaStateRow, r#"
+                SELECT source_id, manga_id, preferred_scanlator, auto_download_mode, auto_download_languages
                 FROM manga_state
                 WHERE source_id = ?1 AND manga_id = ?2;
-            "#,
-            source_id,
-            manga_id,
-        )
+            "#, source_id, manga_id)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -1264,18 +1310,16 @@ impl Database {
     pub async fn upsert_manga_state(&self, manga_id: &MangaId, state: MangaState) -> Result<()> {
         let source_id = manga_id.source_id().value();
         let manga_id = manga_id.value();
+        let (auto_download_mode, auto_download_languages) = state.auto_download.to_columns();
 
-        sqlx::query!(
-            r#"
-                INSERT INTO manga_state (source_id, manga_id, preferred_scanlator)
-                VALUES (?1, ?2, ?3)
+        sqlx::query!(r#"
+                INSERT INTO manga_state (source_id, manga_id, preferred_scanlator, auto_download_mode, auto_download_languages)
+                VALUES (?1, ?2, ?3, ?4, ?5)
                 ON CONFLICT DO UPDATE SET
-                    preferred_scanlator = excluded.preferred_scanlator
-            "#,
-            source_id,
-            manga_id,
-            state.preferred_scanlator,
-        )
+                    preferred_scanlator = excluded.preferred_scanlator,
+                    auto_download_mode = excluded.auto_download_mode,
+                    auto_download_languages = excluded.auto_download_languages
+            "#, source_id, manga_id, state.preferred_scanlator, auto_download_mode, auto_download_languages)
         .execute(&self.pool)
         .await?;
 
@@ -1489,6 +1533,11 @@ impl Database {
         // Execute
         query.execute(&self.pool).await?;
 
+        events::publish(Event::NotificationCreated {
+            kind: NotificationKind::NewChapter,
+            manga_id: Some(manga_id.clone()),
+        });
+
         Ok(())
     }
 
@@ -1587,20 +1636,26 @@ impl Database {
         Ok(value.count)
     }
 
-    pub async fn get_notifications(&self) -> Result<Vec<NotificationInformation>> {
-        let rows = sqlx::query_as!(
-            NotificationInformationRow,
-            r#"
+    /// Lists the unread notifications, newest first. With `include_read`, read ones are listed
+    /// too, after the unread ones.
+    pub async fn get_notifications(
+        &self,
+        include_read: bool,
+    ) -> Result<Vec<NotificationInformation>> {
+        let rows = sqlx::query_as!(NotificationInformationRow, r#"
             SELECT
                 n.id,
+                n.kind,
                 n.source_id,
                 n.manga_id,
                 n.chapter_id,
-                mi.title AS manga_title,
-                md.cover_url AS manga_cover,
-                md.status AS manga_status,
-                ci.title AS chapter_title,
-                ci.chapter_number,
+                mi.title AS "manga_title?",
+                md.cover_url AS "manga_cover?",
+                md.status AS "manga_status?",
+                ci.title AS "chapter_title?",
+                ci.chapter_number AS "chapter_number?",
+                n.message,
+                n.is_read AS "is_read: bool",
                 n.created_at
             FROM
                 notifications n
@@ -1611,17 +1666,138 @@ impl Database {
             LEFT JOIN chapter_informations ci
                 ON ci.manga_id = n.manga_id AND ci.source_id = n.source_id AND ci.chapter_id = n.chapter_id
             WHERE
-                n.is_read = 0
+                ?1 OR n.is_read = 0
             ORDER BY
+                n.is_read ASC,
                 n.created_at DESC
-            "#,
-        )
+            "#, include_read)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(rows.into_iter().map(|row| row.into()).collect())
     }
 
+    pub async fn insert_event_notification(
+        &self,
+        kind: NotificationKind,
+        subject: NotificationSubject<'_>,
+        message: Option<&str>,
+    ) -> Result<()> {
+        let (source_id, manga_id, chapter_id) = match subject {
+            NotificationSubject::None => (None, None, None),
+            NotificationSubject::Source(source_id) => (Some(source_id), None, None),
+            NotificationSubject::Manga(manga_id) => {
+                (Some(manga_id.source_id()), Some(manga_id.value()), None)
+            }
+            NotificationSubject::Chapter(chapter_id) => (
+                Some(chapter_id.source_id()),
+                Some(chapter_id.manga_id().value()),
+                Some(chapter_id.value()),
+            ),
+        };
+        let now = chrono::Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (kind, source_id, manga_id, chapter_id, message, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            kind.as_str(),
+            source_id.map(|source_id| source_id.value()),
+            manga_id,
+            chapter_id,
+            message,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        events::publish(Event::NotificationCreated {
+            kind,
+            manga_id: source_id
+                .cloned()
+                .zip(manga_id)
+                .map(|(source_id, manga_id)| MangaId::new(source_id, manga_id.clone())),
+        });
+
+        Ok(())
+    }
+
+    /// Whether there's an unread notification of `kind` about the given source with the
+    /// same message, used to avoid notifying about the same event twice.
+    pub async fn has_unread_source_notification(
+        &self,
+        kind: NotificationKind,
+        source_id: &SourceId,
+        message: &str,
+    ) -> Result<bool> {
+        let exists: bool = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM notifications
+                WHERE kind = ?1 AND source_id = ?2 AND message = ?3 AND is_read = 0
+            ) AS "exists!: bool"
+            "#,
+            kind.as_str(),
+            source_id.value(),
+            message
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Whether there's an unread notification of `kind`, about anything.
+    pub async fn has_unread_notification(&self, kind: NotificationKind) -> Result<bool> {
+        let exists: bool = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM notifications WHERE kind = ?1 AND is_read = 0
+            ) AS "exists!: bool"
+            "#,
+            kind.as_str()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Marks a notification as read, returning whether it exists.
+    pub async fn mark_notification_as_read(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query!(r#"UPDATE notifications SET is_read = 1 WHERE id = ?1"#, id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Marks all notifications as read, or only the ones about `manga_id` if given.
+    pub async fn mark_notifications_as_read(&self, manga_id: Option<&MangaId>) -> Result<()> {
+        match manga_id {
+            Some(manga_id) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE notifications SET is_read = 1
+                    WHERE source_id = ?1 AND manga_id = ?2 AND is_read = 0
+                    "#,
+                    manga_id.source_id().value(),
+                    manga_id.value()
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                sqlx::query!(r#"UPDATE notifications SET is_read = 1 WHERE is_read = 0"#)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn delete_notification(&self, id: i32) -> Result<()> {
         sqlx::query!(
             r#"
@@ -1635,6 +1811,21 @@ impl Database {
         Ok(())
     }
 
+    /// Drops the read notifications older than `READ_NOTIFICATION_RETENTION_SECS`, returning
+    /// how many were dropped.
+    pub async fn purge_read_notifications(&self) -> Result<u64> {
+        let expired_before = chrono::Utc::now().timestamp() - READ_NOTIFICATION_RETENTION_SECS;
+
+        let result = sqlx::query!(
+            r#"DELETE FROM notifications WHERE is_read = 1 AND created_at < ?1"#,
+            expired_before
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn clear_notifications(&self) -> Result<()> {
         sqlx::query!(
             r#"
@@ -1700,118 +1891,1080 @@ impl Database {
 
         self.count_unread_chapters(manga_id).await
     }
-}
-
-/// Represents a manga entry in the user's library, joined with its information
-/// and the computed number of unread chapters.
-#[derive(sqlx::FromRow)]
-pub struct MangaLibraryRowWithReadCount {
-    /// ID of the source (e.g., MangaDex, NHentai, etc.)
-    pub source_id: String,
 
-    /// ID of the manga within the source
-    pub manga_id: String,
+    pub async fn start_update_run(&self, trigger: UpdateRunTrigger) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
 
-    /// Manga title (nullable in DB)
-    pub title: Option<String>,
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO update_runs (trigger, started_at)
+            VALUES (?1, ?2)
+            "#,
+            trigger.as_str(),
+            now
+        )
+        .execute(&self.pool)
+        .await?;
 
-    /// Author name (nullable in DB)
-    pub author: Option<String>,
+        Ok(result.last_insert_rowid())
+    }
 
-    /// Artist name (nullable in DB)
-    pub artist: Option<String>,
+    pub async fn insert_update_run_result(
+        &self,
+        run_id: i64,
+        manga_id: &MangaId,
+        new_chapters: usize,
+        error: Option<String>,
+        duration_ms: i64,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
 
-    /// Cover image URL (nullable in DB)
-    pub cover_url: Option<String>,
+        sqlx::query!(
+            r#"
+            INSERT INTO update_run_results (
+                run_id, source_id, manga_id, new_chapters, error, duration_ms, checked_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            run_id,
+            manga_id.source_id().value(),
+            manga_id.value(),
+            new_chapters as i64,
+            error,
+            duration_ms,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
 
-    /// Number of unread chapters (computed via COUNT)
-    /// Compatible sqlx but never None in practice
-    pub unread_chapters_count: Option<i64>,
+        Ok(())
+    }
 
-    /// Timestamp of the last read chapter (nullable in DB)
-    pub last_read: Option<i64>,
-}
+    /// Marks the run as finished and drops reports older than `UPDATE_RUN_RETENTION_SECS`.
+    pub async fn finish_update_run(&self, run_id: i64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let expired_before = now - UPDATE_RUN_RETENTION_SECS;
 
-#[derive(sqlx::FromRow)]
-struct MangaInformationsRow {
-    source_id: String,
-    manga_id: String,
-    title: Option<String>,
-    author: Option<String>,
-    artist: Option<String>,
-    cover_url: Option<String>,
-}
+        sqlx::query!(
+            "UPDATE update_runs SET finished_at = ?1 WHERE id = ?2",
+            now,
+            run_id
+        )
+        .execute(&self.pool)
+        .await?;
 
-impl From<MangaInformationsRow> for MangaInformation {
-    fn from(value: MangaInformationsRow) -> Self {
-        Self {
-            id: MangaId::from_strings(value.source_id, value.manga_id),
-            title: value.title,
-            author: value.author,
-            artist: value.artist,
-            cover_url: value
-                .cover_url
-                .map(|url_string| url_string.as_str().try_into().unwrap()),
-        }
-    }
-}
+        sqlx::query!(
+            r#"
+            DELETE FROM update_run_results
+            WHERE run_id IN (SELECT id FROM update_runs WHERE started_at < ?1)
+            "#,
+            expired_before
+        )
+        .execute(&self.pool)
+        .await?;
 
-#[derive(sqlx::FromRow)]
-struct MangaDetailsRow {
-    source_id: String,
-    id: String,
-    title: Option<String>,
-    author: Option<String>,
-    artist: Option<String>,
-    description: Option<String>,
-    tags: Option<String>,
-    cover_url: Option<String>,
-    url: Option<String>,
-    status: i64,
-    nsfw: i64,
-    viewer: i64,
-    // FIXME i dont think those are needed, the sources have no way of creating them
-    last_updated: Option<String>,
-    last_opened: Option<String>,
-    last_read: Option<i64>,
-    date_added: Option<String>,
+        sqlx::query!(
+            "DELETE FROM update_runs WHERE started_at < ?1",
+            expired_before
+        )
+        .execute(&self.pool)
+        .await?;
 
-    pub per_read: Option<f64>,
-}
-impl From<MangaDetailsRow> for crate::source::model::Manga {
-    fn from(row: MangaDetailsRow) -> Self {
-        // Parse enums (SQLite returns INTEGER as i64)
-        let status = crate::source::model::PublishingStatus::from(row.status as u8);
-        let nsfw = crate::source::model::MangaContentRating::from(row.nsfw as u8);
-        let viewer = crate::source::model::MangaViewer::from(row.viewer as u8);
+        Ok(())
+    }
 
-        // Parse tags JSON
-        let tags = row.tags.map(|json| serde_json::from_str(&json).unwrap());
+    pub async fn get_update_runs(&self, limit: i64) -> Result<Vec<UpdateRun>> {
+        let rows = sqlx::query_as!(
+            UpdateRunRow,
+            r#"
+            SELECT id, trigger, started_at, finished_at
+            FROM update_runs
+            ORDER BY started_at DESC, id DESC
+            LIMIT ?1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        // Parse URLs
-        let cover_url = row
-            .cover_url
-            .as_ref()
-            .map(|s| s.as_str().try_into().unwrap());
+        let mut runs = Vec::with_capacity(rows.len());
+        for row in rows {
+            let sources = self.get_update_run_source_summaries(row.id).await?;
+
+            runs.push(UpdateRun {
+                id: row.id,
+                trigger: row.trigger,
+                started_at: row.started_at,
+                finished_at: row.finished_at,
+                sources,
+                results: Vec::new(),
+            });
+        }
 
-        let url = row.url.as_ref().map(|s| s.as_str().try_into().unwrap());
+        Ok(runs)
+    }
 
-        // Parse datetime
-        let parse_dt = |v: Option<String>| {
-            v.map(|s| {
-                chrono::DateTime::parse_from_rfc3339(&s)
-                    .unwrap()
-                    .with_timezone(&chrono_tz::UTC)
-            })
+    pub async fn find_update_run(&self, run_id: i64) -> Result<Option<UpdateRun>> {
+        let Some(row) = sqlx::query_as!(
+            UpdateRunRow,
+            r#"
+            SELECT id, trigger, started_at, finished_at
+            FROM update_runs
+            WHERE id = ?1
+            "#,
+            run_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
         };
 
-        Self {
-            source_id: row.source_id,
-            id: row.id,
-            title: row.title,
-            author: row.author,
-            artist: row.artist,
-            description: row.description,
+        let sources = self.get_update_run_source_summaries(row.id).await?;
+        let results = sqlx::query_as!(
+            UpdateRunResultRow,
+            r#"
+            SELECT
+                r.source_id,
+                r.manga_id,
+                mi.title AS "manga_title?",
+                r.new_chapters,
+                r.error,
+                r.duration_ms,
+                r.checked_at
+            FROM update_run_results r
+            LEFT JOIN manga_informations mi
+                ON mi.source_id = r.source_id AND mi.manga_id = r.manga_id
+            WHERE r.run_id = ?1
+            ORDER BY r.checked_at ASC
+            "#,
+            row.id
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(UpdateRunResult::from)
+        .collect();
+
+        Ok(Some(UpdateRun {
+            id: row.id,
+            trigger: row.trigger,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+            sources,
+            results,
+        }))
+    }
+
+    async fn get_update_run_source_summaries(
+        &self,
+        run_id: i64,
+    ) -> Result<Vec<UpdateRunSourceSummary>> {
+        let rows = sqlx::query_as!(
+            UpdateRunSourceSummaryRow,
+            r#"
+            SELECT
+                source_id,
+                COUNT(*) AS "checked!: i64",
+                SUM(CASE WHEN error IS NOT NULL THEN 1 ELSE 0 END) AS "failed!: i64",
+                SUM(new_chapters) AS "new_chapters!: i64",
+                SUM(duration_ms) AS "duration_ms!: i64"
+            FROM update_run_results
+            WHERE run_id = ?1
+            GROUP BY source_id
+            ORDER BY source_id
+            "#,
+            run_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into()).collect())
+    }
+
+    /// Adds chapters to the download queue. Chapters which are already queued keep their
+    /// place, but failed or paused ones are queued again.
+    pub async fn enqueue_downloads(&self, chapter_ids: &[ChapterId], priority: i64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+
+        for chapter_id in chapter_ids {
+            sqlx::query!(
+                r#"
+                INSERT INTO download_queue
+                    (source_id, manga_id, chapter_id, priority, status, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, 'queued', ?5, ?5)
+                ON CONFLICT (source_id, manga_id, chapter_id) DO UPDATE SET
+                    priority = MAX(priority, excluded.priority),
+                    status = CASE WHEN status = 'downloading' THEN status ELSE 'queued' END,
+                    attempts = CASE WHEN status = 'failed' THEN 0 ELSE attempts END,
+                    auto_download = 0,
+                    updated_at = excluded.updated_at
+                "#,
+                chapter_id.source_id().value(),
+                chapter_id.manga_id().value(),
+                chapter_id.value(),
+                priority,
+                now
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Adds the chapters found by the update check to the download queue, behind the ones the
+    /// user asked for. Chapters which are already queued, or failed before, are left as is.
+    pub async fn enqueue_auto_downloads(
+        &self,
+        chapter_ids: &[ChapterId],
+        priority: i64,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+
+        for chapter_id in chapter_ids {
+            sqlx::query!(
+                r#"
+                INSERT INTO download_queue
+                    (source_id, manga_id, chapter_id, priority, status, auto_download, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, 'queued', 1, ?5, ?5)
+                ON CONFLICT (source_id, manga_id, chapter_id) DO NOTHING
+                "#,
+                chapter_id.source_id().value(),
+                chapter_id.manga_id().value(),
+                chapter_id.value(),
+                priority,
+                now
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn get_download_queue(&self) -> Result<Vec<DownloadQueueItem>> {
+        let rows = sqlx::query_as!(DownloadQueueItemRow, r#"
+            SELECT
+                q.id,
+                q.source_id,
+                q.manga_id,
+                q.chapter_id,
+                ci.title AS "chapter_title?",
+                ci.chapter_number AS "chapter_number?",
+                mi.title AS "manga_title?",
+                q.priority,
+                q.status,
+                q.attempts,
+                q.last_error,
+                q.created_at,
+                q.updated_at
+            FROM download_queue q
+            LEFT JOIN manga_informations mi
+                ON mi.source_id = q.source_id AND mi.manga_id = q.manga_id
+            LEFT JOIN chapter_informations ci
+                ON ci.source_id = q.source_id AND ci.manga_id = q.manga_id AND ci.chapter_id = q.chapter_id
+            ORDER BY
+                q.status = 'downloading' DESC,
+                q.priority DESC,
+                q.created_at ASC,
+                q.id ASC
+            "#)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into()).collect())
+    }
+
+    /// Takes the next queued chapter (highest priority first, then oldest) and marks it as
+    /// downloading. Chapters which failed to download are only taken again once their retry
+    /// delay has passed.
+    pub async fn take_next_queued_download(&self) -> Result<Option<QueuedDownload>> {
+        let now = chrono::Utc::now().timestamp();
+        let row = sqlx::query!(
+            r#"
+            UPDATE download_queue
+            SET status = 'downloading', attempts = attempts + 1, updated_at = ?1
+            WHERE id = (
+                SELECT id FROM download_queue
+                WHERE status = 'queued'
+                AND updated_at + ?2 * ((1 << attempts) / 2) <= ?1
+                ORDER BY priority DESC, created_at ASC, id ASC
+                LIMIT 1
+            )
+            RETURNING id, source_id, manga_id, chapter_id, attempts, auto_download AS "auto_download: bool"
+            "#,
+            now,
+            DOWNLOAD_RETRY_DELAY_SECS
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| QueuedDownload {
+            id: row.id,
+            chapter_id: ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id),
+            attempts: row.attempts,
+            auto_download: row.auto_download,
+        }))
+    }
+
+    pub async fn set_download_status(
+        &self,
+        id: i64,
+        status: DownloadQueueStatus,
+        last_error: Option<&str>,
+    ) -> Result<bool> {
+        let now = chrono::Utc::now().timestamp();
+        let result = sqlx::query!(
+            r#"
+            UPDATE download_queue
+            SET status = ?2, last_error = COALESCE(?3, last_error), updated_at = ?4
+            WHERE id = ?1
+            "#,
+            id,
+            status.as_str(),
+            last_error,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Pauses or resumes a single queue item. Items being downloaded can't be paused, and
+    /// resumed items get all their attempts again.
+    pub async fn set_download_paused(&self, id: i64, paused: bool) -> Result<bool> {
+        let (from, to) = if paused {
+            ("queued", DownloadQueueStatus::Paused)
+        } else {
+            ("paused", DownloadQueueStatus::Queued)
+        };
+        let now = chrono::Utc::now().timestamp();
+        let result = sqlx::query!(
+            r#"
+            UPDATE download_queue
+            SET status = ?3, attempts = CASE WHEN ?3 = 'queued' THEN 0 ELSE attempts END,
+                updated_at = ?4
+            WHERE id = ?1 AND (status = ?2 OR status = 'failed')
+            "#,
+            id,
+            from,
+            to.as_str(),
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn remove_queued_download(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query!(r#"DELETE FROM download_queue WHERE id = ?1"#, id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Puts the downloads which were interrupted (e.g. by a restart) back in the queue.
+    pub async fn requeue_interrupted_downloads(&self) -> Result<()> {
+        sqlx::query!(r#"UPDATE download_queue SET status = 'queued' WHERE status = 'downloading'"#)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn is_download_queue_paused(&self) -> Result<bool> {
+        let paused: Option<bool> = sqlx::query_scalar!(
+            r#"SELECT paused AS "paused: bool" FROM download_queue_state WHERE id = 0"#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(paused.unwrap_or(false))
+    }
+
+    pub async fn set_download_queue_paused(&self, paused: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO download_queue_state (id, paused) VALUES (0, ?1)
+            ON CONFLICT (id) DO UPDATE SET paused = excluded.paused
+            "#,
+            paused
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn has_read_chapters(&self, id: &MangaId) -> Result<bool> {
+        let read: bool = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM chapter_state
+                WHERE source_id = ?1 AND manga_id = ?2 AND read = 1
+            ) AS "exists!: bool"
+            "#,
+            id.source_id().value(),
+            id.value()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(read)
+    }
+
+    /// Lists every known chapter, along with what decides whether its downloaded file may be
+    /// evicted from the storage.
+    pub async fn find_chapter_storage_entries(&self) -> Result<Vec<ChapterStorageEntry>> {
+        let rows = sqlx::query_as!(ChapterStorageEntryRow, r#"
+            WITH chapters AS (
+                SELECT source_id, manga_id, chapter_id, locked
+                FROM chapter_informations
+                UNION
+                -- Pinned chapters the source doesn't list anymore
+                SELECT source_id, manga_id, chapter_id, 0
+                FROM pinned_chapters pc
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM chapter_informations ci
+                    WHERE ci.source_id = pc.source_id AND ci.manga_id = pc.manga_id AND ci.chapter_id = pc.chapter_id
+                )
+            )
+            SELECT
+                ci.source_id,
+                ci.manga_id,
+                ci.chapter_id,
+                mi.title AS "manga_title?",
+                COALESCE(cs.read, 0) AS "read!: bool",
+                ci.locked AS "locked!: bool",
+                ml.manga_id IS NOT NULL AS "in_library!: bool",
+                q.id IS NOT NULL AS "queued!: bool",
+                pc.chapter_id IS NOT NULL OR pm.manga_id IS NOT NULL AS "pinned!: bool"
+            FROM chapters ci
+            LEFT JOIN manga_informations mi
+                ON mi.source_id = ci.source_id AND mi.manga_id = ci.manga_id
+            LEFT JOIN chapter_state cs
+                ON cs.source_id = ci.source_id AND cs.manga_id = ci.manga_id AND cs.chapter_id = ci.chapter_id
+            LEFT JOIN manga_library ml
+                ON ml.source_id = ci.source_id AND ml.manga_id = ci.manga_id
+            LEFT JOIN download_queue q
+                ON q.source_id = ci.source_id AND q.manga_id = ci.manga_id AND q.chapter_id = ci.chapter_id
+                AND q.status IN ('queued', 'downloading', 'paused')
+            LEFT JOIN pinned_chapters pc
+                ON pc.source_id = ci.source_id AND pc.manga_id = ci.manga_id AND pc.chapter_id = ci.chapter_id
+            LEFT JOIN pinned_mangas pm
+                ON pm.source_id = ci.source_id AND pm.manga_id = ci.manga_id
+            "#)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into()).collect())
+    }
+
+    pub async fn set_manga_pinned(&self, manga_id: &MangaId, pinned: bool) -> Result<()> {
+        if pinned {
+            sqlx::query!(
+                r#"
+                INSERT INTO pinned_mangas (source_id, manga_id, pinned_at)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT DO NOTHING
+                "#,
+                manga_id.source_id().value(),
+                manga_id.value(),
+                chrono::Utc::now().timestamp()
+            )
+            .execute(&self.pool)
+            .await?;
+        } else {
+            sqlx::query!(
+                r#"DELETE FROM pinned_mangas WHERE source_id = ?1 AND manga_id = ?2"#,
+                manga_id.source_id().value(),
+                manga_id.value()
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn is_manga_pinned(&self, manga_id: &MangaId) -> Result<bool> {
+        let pinned: bool = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM pinned_mangas
+                WHERE source_id = ?1 AND manga_id = ?2
+            ) AS "exists!: bool"
+            "#,
+            manga_id.source_id().value(),
+            manga_id.value()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(pinned)
+    }
+
+    pub async fn set_chapter_pinned(&self, chapter_id: &ChapterId, pinned: bool) -> Result<()> {
+        if pinned {
+            sqlx::query!(
+                r#"
+                INSERT INTO pinned_chapters (source_id, manga_id, chapter_id, pinned_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT DO NOTHING
+                "#,
+                chapter_id.source_id().value(),
+                chapter_id.manga_id().value(),
+                chapter_id.value(),
+                chrono::Utc::now().timestamp()
+            )
+            .execute(&self.pool)
+            .await?;
+        } else {
+            sqlx::query!(
+                r#"
+                DELETE FROM pinned_chapters
+                WHERE source_id = ?1 AND manga_id = ?2 AND chapter_id = ?3
+                "#,
+                chapter_id.source_id().value(),
+                chapter_id.manga_id().value(),
+                chapter_id.value()
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Finds the chapters pinned one by one, of the given manga or of all of them. Chapters
+    /// of pinned manga aren't included.
+    pub async fn find_pinned_chapter_ids(
+        &self,
+        manga_id: Option<&MangaId>,
+    ) -> Result<HashSet<ChapterId>> {
+        let source_id = manga_id.map(|id| id.source_id().value());
+        let manga_id = manga_id.map(|id| id.value());
+        let rows = sqlx::query!(
+            r#"
+            SELECT source_id, manga_id, chapter_id
+            FROM pinned_chapters
+            WHERE ?1 IS NULL OR (source_id = ?1 AND manga_id = ?2)
+            "#,
+            source_id,
+            manga_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id))
+            .collect())
+    }
+
+    pub async fn find_pinned_mangas(&self) -> Result<Vec<PinnedManga>> {
+        let rows = sqlx::query_as!(
+            PinnedMangaRow,
+            r#"
+            SELECT
+                pm.source_id,
+                pm.manga_id,
+                mi.title AS "manga_title?",
+                pm.pinned_at
+            FROM pinned_mangas pm
+            LEFT JOIN manga_informations mi
+                ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
+            ORDER BY pm.pinned_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into()).collect())
+    }
+
+    pub async fn find_pinned_chapters(&self) -> Result<Vec<PinnedChapter>> {
+        let rows = sqlx::query_as!(PinnedChapterRow, r#"
+            SELECT
+                pc.source_id,
+                pc.manga_id,
+                pc.chapter_id,
+                mi.title AS "manga_title?",
+                ci.title AS "chapter_title?",
+                ci.chapter_number AS "chapter_number?",
+                pc.pinned_at
+            FROM pinned_chapters pc
+            LEFT JOIN manga_informations mi
+                ON mi.source_id = pc.source_id AND mi.manga_id = pc.manga_id
+            LEFT JOIN chapter_informations ci
+                ON ci.source_id = pc.source_id AND ci.manga_id = pc.manga_id AND ci.chapter_id = pc.chapter_id
+            ORDER BY pc.pinned_at DESC
+            "#)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into()).collect())
+    }
+
+    pub async fn find_library_cover_urls(&self) -> Result<Vec<Url>> {
+        let cover_urls: Vec<String> = sqlx::query_scalar!(
+            r#"
+            SELECT mi.cover_url AS "cover_url!"
+            FROM manga_library ml
+            JOIN manga_informations mi
+                ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
+            WHERE mi.cover_url IS NOT NULL
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cover_urls
+            .iter()
+            .filter_map(|url| Url::parse(url).ok())
+            .collect())
+    }
+
+    /// Lists when each library manga was last checked for updates and when it is due next,
+    /// soonest first. Manga which are never checked (e.g. completed ones) come last.
+    pub async fn get_update_schedule(&self) -> Result<Vec<MangaUpdateSchedule>> {
+        let rows = sqlx::query_as!(
+            MangaUpdateScheduleRow,
+            r#"
+            SELECT
+                ml.source_id,
+                ml.manga_id,
+                mi.title AS "manga_title?",
+                lcu.last_check AS "last_check?",
+                lcu.next_ts_arima AS "next_check?"
+            FROM manga_library ml
+            LEFT JOIN manga_informations mi
+                ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
+            LEFT JOIN last_check_update lcu
+                ON lcu.source_id = ml.source_id AND lcu.manga_id = ml.manga_id
+            ORDER BY lcu.next_ts_arima IS NULL, lcu.next_ts_arima
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into()).collect())
+    }
+
+    /// Finds the library manga whose last `min_failures` (or more) update checks all failed.
+    pub async fn find_failing_mangas(&self, min_failures: i64) -> Result<Vec<FailingManga>> {
+        let rows = sqlx::query_as!(
+            FailingMangaRow,
+            r#"
+            WITH last_success AS (
+                SELECT source_id, manga_id, MAX(checked_at) AS last_success
+                FROM update_run_results
+                WHERE error IS NULL
+                GROUP BY source_id, manga_id
+            ),
+            failures AS (
+                SELECT
+                    r.source_id,
+                    r.manga_id,
+                    COUNT(*) AS consecutive_failures,
+                    MAX(r.checked_at) AS last_failure,
+                    ls.last_success
+                FROM update_run_results r
+                LEFT JOIN last_success ls
+                    ON ls.source_id = r.source_id AND ls.manga_id = r.manga_id
+                WHERE r.error IS NOT NULL
+                AND r.checked_at > COALESCE(ls.last_success, -1)
+                GROUP BY r.source_id, r.manga_id, ls.last_success
+            )
+            SELECT
+                f.source_id,
+                f.manga_id,
+                mi.title AS "manga_title?",
+                f.consecutive_failures AS "consecutive_failures!: i64",
+                f.last_failure AS "last_failure!: i64",
+                f.last_success AS "last_success?: i64",
+                (
+                    SELECT r2.error
+                    FROM update_run_results r2
+                    WHERE r2.source_id = f.source_id
+                    AND r2.manga_id = f.manga_id
+                    AND r2.error IS NOT NULL
+                    ORDER BY r2.checked_at DESC
+                    LIMIT 1
+                ) AS last_error
+            FROM failures f
+            JOIN manga_library ml
+                ON ml.source_id = f.source_id AND ml.manga_id = f.manga_id
+            LEFT JOIN manga_informations mi
+                ON mi.source_id = f.source_id AND mi.manga_id = f.manga_id
+            WHERE f.consecutive_failures >= ?1
+            ORDER BY f.source_id ASC, f.consecutive_failures DESC
+            "#,
+            min_failures
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into()).collect())
+    }
+
+    /// Lists the mangas and chapters of a source which are worth migrating when its keys change:
+    /// the ones in the library, with a state, pinned or queued for download, plus every cached
+    /// chapter, whose stored files may still have to be found.
+    pub async fn find_source_keys(
+        &self,
+        source_id: &SourceId,
+    ) -> Result<(HashSet<MangaId>, HashSet<ChapterId>, Vec<ChapterId>)> {
+        let manga_ids = sqlx::query_scalar!(
+            r#"
+            SELECT manga_id AS "manga_id!" FROM manga_library WHERE source_id = ?1
+            UNION SELECT manga_id FROM manga_state WHERE source_id = ?1
+            UNION SELECT manga_id FROM pinned_mangas WHERE source_id = ?1
+            "#,
+            source_id.value()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let chapter_ids = sqlx::query!(
+            r#"
+            SELECT manga_id AS "manga_id!", chapter_id AS "chapter_id!"
+            FROM chapter_state WHERE source_id = ?1
+            UNION SELECT manga_id, chapter_id FROM pinned_chapters WHERE source_id = ?1
+            UNION SELECT manga_id, chapter_id FROM download_queue WHERE source_id = ?1
+            "#,
+            source_id.value()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let cached_chapter_ids = sqlx::query!(
+            r#"
+            SELECT manga_id, chapter_id FROM chapter_informations WHERE source_id = ?1
+            "#,
+            source_id.value()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let to_chapter_id = |manga_id: String, chapter_id: String| {
+            ChapterId::from_strings(source_id.value().clone(), manga_id, chapter_id)
+        };
+
+        Ok((
+            manga_ids
+                .into_iter()
+                .map(|manga_id| MangaId::new(source_id.clone(), manga_id))
+                .collect(),
+            chapter_ids
+                .into_iter()
+                .map(|row| to_chapter_id(row.manga_id, row.chapter_id))
+                .collect(),
+            cached_chapter_ids
+                .into_iter()
+                .map(|row| to_chapter_id(row.manga_id, row.chapter_id))
+                .collect(),
+        ))
+    }
+
+    /// Moves everything stored about mangas and chapters from their old keys to their new
+    /// ones, in a single transaction. Rows already stored under a new key are replaced.
+    pub async fn rewrite_source_keys(
+        &self,
+        mangas: &HashMap<MangaId, MangaId>,
+        chapters: &HashMap<ChapterId, ChapterId>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // Chapters first, as they're looked up by the key of their manga
+        for (old, new) in chapters {
+            for table in CHAPTER_KEY_TABLES {
+                sqlx::query(&format!(
+                    r#"
+                    UPDATE OR REPLACE {table} SET manga_id = ?4, chapter_id = ?5
+                    WHERE source_id = ?1 AND manga_id = ?2 AND chapter_id = ?3
+                    "#
+                ))
+                .bind(old.source_id().value())
+                .bind(old.manga_id().value())
+                .bind(old.value())
+                .bind(new.manga_id().value())
+                .bind(new.value())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        for (old, new) in mangas {
+            for (table, column) in MANGA_KEY_TABLES
+                .iter()
+                .chain(CHAPTER_KEY_TABLES)
+                .map(|table| (*table, "manga_id"))
+                .chain([("manga_details", "id")])
+            {
+                sqlx::query(&format!(
+                    r#"
+                    UPDATE OR REPLACE {table} SET {column} = ?3
+                    WHERE source_id = ?1 AND {column} = ?2
+                    "#
+                ))
+                .bind(old.source_id().value())
+                .bind(old.value())
+                .bind(new.value())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Replaces a manga of the library with another one, carrying over the given chapter states
+    /// and its manga state, in a single transaction.
+    pub async fn migrate_manga_library_entry(
+        &self,
+        from: &MangaId,
+        to: &MangaId,
+        chapter_states: &[(ChapterId, ChapterState)],
+        manga_state: MangaState,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (chapter_id, state) in chapter_states {
+            sqlx::query!(
+                r#"
+                INSERT INTO chapter_state (source_id, manga_id, chapter_id, read, last_read)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT DO UPDATE SET
+                    read = excluded.read,
+                    last_read = excluded.last_read
+                "#,
+                chapter_id.source_id().value(),
+                chapter_id.manga_id().value(),
+                chapter_id.value(),
+                state.read,
+                state.last_read
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let (auto_download_mode, auto_download_languages) = manga_state.auto_download.to_columns();
+        sqlx::query!(r#"
+            INSERT INTO manga_state (source_id, manga_id, preferred_scanlator, auto_download_mode, auto_download_languages)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT DO UPDATE SET
+                preferred_scanlator = excluded.preferred_scanlator,
+                auto_download_mode = excluded.auto_download_mode,
+                auto_download_languages = excluded.auto_download_languages
+            "#, to.source_id().value(), to.value(), manga_state.preferred_scanlator, auto_download_mode, auto_download_languages)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO manga_library (source_id, manga_id)
+            VALUES (?1, ?2)
+            ON CONFLICT DO NOTHING
+            "#,
+            to.source_id().value(),
+            to.value()
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM manga_library
+            WHERE source_id = ?1 AND manga_id = ?2
+            "#,
+            from.source_id().value(),
+            from.value()
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Queues an action, or replaces the arguments of the same action if it's already queued.
+    pub async fn queue_pending_action(&self, action: PendingAction) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO pending_actions (action, arguments, queued_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (action) DO UPDATE SET arguments = excluded.arguments
+            "#,
+            action.as_str(),
+            serde_json::to_string(&action)?,
+            chrono::Utc::now().timestamp()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_pending_actions(&self) -> Result<Vec<QueuedAction>> {
+        let rows = sqlx::query_as!(
+            QueuedActionRow,
+            r#"SELECT action, arguments, queued_at FROM pending_actions ORDER BY queued_at"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let action = match &row.arguments {
+                    Some(arguments) => serde_json::from_str(arguments).ok()?,
+                    None => PendingAction::parse(&row.action)?,
+                };
+
+                Some(QueuedAction {
+                    action,
+                    queued_at: row.queued_at,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn remove_pending_action(&self, action: PendingAction) -> Result<()> {
+        sqlx::query!(
+            r#"DELETE FROM pending_actions WHERE action = ?1"#,
+            action.as_str()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents a manga entry in the user's library, joined with its information
+/// and the computed number of unread chapters.
+#[derive(sqlx::FromRow)]
+pub struct MangaLibraryRowWithReadCount {
+    /// ID of the source (e.g., MangaDex, NHentai, etc.)
+    pub source_id: String,
+
+    /// ID of the manga within the source
+    pub manga_id: String,
+
+    /// Manga title (nullable in DB)
+    pub title: Option<String>,
+
+    /// Author name (nullable in DB)
+    pub author: Option<String>,
+
+    /// Artist name (nullable in DB)
+    pub artist: Option<String>,
+
+    /// Cover image URL (nullable in DB)
+    pub cover_url: Option<String>,
+
+    /// Number of unread chapters (computed via COUNT)
+    /// Compatible sqlx but never None in practice
+    pub unread_chapters_count: Option<i64>,
+
+    /// Timestamp of the last read chapter (nullable in DB)
+    pub last_read: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct MangaInformationsRow {
+    source_id: String,
+    manga_id: String,
+    title: Option<String>,
+    author: Option<String>,
+    artist: Option<String>,
+    cover_url: Option<String>,
+}
+
+impl From<MangaInformationsRow> for MangaInformation {
+    fn from(value: MangaInformationsRow) -> Self {
+        Self {
+            id: MangaId::from_strings(value.source_id, value.manga_id),
+            title: value.title,
+            author: value.author,
+            artist: value.artist,
+            cover_url: value
+                .cover_url
+                .map(|url_string| url_string.as_str().try_into().unwrap()),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct MangaDetailsRow {
+    source_id: String,
+    id: String,
+    title: Option<String>,
+    author: Option<String>,
+    artist: Option<String>,
+    description: Option<String>,
+    tags: Option<String>,
+    cover_url: Option<String>,
+    url: Option<String>,
+    status: i64,
+    nsfw: i64,
+    viewer: i64,
+    // FIXME i dont think those are needed, the sources have no way of creating them
+    last_updated: Option<String>,
+    last_opened: Option<String>,
+    last_read: Option<i64>,
+    date_added: Option<String>,
+
+    pub per_read: Option<f64>,
+}
+impl From<MangaDetailsRow> for crate::source::model::Manga {
+    fn from(row: MangaDetailsRow) -> Self {
+        // Parse enums (SQLite returns INTEGER as i64)
+        let status = crate::source::model::PublishingStatus::from(row.status as u8);
+        let nsfw = crate::source::model::MangaContentRating::from(row.nsfw as u8);
+        let viewer = crate::source::model::MangaViewer::from(row.viewer as u8);
+
+        // Parse tags JSON
+        let tags = row.tags.map(|json| serde_json::from_str(&json).unwrap());
+
+        // Parse URLs
+        let cover_url = row
+            .cover_url
+            .as_ref()
+            .map(|s| s.as_str().try_into().unwrap());
+
+        let url = row.url.as_ref().map(|s| s.as_str().try_into().unwrap());
+
+        // Parse datetime
+        let parse_dt = |v: Option<String>| {
+            v.map(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .unwrap()
+                    .with_timezone(&chrono_tz::UTC)
+            })
+        };
+
+        Self {
+            source_id: row.source_id,
+            id: row.id,
+            title: row.title,
+            author: row.author,
+            artist: row.artist,
+            description: row.description,
 
             tags,
             cover_url,
@@ -1927,12 +3080,18 @@ struct MangaStateRow {
     source_id: String,
     manga_id: String,
     preferred_scanlator: Option<String>,
+    auto_download_mode: i64,
+    auto_download_languages: Option<String>,
 }
 
 impl From<MangaStateRow> for MangaState {
     fn from(value: MangaStateRow) -> Self {
         Self {
             preferred_scanlator: value.preferred_scanlator,
+            auto_download: AutoDownloadMode::from_columns(
+                value.auto_download_mode,
+                value.auto_download_languages,
+            ),
         }
     }
 }
@@ -1940,22 +3099,38 @@ impl From<MangaStateRow> for MangaState {
 #[derive(sqlx::FromRow)]
 struct NotificationInformationRow {
     id: i64,
-    source_id: String,
-    manga_id: String,
-    chapter_id: String,
+    kind: String,
+    source_id: Option<String>,
+    manga_id: Option<String>,
+    chapter_id: Option<String>,
     manga_title: Option<String>,
     manga_cover: Option<String>,
     manga_status: Option<i64>,
     chapter_title: Option<String>,
     chapter_number: Option<f64>,
+    message: Option<String>,
+    is_read: bool,
     created_at: i64,
 }
 
 impl From<NotificationInformationRow> for NotificationInformation {
     fn from(value: NotificationInformationRow) -> Self {
+        let source_id = value.source_id.map(SourceId::new);
+        let manga_id = source_id
+            .clone()
+            .zip(value.manga_id)
+            .map(|(source_id, manga_id)| MangaId::new(source_id, manga_id));
+        let chapter_id = manga_id
+            .clone()
+            .zip(value.chapter_id)
+            .map(|(manga_id, chapter_id)| ChapterId::new(manga_id, chapter_id));
+
         Self {
             id: value.id,
-            chapter_id: ChapterId::from_strings(value.source_id, value.manga_id, value.chapter_id),
+            kind: NotificationKind::parse(&value.kind).unwrap_or(NotificationKind::NewChapter),
+            source_id,
+            manga_id,
+            chapter_id,
             manga_title: value.manga_title.unwrap_or("Unknown".to_owned()),
             manga_cover: value
                 .manga_cover
@@ -1964,7 +3139,301 @@ impl From<NotificationInformationRow> for NotificationInformation {
             manga_status: value.manga_status,
             chapter_title: value.chapter_title.unwrap_or("Unknown".to_owned()),
             chapter_number: value.chapter_number.unwrap_or(-1.0),
+            message: value.message,
+            is_read: value.is_read,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct UpdateRunRow {
+    id: i64,
+    trigger: String,
+    started_at: i64,
+    finished_at: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct UpdateRunResultRow {
+    source_id: String,
+    manga_id: String,
+    manga_title: Option<String>,
+    new_chapters: i64,
+    error: Option<String>,
+    duration_ms: i64,
+    checked_at: i64,
+}
+
+impl From<UpdateRunResultRow> for UpdateRunResult {
+    fn from(value: UpdateRunResultRow) -> Self {
+        Self {
+            manga_id: MangaId::from_strings(value.source_id, value.manga_id),
+            manga_title: value.manga_title,
+            new_chapters: value.new_chapters,
+            error: value.error,
+            duration_ms: value.duration_ms,
+            checked_at: value.checked_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct UpdateRunSourceSummaryRow {
+    source_id: String,
+    checked: i64,
+    failed: i64,
+    new_chapters: i64,
+    duration_ms: i64,
+}
+
+impl From<UpdateRunSourceSummaryRow> for UpdateRunSourceSummary {
+    fn from(value: UpdateRunSourceSummaryRow) -> Self {
+        Self {
+            source_id: SourceId::new(value.source_id),
+            checked: value.checked,
+            failed: value.failed,
+            new_chapters: value.new_chapters,
+            duration_ms: value.duration_ms,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct FailingMangaRow {
+    source_id: String,
+    manga_id: String,
+    manga_title: Option<String>,
+    consecutive_failures: i64,
+    last_failure: i64,
+    last_success: Option<i64>,
+    last_error: Option<String>,
+}
+
+impl From<FailingMangaRow> for FailingManga {
+    fn from(value: FailingMangaRow) -> Self {
+        Self {
+            manga_id: MangaId::from_strings(value.source_id, value.manga_id),
+            manga_title: value.manga_title,
+            consecutive_failures: value.consecutive_failures,
+            last_error: value.last_error,
+            last_failure: value.last_failure,
+            last_success: value.last_success,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PinnedMangaRow {
+    source_id: String,
+    manga_id: String,
+    manga_title: Option<String>,
+    pinned_at: i64,
+}
+
+impl From<PinnedMangaRow> for PinnedManga {
+    fn from(value: PinnedMangaRow) -> Self {
+        Self {
+            manga_id: MangaId::from_strings(value.source_id, value.manga_id),
+            manga_title: value.manga_title,
+            pinned_at: value.pinned_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PinnedChapterRow {
+    source_id: String,
+    manga_id: String,
+    chapter_id: String,
+    manga_title: Option<String>,
+    chapter_title: Option<String>,
+    chapter_number: Option<f64>,
+    pinned_at: i64,
+}
+
+impl From<PinnedChapterRow> for PinnedChapter {
+    fn from(value: PinnedChapterRow) -> Self {
+        Self {
+            chapter_id: ChapterId::from_strings(value.source_id, value.manga_id, value.chapter_id),
+            manga_title: value.manga_title,
+            chapter_title: value.chapter_title,
+            chapter_number: value.chapter_number.map(|number| number as f32),
+            pinned_at: value.pinned_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ChapterStorageEntryRow {
+    source_id: String,
+    manga_id: String,
+    chapter_id: String,
+    manga_title: Option<String>,
+    read: bool,
+    locked: bool,
+    in_library: bool,
+    queued: bool,
+    pinned: bool,
+}
+
+impl From<ChapterStorageEntryRow> for ChapterStorageEntry {
+    fn from(value: ChapterStorageEntryRow) -> Self {
+        Self {
+            id: ChapterId::from_strings(value.source_id, value.manga_id, value.chapter_id),
+            manga_title: value.manga_title,
+            read: value.read,
+            locked: value.locked,
+            in_library: value.in_library,
+            queued: value.queued,
+            pinned: value.pinned,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct MangaUpdateScheduleRow {
+    source_id: String,
+    manga_id: String,
+    manga_title: Option<String>,
+    last_check: Option<i64>,
+    next_check: Option<i64>,
+}
+
+impl From<MangaUpdateScheduleRow> for MangaUpdateSchedule {
+    fn from(value: MangaUpdateScheduleRow) -> Self {
+        Self {
+            manga_id: MangaId::from_strings(value.source_id, value.manga_id),
+            manga_title: value.manga_title,
+            last_check: value.last_check,
+            next_check: value.next_check,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DownloadQueueItemRow {
+    id: i64,
+    source_id: String,
+    manga_id: String,
+    chapter_id: String,
+    chapter_title: Option<String>,
+    chapter_number: Option<f64>,
+    manga_title: Option<String>,
+    priority: i64,
+    status: String,
+    attempts: i64,
+    last_error: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl From<DownloadQueueItemRow> for DownloadQueueItem {
+    fn from(value: DownloadQueueItemRow) -> Self {
+        Self {
+            id: value.id,
+            chapter_id: ChapterId::from_strings(value.source_id, value.manga_id, value.chapter_id),
+            chapter_title: value.chapter_title,
+            chapter_number: value.chapter_number,
+            manga_title: value.manga_title,
+            priority: value.priority,
+            status: DownloadQueueStatus::parse(&value.status)
+                .unwrap_or(DownloadQueueStatus::Queued),
+            attempts: value.attempts,
+            last_error: value.last_error,
             created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct QueuedActionRow {
+    action: String,
+    arguments: Option<String>,
+    queued_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use size::Size;
+
+    use super::*;
+    use crate::chapter_storage::ChapterStorage;
+
+    fn chapter_information(manga_id: &MangaId, key: &str) -> ChapterInformation {
+        ChapterInformation {
+            id: ChapterId::new(manga_id.clone(), key.into()),
+            title: None,
+            scanlator: None,
+            chapter_number: None,
+            volume_number: None,
+            last_updated: None,
+            thumbnail: None,
+            lang: None,
+            url: None,
+            locked: None,
         }
     }
+
+    async fn setup(folder: &Path) -> (Database, ChapterStorage) {
+        let db = Database::new(&folder.join("database.db")).await.unwrap();
+        let chapter_storage =
+            ChapterStorage::new(folder.join("downloads"), Size::from_megabytes(1)).unwrap();
+
+        (db, chapter_storage)
+    }
+
+    #[tokio::test]
+    async fn chapters_stored_at_the_legacy_path_are_not_orphans() {
+        let folder = tempfile::tempdir().unwrap();
+        let (db, chapter_storage) = setup(folder.path()).await;
+        let manga_id = MangaId::from_strings("source".into(), "manga".into());
+        let chapter = chapter_information(&manga_id, "1");
+        db.upsert_cached_chapter_informations(&manga_id, &[chapter])
+            .await
+            .unwrap();
+
+        let legacy_path = folder.path().join("downloads").join("source-1.cbz");
+        let orphan_path = folder.path().join("downloads").join("source-2.cbz");
+        std::fs::write(&legacy_path, []).unwrap();
+        std::fs::write(&orphan_path, []).unwrap();
+
+        let orphans = db
+            .find_orphan_or_read_files(&chapter_storage, true)
+            .await
+            .unwrap();
+
+        assert_eq!(orphans, [orphan_path]);
+    }
+
+    #[tokio::test]
+    async fn chapters_of_pinned_mangas_are_not_orphans() {
+        let folder = tempfile::tempdir().unwrap();
+        let (db, chapter_storage) = setup(folder.path()).await;
+        let manga_id = MangaId::from_strings("source".into(), "manga".into());
+        db.set_manga_pinned(&manga_id, true).await.unwrap();
+
+        // The source doesn't list the chapter anymore, only its state is left
+        let chapter_id = ChapterId::new(manga_id.clone(), "1".into());
+        db.upsert_chapter_state(
+            &chapter_id,
+            ChapterState {
+                read: true,
+                last_read: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let path = chapter_storage.get_path_to_store_chapter(&chapter_id, false);
+        std::fs::write(&path, []).unwrap();
+
+        let orphans = db
+            .find_orphan_or_read_files(&chapter_storage, true)
+            .await
+            .unwrap();
+
+        assert!(orphans.is_empty());
+    }
 }
@@ -0,0 +1,225 @@
+// Persistent download queue. Chapters are queued in the database, so queued and
+// interrupted downloads survive restarts, and are downloaded one at a time by
+// `run_download_queue`, which runs for the whole lifetime of the server.
+//
+// Chapters are still committed to storage only by `ensure_chapter_is_in_storage`; the queue
+// just decides what gets downloaded next, and drops items once their chapter is stored.
+// Chapters auto-downloaded by the update check go through the queue too, but never evict
+// other chapters to make room for themselves.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use tokio::sync::{Mutex, Notify};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    chapter_downloader::{self, ensure_chapter_is_in_storage, ProgressCallback},
+    chapter_storage::{ChapterStorage, StorageFullError},
+    database::Database,
+    events::{self, Event},
+    model::{
+        ChapterId, DownloadQueueStatus, NotificationKind, NotificationSubject, QueuedDownload,
+    },
+    settings::Settings,
+    source_collection::SourceCollection,
+    source_manager::SourceManager,
+};
+
+/// Failed downloads are retried, each time after a longer delay, until they fail this many
+/// times in a row.
+const MAX_ATTEMPTS: i64 = 3;
+
+// The queue is also re-checked periodically, in case a change was not notified.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+static QUEUE_CHANGED: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Wakes up the queue worker after items were added or resumed, or the queue was resumed.
+pub fn notify_queue_changed() {
+    QUEUE_CHANGED.notify_one();
+    events::publish(Event::DownloadQueueChanged);
+}
+
+pub async fn run_download_queue(
+    db: Arc<Mutex<Database>>,
+    chapter_storage: Arc<Mutex<ChapterStorage>>,
+    source_manager: Arc<Mutex<SourceManager>>,
+    settings: Arc<Mutex<Settings>>,
+) {
+    if let Err(e) = db.lock().await.requeue_interrupted_downloads().await {
+        eprintln!("Failed to requeue interrupted downloads: {}", e);
+    }
+
+    let token = CancellationToken::new();
+    loop {
+        let next = match take_next_download(&db).await {
+            Ok(next) => next,
+            Err(e) => {
+                eprintln!("Failed to get the next queued download: {}", e);
+                None
+            }
+        };
+
+        let Some(next) = next else {
+            let _ = tokio::time::timeout(IDLE_POLL_INTERVAL, QUEUE_CHANGED.notified()).await;
+            continue;
+        };
+
+        events::publish(Event::DownloadQueueChanged);
+
+        let result = download_queued_chapter(
+            &token,
+            &db,
+            &chapter_storage,
+            &source_manager,
+            &settings,
+            &next.chapter_id,
+            next.auto_download,
+        )
+        .await;
+
+        events::publish(match &result {
+            Ok(_) => Event::DownloadFinished {
+                chapter_id: next.chapter_id.clone(),
+            },
+            Err(e) => Event::DownloadFailed {
+                chapter_id: next.chapter_id.clone(),
+                error: e.to_string(),
+            },
+        });
+
+        let db = db.lock().await;
+        let (update, notification) = match &result {
+            Ok(_) => (
+                db.remove_queued_download(next.id).await,
+                Some((NotificationKind::DownloadFinished, None)),
+            ),
+            Err(e) => {
+                eprintln!(
+                    "Warn[{}]: queued download failed (attempt {}): {:?}",
+                    next.chapter_id.value(),
+                    next.attempts,
+                    e
+                );
+
+                // Retrying won't help an auto-download which doesn't fit in the storage
+                let failed = next.attempts >= MAX_ATTEMPTS
+                    || (next.auto_download && e.downcast_ref::<StorageFullError>().is_some());
+                let status = if failed {
+                    DownloadQueueStatus::Failed
+                } else {
+                    DownloadQueueStatus::Queued
+                };
+
+                (
+                    db.set_download_status(next.id, status, Some(&e.to_string()))
+                        .await,
+                    failed.then(|| (NotificationKind::DownloadFailed, Some(e.to_string()))),
+                )
+            }
+        };
+
+        if let Err(e) = update {
+            eprintln!("Failed to update download queue item {}: {}", next.id, e);
+        }
+
+        // Only auto-downloads are notified, as the user didn't ask for them
+        if next.auto_download {
+            if let Some((kind, message)) = notification {
+                let _ = db
+                    .insert_event_notification(
+                        kind,
+                        NotificationSubject::Chapter(&next.chapter_id),
+                        message.as_deref(),
+                    )
+                    .await;
+            }
+        }
+
+        events::publish(Event::DownloadQueueChanged);
+    }
+}
+
+async fn take_next_download(db: &Mutex<Database>) -> Result<Option<QueuedDownload>> {
+    let db = db.lock().await;
+
+    if db.is_download_queue_paused().await? {
+        return Ok(None);
+    }
+
+    db.take_next_queued_download().await
+}
+
+async fn download_queued_chapter(
+    token: &CancellationToken,
+    db: &Mutex<Database>,
+    chapter_storage: &Mutex<ChapterStorage>,
+    source_manager: &Mutex<SourceManager>,
+    settings: &Mutex<Settings>,
+    chapter_id: &ChapterId,
+    auto_download: bool,
+) -> Result<()> {
+    let source = source_manager
+        .lock()
+        .await
+        .get_by_id(chapter_id.source_id())
+        .cloned()
+        .ok_or_else(|| anyhow!("Missing source {}", chapter_id.source_id().value()))?;
+
+    let (manga, chapter) = {
+        let db = db.lock().await;
+        let manga = db
+            .find_cached_manga_information(chapter_id.manga_id())
+            .await?
+            .ok_or_else(|| anyhow!("Expected manga to be in the database"))?;
+        let chapter = db
+            .find_cached_chapter_information(chapter_id)
+            .await?
+            .ok_or_else(|| anyhow!("Expected chapter to be in the database"))?;
+
+        (manga, chapter)
+    };
+
+    let mut chapter_storage = chapter_storage.lock().await.clone();
+    if auto_download {
+        chapter_storage = chapter_storage.without_eviction();
+    }
+    let (concurrent_requests_pages, optimize_image) = {
+        let settings = settings.lock().await;
+
+        (
+            settings.concurrent_requests_pages.unwrap_or(4),
+            settings.optimize_image,
+        )
+    };
+
+    ensure_chapter_is_in_storage(
+        token,
+        &chapter_storage,
+        &source,
+        &manga,
+        &chapter,
+        concurrent_requests_pages,
+        optimize_image,
+        Some(publish_progress(chapter_id.clone())),
+    )
+    .await
+    .map_err(|e| match e {
+        // Keeps the error downcastable, e.g. to `StorageFullError`
+        chapter_downloader::Error::Other(e) => e,
+        e => e.into(),
+    })?;
+
+    Ok(())
+}
+
+fn publish_progress(chapter_id: ChapterId) -> ProgressCallback {
+    Arc::new(move |progress| {
+        events::publish(Event::DownloadProgress {
+            chapter_id: chapter_id.clone(),
+            progress,
+        })
+    })
+}
@@ -0,0 +1,230 @@
+// Process-wide scheduler for page downloads. Every chapter download asks it for a permit
+// before fetching a page, whatever started it (jobs, the download queue, auto-downloads),
+// so the limits hold across all of them:
+//   - a global limit of pages being downloaded at once
+//   - a concurrency limit per source and per host, and an optional rate limit per host
+//   - the rate limit declared by the source itself through `net.set_rate_limit`
+//   - an optional bandwidth cap, shared by all downloads
+//
+// Tokio's semaphores hand out permits in FIFO order, so concurrent downloads get their pages
+// fetched in turns, instead of the first job starving the others. All downloads also share a
+// single HTTP client (and its connection pool).
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use reqwest::{redirect::Policy, Client};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+use crate::settings::DownloadLimits;
+
+static SCHEDULER: Lazy<DownloadScheduler> =
+    Lazy::new(|| DownloadScheduler::new(DownloadLimits::default()));
+
+pub fn download_scheduler() -> &'static DownloadScheduler {
+    &SCHEDULER
+}
+
+/// A rate limit of `permits` requests every `period`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub permits: usize,
+    pub period: Duration,
+}
+
+/// Allows downloading a single page. The slots are released once it's dropped.
+pub struct PagePermit {
+    _permits: [OwnedSemaphorePermit; 3],
+}
+
+pub struct DownloadScheduler {
+    limits: RwLock<DownloadLimits>,
+    global: RwLock<Arc<Semaphore>>,
+    sources: Mutex<HashMap<String, Arc<Slot>>>,
+    hosts: Mutex<HashMap<String, Arc<Slot>>>,
+    // The instant at which the bandwidth used by previous downloads is paid off.
+    bandwidth_free_at: Mutex<Instant>,
+    client: Client,
+}
+
+impl DownloadScheduler {
+    fn new(limits: DownloadLimits) -> Self {
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .redirect(Policy::none())
+            .build()
+            .expect("failed to build the download HTTP client");
+
+        Self {
+            global: RwLock::new(Arc::new(Semaphore::new(limits.max_concurrent_pages.max(1)))),
+            limits: RwLock::new(limits),
+            sources: Default::default(),
+            hosts: Default::default(),
+            bandwidth_free_at: Mutex::new(Instant::now()),
+            client,
+        }
+    }
+
+    /// Applies new limits. Downloads already in progress keep the slots they hold, so the
+    /// new limits are fully in effect once those are released.
+    pub fn configure(&self, limits: &DownloadLimits) {
+        let mut current = self.limits.write().unwrap();
+        if *current == *limits {
+            return;
+        }
+
+        *current = limits.clone();
+        *self.global.write().unwrap() =
+            Arc::new(Semaphore::new(limits.max_concurrent_pages.max(1)));
+        self.sources.lock().unwrap().clear();
+        self.hosts.lock().unwrap().clear();
+    }
+
+    pub fn client(&self) -> Client {
+        self.client.clone()
+    }
+
+    /// Waits until a page from `url` can be downloaded for the given source.
+    /// `source_rate_limit` is the limit declared by the source, if any.
+    pub async fn acquire(
+        &self,
+        source_id: &str,
+        url: &Url,
+        source_rate_limit: Option<RateLimit>,
+    ) -> PagePermit {
+        let limits = self.limits.read().unwrap().clone();
+        let host_rate_limit = limits
+            .per_host_requests_per_minute
+            .map(|permits| RateLimit {
+                permits,
+                period: Duration::from_secs(60),
+            });
+
+        let source_slot = slot(&self.sources, source_id, limits.per_source_concurrency);
+        let host_slot = slot(
+            &self.hosts,
+            url.host_str().unwrap_or_default(),
+            limits.per_host_concurrency,
+        );
+        let global = self.global.read().unwrap().clone();
+
+        // Always acquired in the same order, so downloads can't deadlock each other
+        let source_permit = acquire_owned(source_slot.semaphore.clone()).await;
+        let host_permit = acquire_owned(host_slot.semaphore.clone()).await;
+        let global_permit = acquire_owned(global).await;
+
+        source_slot.wait_for_rate_limit(source_rate_limit).await;
+        host_slot.wait_for_rate_limit(host_rate_limit).await;
+
+        PagePermit {
+            _permits: [source_permit, host_permit, global_permit],
+        }
+    }
+
+    /// Accounts for `bytes` downloaded, waiting as needed to keep under the bandwidth cap.
+    pub async fn consume_bandwidth(&self, bytes: usize) {
+        let Some(max_kib_per_second) = self.limits.read().unwrap().max_bandwidth_kib_per_second
+        else {
+            return;
+        };
+        let bytes_per_second = max_kib_per_second.max(1) * 1024;
+
+        let wait_until = {
+            let mut free_at = self.bandwidth_free_at.lock().unwrap();
+            let start = (*free_at).max(Instant::now());
+            *free_at = start + Duration::from_secs_f64(bytes as f64 / bytes_per_second as f64);
+
+            start
+        };
+
+        tokio::time::sleep_until(wait_until.into()).await;
+    }
+}
+
+struct Slot {
+    semaphore: Arc<Semaphore>,
+    rate_limiter: Mutex<Option<RateLimiter>>,
+}
+
+impl Slot {
+    async fn wait_for_rate_limit(&self, rate_limit: Option<RateLimit>) {
+        let Some(rate_limit) = rate_limit else {
+            return;
+        };
+
+        loop {
+            let wait = {
+                let mut rate_limiter = self.rate_limiter.lock().unwrap();
+                if !matches!(&*rate_limiter, Some(current) if current.limit == rate_limit) {
+                    *rate_limiter = Some(RateLimiter::new(rate_limit));
+                }
+
+                rate_limiter.as_mut().unwrap().reserve()
+            };
+
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+}
+
+fn slot(slots: &Mutex<HashMap<String, Arc<Slot>>>, key: &str, concurrency: usize) -> Arc<Slot> {
+    slots
+        .lock()
+        .unwrap()
+        .entry(key.to_owned())
+        .or_insert_with(|| {
+            Arc::new(Slot {
+                semaphore: Arc::new(Semaphore::new(concurrency.max(1))),
+                rate_limiter: Mutex::new(None),
+            })
+        })
+        .clone()
+}
+
+async fn acquire_owned(semaphore: Arc<Semaphore>) -> OwnedSemaphorePermit {
+    // The semaphores are never closed
+    semaphore.acquire_owned().await.unwrap()
+}
+
+// Fixed window rate limiter, the same as the one used for the requests made by sources.
+struct RateLimiter {
+    limit: RateLimit,
+    window_start: Instant,
+    available: usize,
+}
+
+impl RateLimiter {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            window_start: Instant::now(),
+            available: limit.permits,
+        }
+    }
+
+    /// Takes a permit, or returns how long to wait until the next window if there are none.
+    fn reserve(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= self.limit.period {
+            self.window_start = now;
+            self.available = self.limit.permits;
+        }
+
+        if self.available > 0 {
+            self.available -= 1;
+
+            return None;
+        }
+
+        Some((self.window_start + self.limit.period).saturating_duration_since(now))
+    }
+}
@@ -0,0 +1,77 @@
+// In-process event bus for background activity (downloads, update checks, notifications,
+// sync). Producers call `publish` wherever something happens; the server forwards every
+// event to the clients connected to `/events`, so they don't have to poll for progress.
+//
+// Publishing never blocks and never fails: when nobody is subscribed the event is dropped,
+// and slow subscribers just miss the oldest events (see `tokio::sync::broadcast`).
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::{
+    chapter_downloader::DownloadProgress,
+    model::{ChapterId, MangaId, NotificationKind, UpdateRunTrigger},
+    usecases::sync_database::SyncResult,
+};
+
+const EVENT_BUFFER_SIZE: usize = 256;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Event {
+    /// A job's progress changed. `detail` has the same shape as `GET /jobs/{id}`.
+    JobProgress {
+        job_id: String,
+        detail: Value,
+    },
+    JobFinished {
+        job_id: String,
+        detail: Value,
+    },
+    /// A page of a queued chapter finished downloading.
+    DownloadProgress {
+        chapter_id: ChapterId,
+        progress: DownloadProgress,
+    },
+    DownloadFinished {
+        chapter_id: ChapterId,
+    },
+    DownloadFailed {
+        chapter_id: ChapterId,
+        error: String,
+    },
+    /// The download queue changed (items added, removed or finished, or the queue was paused
+    /// or resumed). `GET /download-queue` has the new state.
+    DownloadQueueChanged,
+    MangaUpdateChecked {
+        manga_id: MangaId,
+        new_chapters: usize,
+        error: Option<String>,
+    },
+    UpdateRunFinished {
+        run_id: Option<i64>,
+        trigger: UpdateRunTrigger,
+    },
+    NotificationCreated {
+        kind: NotificationKind,
+        manga_id: Option<MangaId>,
+    },
+    SyncStatus {
+        result: Option<SyncResult>,
+        error: Option<String>,
+    },
+}
+
+static EVENTS: Lazy<broadcast::Sender<Event>> =
+    Lazy::new(|| broadcast::channel(EVENT_BUFFER_SIZE).0);
+
+pub fn publish(event: Event) {
+    // Only fails when there are no subscribers, in which case nobody cares about it
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    EVENTS.subscribe()
+}
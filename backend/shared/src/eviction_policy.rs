@@ -0,0 +1,115 @@
+// Eviction policy backed by the database, used when the chapter storage runs out of room.
+// Files are evicted in this order, least recently modified first within each group:
+//   1. posters of manga outside the library, and chapters the database doesn't know about
+//   2. read chapters
+//   3. unread chapters of manga outside the library
+//
+// Pinned chapters (or chapters of pinned manga), unread chapters of library manga, locked
+// chapters (which may not be downloadable again), chapters waiting in the download queue and
+// prefetched chapters are never evicted.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use futures::{future::BoxFuture, FutureExt};
+
+use crate::{
+    chapter_storage::{ChapterStorage, EvictionPolicy, StoredChapterFile},
+    database::Database,
+    model::ChapterStorageEntry,
+};
+
+pub struct DatabaseEvictionPolicy {
+    // Queried through its own connection pool, as the storage may be asked for room while
+    // the database lock is held (e.g. when downloading a chapter from a request).
+    db: Database,
+}
+
+impl DatabaseEvictionPolicy {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EvictionGroup {
+    Unused,
+    Read,
+    OutsideLibrary,
+}
+
+impl EvictionPolicy for DatabaseEvictionPolicy {
+    fn eviction_order<'a>(
+        &'a self,
+        chapter_storage: &'a ChapterStorage,
+    ) -> BoxFuture<'a, Result<Vec<PathBuf>>> {
+        async move {
+            let entries: HashMap<_, _> = self
+                .db
+                .find_chapter_storage_entries()
+                .await?
+                .into_iter()
+                .map(|entry| (entry.id.clone(), entry))
+                .collect();
+            let library_posters: HashSet<_> = self
+                .db
+                .find_library_cover_urls()
+                .await?
+                .iter()
+                .map(ChapterStorage::poster_file_stem)
+                .collect();
+
+            let mut files: Vec<(EvictionGroup, SystemTime, PathBuf)> = Vec::new();
+
+            for poster in chapter_storage.stored_poster_files() {
+                let stem = poster.path.file_stem().map(|stem| stem.to_string_lossy());
+                if stem.is_some_and(|stem| library_posters.contains(&*stem)) {
+                    continue;
+                }
+
+                // Posters are small and downloaded again when needed, so their age doesn't matter
+                files.push((EvictionGroup::Unused, SystemTime::UNIX_EPOCH, poster.path));
+            }
+
+            for chapter in chapter_storage.stored_chapter_files(entries.keys()) {
+                let entry = chapter.chapter_id.as_ref().and_then(|id| entries.get(id));
+
+                if let Some(group) = eviction_group(&chapter, entry) {
+                    files.push((group, chapter.modified, chapter.path));
+                }
+            }
+
+            files.sort_by_key(|(group, modified, _)| (*group, *modified));
+
+            Ok(files.into_iter().map(|(_, _, path)| path).collect())
+        }
+        .boxed()
+    }
+}
+
+fn eviction_group(
+    chapter: &StoredChapterFile,
+    entry: Option<&ChapterStorageEntry>,
+) -> Option<EvictionGroup> {
+    if chapter.prefetched {
+        return None;
+    }
+
+    let Some(entry) = entry else {
+        return Some(EvictionGroup::Unused);
+    };
+
+    if entry.pinned || entry.locked || entry.queued {
+        None
+    } else if entry.read {
+        Some(EvictionGroup::Read)
+    } else if !entry.in_library {
+        Some(EvictionGroup::OutsideLibrary)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,581 @@
+// Disk cache for the HTTP requests made by sources, opt-in through the `http_cache` settings.
+// Only GET and HEAD requests are cached, and only for the operations with a max-age. Responses
+// are keyed by everything that makes up the request (method, URL, headers and body) and stay
+// fresh for the max-age configured for the operation that made the request, or less if the
+// server asks for it through `Cache-Control`. Once stale, they're revalidated with the
+// server through their `ETag`/`Last-Modified` when possible, and served as they are when
+// there's no connection, so that sources keep working offline for what was already visited.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED,
+    },
+    Method, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+use walkdir::WalkDir;
+
+use crate::{
+    settings::HttpCacheSettings,
+    source::{RequestBuildingState, ResponseData, SourceOperation},
+};
+
+static HTTP_CACHE: Lazy<HttpCache> = Lazy::new(|| HttpCache {
+    folder: RwLock::new(None),
+    settings: RwLock::new(HttpCacheSettings::default()),
+    cached_size: Mutex::new(None),
+});
+
+pub fn http_cache() -> &'static HttpCache {
+    &HTTP_CACHE
+}
+
+pub struct HttpCache {
+    folder: RwLock<Option<PathBuf>>,
+    settings: RwLock<HttpCacheSettings>,
+    cached_size: Mutex<Option<u64>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    url: String,
+    status_code: u16,
+    headers: Vec<(String, Vec<u8>)>,
+    body: Option<Vec<u8>>,
+    // Seconds since the epoch at which the response was fetched, or last revalidated.
+    stored_at: u64,
+}
+
+impl HttpCache {
+    /// Sets the folder where responses are stored. The cache stays disabled until it's set.
+    pub fn set_folder(&self, folder: PathBuf) {
+        *self.folder.write().unwrap() = Some(folder);
+        *self.cached_size.lock().unwrap() = None;
+    }
+
+    pub fn configure(&self, settings: &HttpCacheSettings) {
+        *self.settings.write().unwrap() = settings.clone();
+    }
+
+    /// Sends a request made by a source through the cache. `fetch` makes the actual request,
+    /// and is only called when no fresh response is cached. `is_online` is checked before
+    /// that, so stale responses are served right away when there's no connection.
+    pub fn send(
+        &self,
+        source_id: &str,
+        operation: SourceOperation,
+        request: &RequestBuildingState,
+        is_online: impl FnOnce() -> Result<bool>,
+        fetch: impl FnOnce(&RequestBuildingState) -> Result<ResponseData>,
+    ) -> Result<ResponseData> {
+        let settings = self.settings.read().unwrap().clone();
+        let folder = self.folder.read().unwrap().clone();
+        let max_age = Duration::from_secs(match operation {
+            SourceOperation::MangaDetails => settings.manga_details_max_age_seconds,
+            SourceOperation::ChapterList => settings.chapter_list_max_age_seconds,
+            SourceOperation::PageList => settings.page_list_max_age_seconds,
+            SourceOperation::Other => settings.other_max_age_seconds,
+        });
+
+        let Some(folder) = folder
+            .filter(|_| settings.enabled && !max_age.is_zero() && is_cacheable_method(request))
+        else {
+            if !is_online()? {
+                bail!("no internet connection available");
+            }
+
+            return fetch(request);
+        };
+
+        let path = folder
+            .join(sanitize_filename::sanitize(source_id))
+            .join(format!("{}.bin", cache_key(request)));
+        let cached = read_cached_response(&path);
+
+        if let Some(cached) = cached.as_ref().filter(|cached| cached.is_fresh(max_age)) {
+            debug!("http cache: fresh hit for {}", cached.url);
+
+            return cached.to_response_data();
+        }
+
+        if !is_online()? {
+            return match cached {
+                Some(cached) => {
+                    debug!("http cache: offline, serving stale {}", cached.url);
+
+                    cached.to_response_data()
+                }
+                None => bail!("no internet connection available"),
+            };
+        }
+
+        let mut conditional_request = request.clone();
+        let revalidating = cached
+            .as_ref()
+            .is_some_and(|cached| cached.add_validators(&mut conditional_request));
+
+        let response = match fetch(&conditional_request) {
+            Ok(response) => response,
+            Err(e) => {
+                let Some(cached) = cached else {
+                    return Err(e);
+                };
+                warn!(
+                    "http cache: request failed, serving stale {}: {e:?}",
+                    cached.url
+                );
+
+                return cached.to_response_data();
+            }
+        };
+
+        if let Some(mut cached) =
+            cached.filter(|_| revalidating && response.status_code == StatusCode::NOT_MODIFIED)
+        {
+            debug!("http cache: revalidated {}", cached.url);
+            cached.refresh(&response.headers);
+            self.store(&path, &cached, &settings);
+
+            return cached.to_response_data();
+        }
+
+        if is_cacheable(request, &response) {
+            self.store(&path, &CachedResponse::from(&response), &settings);
+        }
+
+        Ok(response)
+    }
+
+    fn store(&self, path: &Path, response: &CachedResponse, settings: &HttpCacheSettings) {
+        if let Err(e) = self.write_cached_response(path, response, settings) {
+            warn!("http cache: couldn't store {}: {e:?}", response.url);
+        }
+    }
+
+    fn write_cached_response(
+        &self,
+        path: &Path,
+        response: &CachedResponse,
+        settings: &HttpCacheSettings,
+    ) -> Result<()> {
+        let folder = path
+            .parent()
+            .ok_or_else(|| anyhow!("cache entry has no parent folder"))?;
+        fs::create_dir_all(folder)?;
+
+        let bytes = postcard::to_allocvec(response)?;
+        let mut cached_size = self.cached_size.lock().unwrap();
+        let current_size = cached_size.unwrap_or_else(|| self.compute_size());
+        let previous_size = fs::metadata(path).map(|m| m.len()).unwrap_or_default();
+
+        // Written to a temporary file first, so that other sources never read a partial entry
+        let temporary_path = path.with_extension("tmp");
+        fs::write(&temporary_path, &bytes)?;
+        fs::rename(&temporary_path, path)?;
+
+        let size = current_size.saturating_sub(previous_size) + bytes.len() as u64;
+        *cached_size = Some(size);
+        drop(cached_size);
+
+        if size > settings.max_size_mb * 1024 * 1024 {
+            self.prune(settings.max_size_mb * 1024 * 1024);
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Some(folder) = self.folder.read().unwrap().clone() else {
+            return Vec::new();
+        };
+
+        WalkDir::new(folder)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+
+                Some((entry.into_path(), metadata.len(), modified))
+            })
+            .collect()
+    }
+
+    fn compute_size(&self) -> u64 {
+        self.entries().iter().map(|(_, size, _)| size).sum()
+    }
+
+    // Removes the least recently stored responses until the cache takes at most 90% of
+    // `max_size`, so that pruning doesn't happen again on the next few responses.
+    fn prune(&self, max_size: u64) {
+        let mut entries = self.entries();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        let target_size = max_size / 10 * 9;
+        let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+
+        for (path, entry_size, _) in entries {
+            if size <= target_size {
+                break;
+            }
+
+            match fs::remove_file(&path) {
+                Ok(()) => size -= entry_size,
+                Err(e) => warn!("http cache: couldn't remove {}: {e}", path.display()),
+            }
+        }
+
+        *self.cached_size.lock().unwrap() = Some(size);
+    }
+}
+
+impl CachedResponse {
+    fn header(&self, name: &HeaderName) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name.as_str()))
+            .and_then(|(_, value)| std::str::from_utf8(value).ok())
+    }
+
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        let max_age = match self.header(&CACHE_CONTROL).map(CacheControl::parse) {
+            Some(cache_control) if cache_control.no_cache => Duration::ZERO,
+            Some(CacheControl {
+                max_age: Some(server_max_age),
+                ..
+            }) => max_age.min(server_max_age),
+            _ => max_age,
+        };
+
+        now().saturating_sub(self.stored_at) < max_age.as_secs()
+    }
+
+    /// Adds the headers needed to revalidate this response to `request`, unless the source
+    /// already set them. Returns whether the request is now conditional.
+    fn add_validators(&self, request: &mut RequestBuildingState) -> bool {
+        let has_header = |name: &HeaderName| {
+            request
+                .headers
+                .keys()
+                .any(|header| header.eq_ignore_ascii_case(name.as_str()))
+        };
+        if has_header(&IF_NONE_MATCH) || has_header(&IF_MODIFIED_SINCE) {
+            return false;
+        }
+
+        let validators = [(ETAG, IF_NONE_MATCH), (LAST_MODIFIED, IF_MODIFIED_SINCE)];
+        let mut added = false;
+        for (validator, conditional_header) in validators {
+            if let Some(value) = self.header(&validator) {
+                request
+                    .headers
+                    .insert(conditional_header.to_string(), value.to_owned());
+                added = true;
+            }
+        }
+
+        added
+    }
+
+    /// Updates the response after the server confirmed it's still valid, with the headers
+    /// it sent along (e.g. a new `Cache-Control`).
+    fn refresh(&mut self, headers: &HeaderMap) {
+        for name in headers.keys() {
+            self.headers
+                .retain(|(header, _)| !header.eq_ignore_ascii_case(name.as_str()));
+        }
+        self.headers.extend(
+            headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec())),
+        );
+        self.stored_at = now();
+    }
+
+    fn to_response_data(&self) -> Result<ResponseData> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_bytes(value)?,
+            );
+        }
+
+        Ok(ResponseData {
+            url: Url::parse(&self.url)?,
+            status_code: StatusCode::from_u16(self.status_code)?,
+            headers,
+            body: self.body.clone(),
+            bytes_read: 0,
+        })
+    }
+}
+
+impl From<&ResponseData> for CachedResponse {
+    fn from(response: &ResponseData) -> Self {
+        Self {
+            url: response.url.to_string(),
+            status_code: response.status_code.as_u16(),
+            headers: response
+                .headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+                .collect(),
+            body: response.body.clone(),
+            stored_at: now(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<Duration>,
+}
+
+impl CacheControl {
+    fn parse(value: &str) -> Self {
+        let mut cache_control = Self::default();
+
+        for directive in value.split(',').map(|directive| directive.trim()) {
+            let (name, argument) = directive
+                .split_once('=')
+                .map_or((directive, None), |(name, argument)| (name, Some(argument)));
+
+            match name.to_ascii_lowercase().as_str() {
+                "no-store" => cache_control.no_store = true,
+                "no-cache" => cache_control.no_cache = true,
+                "max-age" => {
+                    cache_control.max_age = argument
+                        .and_then(|argument| argument.trim_matches('"').parse().ok())
+                        .map(Duration::from_secs)
+                }
+                _ => {}
+            }
+        }
+
+        cache_control
+    }
+
+    fn of(headers: impl IntoIterator<Item = String>) -> Self {
+        Self::parse(&headers.into_iter().collect::<Vec<_>>().join(","))
+    }
+}
+
+fn is_cacheable_method(request: &RequestBuildingState) -> bool {
+    matches!(request.method, Some(Method::GET | Method::HEAD))
+}
+
+fn is_cacheable(request: &RequestBuildingState, response: &ResponseData) -> bool {
+    let request_cache_control = CacheControl::of(
+        request
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(CACHE_CONTROL.as_str()))
+            .map(|(_, value)| value.clone()),
+    );
+    let response_cache_control = CacheControl::of(
+        response
+            .headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(str::to_owned),
+    );
+
+    is_cacheable_method(request)
+        && response.status_code.is_success()
+        && response.body.is_some()
+        && !request_cache_control.no_store
+        && !response_cache_control.no_store
+}
+
+fn cache_key(request: &RequestBuildingState) -> String {
+    let mut headers: Vec<_> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value))
+        .collect();
+    headers.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(
+        request
+            .method
+            .as_ref()
+            .map(|m| m.as_str())
+            .unwrap_or_default(),
+    );
+    hasher.update([0]);
+    hasher.update(request.url.as_ref().map(|u| u.as_str()).unwrap_or_default());
+    hasher.update([0]);
+    for (name, value) in headers {
+        hasher.update(name);
+        hasher.update([b':']);
+        hasher.update(value);
+        hasher.update([0]);
+    }
+    hasher.update([0]);
+    if let Some(body) = &request.body {
+        hasher.update(body);
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+fn read_cached_response(path: &Path) -> Option<CachedResponse> {
+    let bytes = fs::read(path).ok()?;
+
+    match postcard::from_bytes(&bytes) {
+        Ok(response) => Some(response),
+        Err(e) => {
+            warn!(
+                "http cache: ignoring unreadable entry {}: {e}",
+                path.display()
+            );
+
+            None
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn request(method: Method, url: &str, headers: &[(&str, &str)]) -> RequestBuildingState {
+        RequestBuildingState {
+            url: Some(Url::parse(url).unwrap()),
+            method: Some(method),
+            body: None,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>(),
+            timeout: None,
+        }
+    }
+
+    fn cached_response(cache_control: Option<&str>, age: u64) -> CachedResponse {
+        CachedResponse {
+            url: "https://example.com/".into(),
+            status_code: 200,
+            headers: cache_control
+                .map(|value| ("Cache-Control".to_owned(), value.as_bytes().to_vec()))
+                .into_iter()
+                .collect(),
+            body: Some(Vec::new()),
+            stored_at: now() - age,
+        }
+    }
+
+    #[test]
+    fn parses_cache_control_directives() {
+        let cache_control = CacheControl::parse("public, Max-Age=\"60\", no-cache");
+        assert!(cache_control.no_cache);
+        assert!(!cache_control.no_store);
+        assert_eq!(cache_control.max_age, Some(Duration::from_secs(60)));
+
+        let cache_control = CacheControl::parse("no-store,max-age=abc");
+        assert!(cache_control.no_store);
+        assert_eq!(cache_control.max_age, None);
+    }
+
+    #[test]
+    fn cache_key_ignores_header_order_and_case() {
+        let a = request(
+            Method::GET,
+            "https://example.com/a",
+            &[("Accept", "text/html"), ("Referer", "https://example.com")],
+        );
+        let b = request(
+            Method::GET,
+            "https://example.com/a",
+            &[("referer", "https://example.com"), ("accept", "text/html")],
+        );
+
+        assert_eq!(cache_key(&a), cache_key(&b));
+    }
+
+    #[test]
+    fn cache_key_depends_on_the_whole_request() {
+        let get = request(Method::GET, "https://example.com/a", &[]);
+        let mut with_body = get.clone();
+        with_body.body = Some(b"page=2".to_vec());
+
+        assert_ne!(
+            cache_key(&get),
+            cache_key(&request(Method::HEAD, "https://example.com/a", &[]))
+        );
+        assert_ne!(
+            cache_key(&get),
+            cache_key(&request(Method::GET, "https://example.com/b", &[]))
+        );
+        assert_ne!(
+            cache_key(&get),
+            cache_key(&request(
+                Method::GET,
+                "https://example.com/a",
+                &[("A", "b")]
+            ))
+        );
+        assert_ne!(cache_key(&get), cache_key(&with_body));
+    }
+
+    #[test]
+    fn freshness_follows_the_shortest_max_age() {
+        let max_age = Duration::from_secs(60);
+
+        assert!(cached_response(None, 30).is_fresh(max_age));
+        assert!(!cached_response(None, 90).is_fresh(max_age));
+        assert!(!cached_response(Some("max-age=10"), 30).is_fresh(max_age));
+        assert!(cached_response(Some("max-age=600"), 30).is_fresh(max_age));
+        assert!(!cached_response(Some("no-cache"), 0).is_fresh(max_age));
+    }
+
+    #[test]
+    fn only_caches_get_and_head_requests() {
+        let response = ResponseData {
+            url: Url::parse("https://example.com/").unwrap(),
+            status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Some(Vec::new()),
+            bytes_read: 0,
+        };
+
+        for method in [Method::GET, Method::HEAD] {
+            assert!(is_cacheable(
+                &request(method, "https://example.com/", &[]),
+                &response
+            ));
+        }
+        assert!(!is_cacheable(
+            &request(Method::POST, "https://example.com/", &[]),
+            &response
+        ));
+    }
+}
@@ -0,0 +1,480 @@
+// Record/replay of the HTTP requests made for sources, so that they can be exercised without
+// depending on live sites (e.g. in CI). When recording, the requests sources make through their
+// `net` imports, as well as the page image requests, are sent as usual and saved along with their
+// responses to a fixtures folder. When replaying, responses are served from that folder instead,
+// and a request without a fixture fails without the network ever being involved.
+//
+// Fixtures are keyed by the method, URL and body of the request. Headers are left out, as they
+// often carry things like timestamps or tokens that change from one run to the other. Each one is
+// stored as a JSON file, so that they can be reviewed (and edited) by hand.
+
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use once_cell::sync::Lazy;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Method, Request, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::source::{RequestBuildingState, ResponseData};
+
+// Page images aren't requested by the sources themselves, so they're kept apart.
+const IMAGES_FOLDER: &str = "_images";
+
+static HTTP_FIXTURES: Lazy<HttpFixtures> = Lazy::new(|| HttpFixtures {
+    mode: RwLock::new(FixturesMode::Off),
+});
+
+pub fn http_fixtures() -> &'static HttpFixtures {
+    &HTTP_FIXTURES
+}
+
+pub struct HttpFixtures {
+    mode: RwLock<FixturesMode>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FixturesMode {
+    #[default]
+    Off,
+    /// Requests are sent, and saved along with their responses to the given folder.
+    Record(PathBuf),
+    /// Responses are served from the given folder, without sending anything.
+    Replay(PathBuf),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    request: FixtureRequest,
+    response: FixtureResponse,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FixtureRequest {
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<FixtureBody>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FixtureResponse {
+    url: String,
+    status_code: u16,
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<FixtureBody>,
+}
+
+// Bodies are stored as text when possible, so that fixtures stay readable.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum FixtureBody {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl HttpFixtures {
+    pub fn set_mode(&self, mode: FixturesMode) {
+        *self.mode.write().unwrap() = mode;
+    }
+
+    pub fn mode(&self) -> FixturesMode {
+        self.mode.read().unwrap().clone()
+    }
+
+    /// Sends a request made by a source. `send` makes the actual request, and isn't called when
+    /// replaying.
+    pub fn send(
+        &self,
+        source_id: &str,
+        request: &RequestBuildingState,
+        send: impl FnOnce(&RequestBuildingState) -> Result<ResponseData>,
+    ) -> Result<ResponseData> {
+        let (folder, record) = match self.mode() {
+            FixturesMode::Off => return send(request),
+            FixturesMode::Record(folder) => (folder, true),
+            FixturesMode::Replay(folder) => (folder, false),
+        };
+        let method = request.method.clone().unwrap_or_default();
+        let url = request.url.as_ref().context("request has no URL")?;
+        let fixture_path = fixture_path(
+            &folder.join(sanitize_filename::sanitize(source_id)),
+            &method,
+            url,
+            request.body.as_deref(),
+        );
+
+        if !record {
+            return read_fixture(&fixture_path)?.response_data();
+        }
+
+        let response = send(request)?;
+        let fixture = Fixture {
+            request: FixtureRequest::new(&method, url, request.body.as_deref()),
+            response: FixtureResponse::new(
+                &response.url,
+                response.status_code,
+                &response.headers,
+                response.body.as_deref(),
+            ),
+        };
+        write_fixture(&fixture_path, &fixture)?;
+
+        Ok(response)
+    }
+
+    /// Sends a page image request. `send` makes the actual request, and isn't called when
+    /// replaying. When recording, the body of the response is read in full so that it can be
+    /// saved.
+    pub async fn send_image<F>(
+        &self,
+        request: Request,
+        send: impl FnOnce(Request) -> F,
+    ) -> Result<reqwest::Response>
+    where
+        F: Future<Output = Result<reqwest::Response>>,
+    {
+        let (folder, record) = match self.mode() {
+            FixturesMode::Off => return send(request).await,
+            FixturesMode::Record(folder) => (folder, true),
+            FixturesMode::Replay(folder) => (folder, false),
+        };
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(Vec::from);
+        let fixture_path =
+            fixture_path(&folder.join(IMAGES_FOLDER), &method, &url, body.as_deref());
+
+        if !record {
+            return read_fixture(&fixture_path)?.reqwest_response();
+        }
+
+        let response = send(request).await?;
+        let response_url = response.url().clone();
+        let status_code = response.status();
+        let headers = response.headers().clone();
+        let response_body = response
+            .bytes()
+            .await
+            .context("failed to read response bytes")?;
+
+        let fixture = Fixture {
+            request: FixtureRequest::new(&method, &url, body.as_deref()),
+            response: FixtureResponse::new(
+                &response_url,
+                status_code,
+                &headers,
+                Some(&response_body),
+            ),
+        };
+        write_fixture(&fixture_path, &fixture)?;
+
+        // The body was consumed to be saved, so the response is rebuilt from the fixture
+        fixture.reqwest_response()
+    }
+}
+
+impl Fixture {
+    fn response_data(&self) -> Result<ResponseData> {
+        Ok(ResponseData {
+            url: Url::parse(&self.response.url).context("invalid URL in fixture")?,
+            status_code: self.response.status_code()?,
+            headers: self.response.headers()?,
+            body: self
+                .response
+                .body
+                .as_ref()
+                .map(FixtureBody::bytes)
+                .transpose()?,
+            bytes_read: 0,
+        })
+    }
+
+    fn reqwest_response(&self) -> Result<reqwest::Response> {
+        let mut response = http::Response::new(
+            self.response
+                .body
+                .as_ref()
+                .map(FixtureBody::bytes)
+                .transpose()?
+                .unwrap_or_default(),
+        );
+        *response.status_mut() = self.response.status_code()?;
+        *response.headers_mut() = self.response.headers()?;
+
+        Ok(response.into())
+    }
+}
+
+impl FixtureRequest {
+    fn new(method: &Method, url: &Url, body: Option<&[u8]>) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            body: body.map(FixtureBody::new),
+        }
+    }
+}
+
+impl FixtureResponse {
+    fn new(url: &Url, status_code: StatusCode, headers: &HeaderMap, body: Option<&[u8]>) -> Self {
+        Self {
+            url: url.to_string(),
+            status_code: status_code.as_u16(),
+            headers: headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+                })
+                .collect(),
+            body: body.map(FixtureBody::new),
+        }
+    }
+
+    fn status_code(&self) -> Result<StatusCode> {
+        StatusCode::from_u16(self.status_code).context("invalid status code in fixture")
+    }
+
+    fn headers(&self) -> Result<HeaderMap> {
+        self.headers
+            .iter()
+            .map(|(name, value)| {
+                Ok((
+                    HeaderName::try_from(name.as_str())?,
+                    HeaderValue::try_from(value.as_str())?,
+                ))
+            })
+            .collect::<Result<_>>()
+            .context("invalid header in fixture")
+    }
+}
+
+impl FixtureBody {
+    fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(text.to_owned()),
+            Err(_) => Self::Binary {
+                base64: BASE64.encode(bytes),
+            },
+        }
+    }
+
+    fn bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            Self::Text(text) => Ok(text.clone().into_bytes()),
+            Self::Binary { base64 } => BASE64
+                .decode(base64)
+                .context("invalid base64 body in fixture"),
+        }
+    }
+}
+
+fn fixture_path(folder: &Path, method: &Method, url: &Url, body: Option<&[u8]>) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update([0]);
+    hasher.update(url.as_str());
+    hasher.update([0]);
+    hasher.update(body.unwrap_or_default());
+
+    folder.join(format!("{:x}.json", hasher.finalize()))
+}
+
+fn read_fixture(path: &Path) -> Result<Fixture> {
+    let contents = fs::read(path).map_err(|_| {
+        anyhow!(
+            "no fixture recorded for this request (expected at {})",
+            path.display()
+        )
+    })?;
+
+    serde_json::from_slice(&contents)
+        .with_context(|| format!("invalid fixture at {}", path.display()))
+}
+
+fn write_fixture(path: &Path, fixture: &Fixture) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(fixture)?)
+        .with_context(|| format!("failed to write fixture at {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, sync::Arc};
+
+    use tokio_util::sync::CancellationToken;
+    use zip::{CompressionMethod, ZipWriter};
+
+    use crate::{settings::Settings, source::Source, source_manager::SourceManager};
+
+    use super::*;
+
+    // A source whose `get_page_list` requests `https://example.com/chapter/1`, and returns a
+    // single page with the body of the response as its URL.
+    const PAGE_LIST_SOURCE: &str = r#"
+        (module
+          (import "net" "init" (func $init (param i32) (result i32)))
+          (import "net" "set_url" (func $set_url (param i32 i32 i32)))
+          (import "net" "send" (func $send (param i32)))
+          (import "net" "get_data_size" (func $get_data_size (param i32) (result i32)))
+          (import "net" "get_data" (func $get_data (param i32 i32 i32)))
+          (import "net" "close" (func $close (param i32)))
+          (import "aidoku" "create_page"
+            (func $create_page (param i32 i32 i32 i32 i32 i32 i32) (result i32)))
+          (import "std" "create_array" (func $create_array (result i32)))
+          (import "std" "array_append" (func $array_append (param i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "https://example.com/chapter/1")
+          (func (export "get_page_list") (param $chapter i32) (result i32)
+            (local $request i32)
+            (local $size i32)
+            (local $pages i32)
+            (local.set $request (call $init (i32.const 0)))
+            (call $set_url (local.get $request) (i32.const 0) (i32.const 29))
+            (call $send (local.get $request))
+            (local.set $size (call $get_data_size (local.get $request)))
+            (call $get_data (local.get $request) (i32.const 1024) (local.get $size))
+            (call $close (local.get $request))
+            (local.set $pages (call $create_array))
+            (call $array_append
+              (local.get $pages)
+              (call $create_page
+                (i32.const 0)
+                (i32.const 1024) (local.get $size)
+                (i32.const 0) (i32.const 0)
+                (i32.const 0) (i32.const 0)))
+            (local.get $pages)))
+    "#;
+
+    fn write_source(path: &Path) {
+        let mut writer = ZipWriter::new(fs::File::create(path).unwrap());
+        let options: zip::write::FileOptions<'_, ()> =
+            zip::write::FileOptions::default().compression_method(CompressionMethod::Stored);
+
+        writer.start_file("Payload/source.json", options).unwrap();
+        writer
+            .write_all(
+                br#"{"info": {"id": "en.fixtures", "name": "Fixtures", "version": 1, "url": "https://example.com"}}"#,
+            )
+            .unwrap();
+        writer.start_file("Payload/main.wasm", options).unwrap();
+        writer.write_all(PAGE_LIST_SOURCE.as_bytes()).unwrap();
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn replays_recorded_responses_without_sending() {
+        let folder = tempfile::tempdir().unwrap();
+        let fixtures = HttpFixtures {
+            mode: RwLock::new(FixturesMode::Record(folder.path().to_owned())),
+        };
+        let request = RequestBuildingState {
+            url: Some(Url::parse("https://example.com/search?q=one").unwrap()),
+            method: Some(Method::POST),
+            body: Some(b"page=1".to_vec()),
+            ..Default::default()
+        };
+
+        fixtures
+            .send("en.example", &request, |request| {
+                Ok(ResponseData {
+                    url: request.url.clone().unwrap(),
+                    status_code: StatusCode::OK,
+                    headers: HeaderMap::from_iter([(
+                        reqwest::header::CONTENT_TYPE,
+                        HeaderValue::from_static("text/html"),
+                    )]),
+                    body: Some(b"<html></html>".to_vec()),
+                    bytes_read: 0,
+                })
+            })
+            .unwrap();
+
+        fixtures.set_mode(FixturesMode::Replay(folder.path().to_owned()));
+        let response = fixtures
+            .send("en.example", &request, |_| panic!("request was sent"))
+            .unwrap();
+        assert_eq!(response.status_code, StatusCode::OK);
+        assert_eq!(response.headers["content-type"], "text/html");
+        assert_eq!(response.body.as_deref(), Some(&b"<html></html>"[..]));
+
+        let other_request = RequestBuildingState {
+            body: Some(b"page=2".to_vec()),
+            ..request
+        };
+        assert!(fixtures
+            .send("en.example", &other_request, |_| panic!("request was sent"))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_responses_through_a_source() {
+        let folder = tempfile::tempdir().unwrap();
+        let fixtures_folder = folder.path().join("fixtures");
+        let sources_folder = folder.path().join("sources");
+
+        // Recorded as if the source had sent the request
+        let request = RequestBuildingState {
+            url: Some(Url::parse("https://example.com/chapter/1").unwrap()),
+            method: Some(Method::GET),
+            ..Default::default()
+        };
+        let recorder = HttpFixtures {
+            mode: RwLock::new(FixturesMode::Record(fixtures_folder.clone())),
+        };
+        recorder
+            .send("en.fixtures", &request, |request| {
+                Ok(ResponseData {
+                    url: request.url.clone().unwrap(),
+                    status_code: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    body: Some(b"https://example.com/pages/1.png".to_vec()),
+                    bytes_read: 0,
+                })
+            })
+            .unwrap();
+
+        let source_manager = Arc::new(tokio::sync::Mutex::new(
+            SourceManager::from_folder(sources_folder.clone(), Settings::default()).unwrap(),
+        ));
+        let source_path = sources_folder.join("en.fixtures.aix");
+        write_source(&source_path);
+        let source =
+            Source::from_aix_file(&source_path, &*source_manager.lock().await, &source_manager)
+                .unwrap();
+
+        http_fixtures().set_mode(FixturesMode::Replay(fixtures_folder));
+        let pages = source
+            .get_page_list(
+                CancellationToken::new(),
+                "manga".into(),
+                "chapter".into(),
+                None,
+            )
+            .await;
+        http_fixtures().set_mode(FixturesMode::Off);
+
+        let pages = pages.unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(
+            pages[0].image_url.as_ref().map(Url::as_str),
+            Some("https://example.com/pages/1.png")
+        );
+    }
+}
@@ -3,14 +3,30 @@ pub mod arima_light;
 pub mod cbz_metadata;
 pub mod chapter_downloader;
 pub mod chapter_storage;
+pub mod connectivity;
 #[cfg(feature = "all")]
 pub mod database;
+#[cfg(feature = "all")]
+pub mod download_queue;
+pub mod download_scheduler;
+#[cfg(feature = "all")]
+pub mod events;
+#[cfg(feature = "all")]
+pub mod eviction_policy;
+pub mod http_cache;
+pub mod http_fixtures;
 pub mod model;
+#[cfg(feature = "all")]
+pub mod pending_actions;
+#[cfg(feature = "all")]
+pub mod prefetch;
 pub mod settings;
 pub mod source;
 pub mod source_collection;
 pub mod source_manager;
 pub(crate) mod unscrable_image;
 #[cfg(feature = "all")]
+pub mod update_policy;
+#[cfg(feature = "all")]
 pub mod usecases;
 pub mod util;
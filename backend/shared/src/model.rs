@@ -3,6 +3,7 @@ use url::Url;
 
 use crate::source::{
     model::{Chapter as SourceChapter, Manga as SourceManga},
+    sandbox::{SourcePolicy, SourcePolicyViolation},
     SourceManifest,
 };
 
@@ -127,6 +128,44 @@ pub struct ChapterInformation {
 #[derive(Default, Clone, Debug)]
 pub struct MangaState {
     pub preferred_scanlator: Option<String>,
+    pub auto_download: AutoDownloadMode,
+}
+
+/// Which of the new chapters found by the update check should be downloaded automatically.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "mode")]
+pub enum AutoDownloadMode {
+    #[default]
+    Off,
+    AllNewChapters,
+    PreferredScanlator,
+    Languages {
+        languages: Vec<String>,
+    },
+}
+
+impl AutoDownloadMode {
+    pub fn from_columns(mode: i64, languages: Option<String>) -> Self {
+        match mode {
+            1 => Self::AllNewChapters,
+            2 => Self::PreferredScanlator,
+            3 => Self::Languages {
+                languages: languages
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+            },
+            _ => Self::Off,
+        }
+    }
+
+    pub fn to_columns(&self) -> (i64, Option<String>) {
+        match self {
+            Self::Off => (0, None),
+            Self::AllNewChapters => (1, None),
+            Self::PreferredScanlator => (2, None),
+            Self::Languages { languages } => (3, serde_json::to_string(languages).ok()),
+        }
+    }
 }
 
 #[derive(Default)]
@@ -139,6 +178,8 @@ pub struct Chapter {
     pub information: ChapterInformation,
     pub state: ChapterState,
     pub downloaded: bool,
+    /// Whether the chapter, or its whole manga, is pinned to be kept in the storage.
+    pub pinned: bool,
 }
 
 pub struct Manga {
@@ -191,14 +232,313 @@ impl From<SourceChapter> for ChapterInformation {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    NewChapter,
+    SourceUpdateAvailable,
+    DownloadFinished,
+    DownloadFailed,
+    SyncConflict,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NewChapter => "new_chapter",
+            Self::SourceUpdateAvailable => "source_update_available",
+            Self::DownloadFinished => "download_finished",
+            Self::DownloadFailed => "download_failed",
+            Self::SyncConflict => "sync_conflict",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "new_chapter" => Some(Self::NewChapter),
+            "source_update_available" => Some(Self::SourceUpdateAvailable),
+            "download_finished" => Some(Self::DownloadFinished),
+            "download_failed" => Some(Self::DownloadFailed),
+            "sync_conflict" => Some(Self::SyncConflict),
+            _ => None,
+        }
+    }
+}
+
+/// What a notification is about. Notifications about a chapter or a manga are grouped
+/// together with the other notifications of the same kind for that manga.
+pub enum NotificationSubject<'a> {
+    None,
+    Source(&'a SourceId),
+    Manga(&'a MangaId),
+    Chapter(&'a ChapterId),
+}
+
 #[derive(Serialize)]
 pub struct NotificationInformation {
     pub id: i64,
-    pub chapter_id: ChapterId,
+    pub kind: NotificationKind,
+    pub source_id: Option<SourceId>,
+    pub manga_id: Option<MangaId>,
+    pub chapter_id: Option<ChapterId>,
     pub manga_title: String,
     pub manga_cover: Option<Url>,
     pub manga_status: Option<i64>,
     pub chapter_title: String,
     pub chapter_number: f64,
+    pub message: Option<String>,
+    pub is_read: bool,
+    pub created_at: i64,
+}
+
+/// Notifications of the same kind about the same manga (or source), e.g.
+/// "5 new chapters of X".
+#[derive(Serialize)]
+pub struct NotificationGroup {
+    pub kind: NotificationKind,
+    pub source_id: Option<SourceId>,
+    pub manga_id: Option<MangaId>,
+    pub manga_title: Option<String>,
+    pub manga_cover: Option<Url>,
+    pub summary: String,
+    pub unread_count: usize,
+    pub latest_at: i64,
+    pub notifications: Vec<NotificationInformation>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateRunTrigger {
+    Manual,
+    Cron,
+}
+
+impl UpdateRunTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Cron => "cron",
+        }
+    }
+}
+
+/// Outcome of checking a single manga for new chapters during an update run.
+#[derive(Serialize)]
+pub struct UpdateRunResult {
+    pub manga_id: MangaId,
+    pub manga_title: Option<String>,
+    pub new_chapters: i64,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub checked_at: i64,
+}
+
+/// Aggregated results of an update run for a single source.
+#[derive(Serialize)]
+pub struct UpdateRunSourceSummary {
+    pub source_id: SourceId,
+    pub checked: i64,
+    pub failed: i64,
+    pub new_chapters: i64,
+    pub duration_ms: i64,
+}
+
+#[derive(Serialize)]
+pub struct UpdateRun {
+    pub id: i64,
+    pub trigger: String,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub sources: Vec<UpdateRunSourceSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<UpdateRunResult>,
+}
+
+/// A manga whose latest update checks have all failed.
+#[derive(Serialize)]
+pub struct FailingManga {
+    pub manga_id: MangaId,
+    pub manga_title: Option<String>,
+    pub consecutive_failures: i64,
+    pub last_error: Option<String>,
+    pub last_failure: i64,
+    pub last_success: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct SourceUpdateHealth {
+    pub source_id: SourceId,
+    pub failing_mangas: Vec<FailingManga>,
+}
+
+/// Calls into a source that trapped or panicked, and how many of them were followed by a call
+/// that went fine.
+#[derive(Clone, Debug, Serialize)]
+pub struct SourceCrashes {
+    pub source_id: SourceId,
+    pub crashes: usize,
+    pub recoveries: usize,
+    pub last_error: Option<String>,
+    pub last_crashed_at: Option<i64>,
+}
+
+impl SourceCrashes {
+    pub fn new(source_id: SourceId) -> Self {
+        Self {
+            source_id,
+            crashes: 0,
+            recoveries: 0,
+            last_error: None,
+            last_crashed_at: None,
+        }
+    }
+}
+
+/// What a source is allowed to do, and the latest things it tried to do that it wasn't allowed to
+/// since the server started.
+#[derive(Clone, Debug, Serialize)]
+pub struct SourcePolicyReport {
+    pub source_id: SourceId,
+    pub policy: SourcePolicy,
+    pub violations: Vec<SourcePolicyViolation>,
+}
+
+/// What the database knows about a chapter that matters when deciding whether its downloaded
+/// file can be evicted from the storage.
+#[derive(Clone, Debug)]
+pub struct ChapterStorageEntry {
+    pub id: ChapterId,
+    pub manga_title: Option<String>,
+    pub read: bool,
+    pub locked: bool,
+    pub in_library: bool,
+    /// Whether the chapter is waiting in the download queue (or being downloaded by it).
+    pub queued: bool,
+    /// Whether the chapter, or its whole manga, is pinned.
+    pub pinned: bool,
+}
+
+/// A manga whose downloaded chapters are never removed from the storage.
+#[derive(Serialize)]
+pub struct PinnedManga {
+    pub manga_id: MangaId,
+    pub manga_title: Option<String>,
+    pub pinned_at: i64,
+}
+
+/// A single chapter which is never removed from the storage.
+#[derive(Serialize)]
+pub struct PinnedChapter {
+    pub chapter_id: ChapterId,
+    pub manga_title: Option<String>,
+    pub chapter_title: Option<String>,
+    pub chapter_number: Option<f32>,
+    pub pinned_at: i64,
+}
+
+/// When a library manga was last checked for updates, and when it will be checked next.
+#[derive(Serialize)]
+pub struct MangaUpdateSchedule {
+    pub manga_id: MangaId,
+    pub manga_title: Option<String>,
+    pub last_check: Option<i64>,
+    pub next_check: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadQueueStatus {
+    Queued,
+    Downloading,
+    Paused,
+    Failed,
+}
+
+impl DownloadQueueStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Downloading => "downloading",
+            Self::Paused => "paused",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "queued" => Some(Self::Queued),
+            "downloading" => Some(Self::Downloading),
+            "paused" => Some(Self::Paused),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// A chapter waiting in the persistent download queue. Items are removed from the queue
+/// once the chapter is in storage.
+#[derive(Serialize)]
+pub struct DownloadQueueItem {
+    pub id: i64,
+    pub chapter_id: ChapterId,
+    pub chapter_title: Option<String>,
+    pub chapter_number: Option<f64>,
+    pub manga_title: Option<String>,
+    pub priority: i64,
+    pub status: DownloadQueueStatus,
+    pub attempts: i64,
+    pub last_error: Option<String>,
     pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// The next chapter to download, taken from the persistent download queue.
+pub struct QueuedDownload {
+    pub id: i64,
+    pub chapter_id: ChapterId,
+    pub attempts: i64,
+    /// Whether the chapter was queued by the update check, rather than by the user.
+    pub auto_download: bool,
+}
+
+/// An action that needs the network, queued while offline and replayed once the connection
+/// comes back, with the arguments it was asked for with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "action")]
+pub enum PendingAction {
+    SyncDatabase {
+        #[serde(default)]
+        accept_migrate_local: bool,
+        #[serde(default)]
+        accept_replace_remote: bool,
+    },
+    CheckMangasUpdate,
+}
+
+impl PendingAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SyncDatabase { .. } => "sync_database",
+            Self::CheckMangasUpdate => "check_mangas_update",
+        }
+    }
+
+    /// Parses an action queued without its arguments, which get their default values.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sync_database" => Some(Self::SyncDatabase {
+                accept_migrate_local: false,
+                accept_replace_remote: false,
+            }),
+            "check_mangas_update" => Some(Self::CheckMangasUpdate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QueuedAction {
+    #[serde(flatten)]
+    pub action: PendingAction,
+    pub queued_at: i64,
 }
@@ -0,0 +1,116 @@
+// Actions that need the network but were asked for while offline (see `connectivity`). They're
+// kept in the database, so they survive restarts, and replayed in the order they were queued
+// once the connection comes back. Queueing the same action twice only replays it once, with
+// the arguments it was queued with last.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    chapter_storage::ChapterStorage,
+    connectivity::connectivity,
+    database::Database,
+    model::{PendingAction, UpdateRunTrigger},
+    settings::Settings,
+    source_manager::SourceManager,
+    usecases::{check_mangas_update, sync_database},
+};
+
+// How often the connection is probed while offline.
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Probes the connection while offline, and replays the pending actions whenever it's back.
+pub async fn run_pending_actions(
+    db: Arc<Mutex<Database>>,
+    chapter_storage: Arc<Mutex<ChapterStorage>>,
+    source_manager: Arc<Mutex<SourceManager>>,
+    settings: Arc<Mutex<Settings>>,
+) {
+    let mut changes = connectivity().subscribe();
+
+    loop {
+        // Woken up early when a request notices the connection state changed
+        let _ = tokio::time::timeout(PROBE_INTERVAL, changes.changed()).await;
+
+        if connectivity().is_offline() && !connectivity().check().await {
+            continue;
+        }
+
+        if let Err(e) =
+            replay_pending_actions(&db, &chapter_storage, &source_manager, &settings).await
+        {
+            eprintln!("Warn: couldn't replay pending actions: {e:?}");
+        }
+    }
+}
+
+async fn replay_pending_actions(
+    db: &Arc<Mutex<Database>>,
+    chapter_storage: &Arc<Mutex<ChapterStorage>>,
+    source_manager: &Arc<Mutex<SourceManager>>,
+    settings: &Arc<Mutex<Settings>>,
+) -> Result<()> {
+    let pending_actions = db.lock().await.find_pending_actions().await?;
+
+    for pending_action in pending_actions {
+        let result = match pending_action.action {
+            PendingAction::SyncDatabase {
+                accept_migrate_local,
+                accept_replace_remote,
+            } => {
+                let mut settings = settings.lock().await;
+                let mut db = db.lock().await;
+
+                sync_database(
+                    &mut db,
+                    &mut settings,
+                    accept_migrate_local,
+                    accept_replace_remote,
+                )
+                .await
+                .map(|_| ())
+            }
+            PendingAction::CheckMangasUpdate => {
+                // The check can take a while, and must not keep everything else waiting
+                let db = db.lock().await.clone();
+                let chapter_storage = chapter_storage.lock().await.clone();
+                let source_manager = source_manager.lock().await.clone();
+                let settings = settings.lock().await.clone();
+
+                check_mangas_update(
+                    &CancellationToken::new(),
+                    &db,
+                    &chapter_storage,
+                    &source_manager,
+                    &settings,
+                    UpdateRunTrigger::Manual,
+                )
+                .await;
+
+                Ok(())
+            }
+        };
+
+        // Kept for later if we went offline again in the meantime
+        if result.is_err() && connectivity().is_offline() {
+            break;
+        }
+
+        if let Err(e) = result {
+            eprintln!(
+                "Warn[{}]: pending action failed: {e:?}",
+                pending_action.action.as_str()
+            );
+        }
+
+        db.lock()
+            .await
+            .remove_pending_action(pending_action.action)
+            .await?;
+    }
+
+    Ok(())
+}
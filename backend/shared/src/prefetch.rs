@@ -0,0 +1,290 @@
+// Read-ahead for the manga being read. When a chapter is opened, the next `preload_chapters`
+// chapters (in reading order) are downloaded in the background. Prefetches download one page
+// at a time, so they take at most a single slot of the download scheduler and never hold up
+// downloads the user is waiting for.
+//
+// Only one manga is prefetched at a time: prefetching another manga, or leaving the current
+// one, cancels the previous prefetch. Prefetched chapters are marked in the chapter storage,
+// which keeps them from being evicted until they're opened.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use ordered_float::OrderedFloat;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    chapter_downloader::ensure_chapter_is_in_storage,
+    chapter_storage::ChapterStorage,
+    database::Database,
+    events::{self, Event},
+    model::{ChapterId, ChapterInformation, MangaId},
+    settings::Settings,
+    source::Source,
+};
+
+static CURRENT_PREFETCH: Lazy<std::sync::Mutex<Option<(MangaId, CancellationToken)>>> =
+    Lazy::new(Default::default);
+
+/// Starts prefetching the chapters after `chapter_id`, replacing any prefetch in progress.
+/// Only chapters in `languages` are considered; when empty, the language of the opened
+/// chapter is used instead.
+pub async fn start_prefetch(
+    db: Arc<Mutex<Database>>,
+    chapter_storage: Arc<Mutex<ChapterStorage>>,
+    source: Source,
+    settings: &Settings,
+    chapter_id: &ChapterId,
+    languages: Vec<String>,
+) -> Result<()> {
+    let amount = settings.preload_chapters;
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let (manga, chapters) = {
+        let db = db.lock().await;
+        let manga = db
+            .find_cached_manga_information(chapter_id.manga_id())
+            .await?
+            .ok_or_else(|| anyhow!("Expected manga to be in the database"))?;
+        let chapters = db
+            .find_cached_chapter_informations(chapter_id.manga_id())
+            .await?;
+        let preferred_scanlator = db
+            .find_manga_state(chapter_id.manga_id())
+            .await?
+            .and_then(|state| state.preferred_scanlator);
+
+        let chapters = select_chapters_to_prefetch(
+            &chapters,
+            chapter_id,
+            &languages,
+            preferred_scanlator.as_deref(),
+            amount,
+        );
+
+        (manga, chapters)
+    };
+
+    let token = CancellationToken::new();
+    if let Some((_, previous)) = CURRENT_PREFETCH
+        .lock()
+        .unwrap()
+        .replace((chapter_id.manga_id().clone(), token.clone()))
+    {
+        previous.cancel();
+    }
+
+    let chapter_storage = chapter_storage.lock().await.clone();
+    let optimize_image = settings.optimize_image;
+
+    tokio::spawn(async move {
+        for chapter in chapters {
+            if token.is_cancelled() {
+                break;
+            }
+
+            if chapter_storage.get_stored_chapter(&chapter.id).is_some() {
+                continue;
+            }
+
+            let result = ensure_chapter_is_in_storage(
+                &token,
+                &chapter_storage,
+                &source,
+                &manga,
+                &chapter,
+                1,
+                optimize_image,
+                None,
+            )
+            .await;
+
+            match result {
+                Ok(_) => {
+                    if let Err(e) = chapter_storage.mark_prefetched(&chapter.id) {
+                        eprintln!(
+                            "Warn[{}]: couldn't mark chapter as prefetched: {}",
+                            chapter.id.value(),
+                            e
+                        );
+                    }
+
+                    events::publish(Event::DownloadFinished {
+                        chapter_id: chapter.id.clone(),
+                    });
+                }
+                Err(e) if !token.is_cancelled() => {
+                    eprintln!("Warn[{}]: prefetch failed: {:?}", chapter.id.value(), e);
+
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Cancels the prefetch of the given manga, if it's the one being prefetched.
+pub fn cancel_prefetch(manga_id: &MangaId) {
+    let mut current = CURRENT_PREFETCH.lock().unwrap();
+
+    if current.as_ref().is_some_and(|(id, _)| id == manga_id) {
+        if let Some((_, token)) = current.take() {
+            token.cancel();
+        }
+    }
+}
+
+/// Picks up to `amount` chapters to read after `current`, oldest first. Like the reader, the
+/// next chapter is the one with the closest higher chapter number, preferring the preferred
+/// scanlator or else the scanlator of the current chapter. Chapters without a number are only
+/// used when the current chapter has none, following the source order.
+fn select_chapters_to_prefetch(
+    chapters: &[ChapterInformation],
+    current: &ChapterId,
+    languages: &[String],
+    preferred_scanlator: Option<&str>,
+    amount: usize,
+) -> Vec<ChapterInformation> {
+    let Some(current) = chapters.iter().find(|chapter| chapter.id == *current) else {
+        return Vec::new();
+    };
+
+    let is_candidate = |chapter: &&ChapterInformation| {
+        let lang = chapter.lang.as_deref().unwrap_or("unknown");
+        let language_matches = if languages.is_empty() {
+            current.lang.as_deref().unwrap_or("unknown") == lang
+        } else {
+            languages.iter().any(|l| l == lang)
+        };
+
+        language_matches && !chapter.locked.unwrap_or_default()
+    };
+
+    let Some(current_number) = current.chapter_number else {
+        // Source order goes from the newest chapter to the oldest one
+        let newer_chapters: Vec<_> = chapters
+            .iter()
+            .take_while(|chapter| chapter.id != current.id)
+            .filter(is_candidate)
+            .collect();
+
+        return newer_chapters
+            .into_iter()
+            .rev()
+            .take(amount)
+            .cloned()
+            .collect();
+    };
+
+    let scanlator_rank = |chapter: &ChapterInformation| {
+        let scanlator = chapter.scanlator.as_deref();
+
+        if preferred_scanlator.is_some() && scanlator == preferred_scanlator {
+            0
+        } else if current.scanlator.is_some() && scanlator == current.scanlator.as_deref() {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut best_by_number: BTreeMap<OrderedFloat<f32>, &ChapterInformation> = BTreeMap::new();
+    for chapter in chapters.iter().filter(is_candidate) {
+        let Some(number) = chapter
+            .chapter_number
+            .filter(|number| *number > current_number)
+        else {
+            continue;
+        };
+
+        best_by_number
+            .entry(OrderedFloat(number))
+            .and_modify(|best| {
+                if scanlator_rank(chapter) < scanlator_rank(best) {
+                    *best = chapter;
+                }
+            })
+            .or_insert(chapter);
+    }
+
+    best_by_number.into_values().take(amount).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(key: &str, number: Option<f32>, scanlator: Option<&str>) -> ChapterInformation {
+        ChapterInformation {
+            id: ChapterId::from_strings("source".into(), "manga".into(), key.into()),
+            title: None,
+            scanlator: scanlator.map(str::to_owned),
+            chapter_number: number,
+            volume_number: None,
+            last_updated: None,
+            thumbnail: None,
+            lang: Some("en".into()),
+            url: None,
+            locked: None,
+        }
+    }
+
+    fn keys(chapters: &[ChapterInformation]) -> Vec<&str> {
+        chapters
+            .iter()
+            .map(|chapter| chapter.id.value().as_str())
+            .collect()
+    }
+
+    // Source order, newest first
+    fn chapters() -> Vec<ChapterInformation> {
+        vec![
+            chapter("5", Some(5.0), Some("a")),
+            chapter("4b", Some(4.0), Some("b")),
+            chapter("4a", Some(4.0), Some("a")),
+            chapter("3", Some(3.0), Some("a")),
+            chapter("2", Some(2.0), Some("a")),
+            chapter("1", Some(1.0), Some("a")),
+        ]
+    }
+
+    #[test]
+    fn prefetches_the_following_chapters() {
+        let chapters = chapters();
+        let current = chapters[3].id.clone();
+
+        let selected = select_chapters_to_prefetch(&chapters, &current, &[], None, 2);
+
+        assert_eq!(keys(&selected), ["4a", "5"]);
+    }
+
+    #[test]
+    fn prefetches_forward_preferring_the_preferred_scanlator() {
+        let chapters = chapters();
+        let current = chapters[4].id.clone();
+
+        let selected = select_chapters_to_prefetch(&chapters, &current, &[], Some("b"), 3);
+
+        assert_eq!(keys(&selected), ["3", "4b", "5"]);
+    }
+
+    #[test]
+    fn follows_the_source_order_without_chapter_numbers() {
+        let chapters: Vec<_> = ["c", "b", "a"]
+            .into_iter()
+            .map(|key| chapter(key, None, None))
+            .collect();
+        let current = chapters[2].id.clone();
+
+        let selected = select_chapters_to_prefetch(&chapters, &current, &[], None, 5);
+
+        assert_eq!(keys(&selected), ["b", "c"]);
+    }
+}
@@ -2,5 +2,7 @@ mod implementation;
 mod schema;
 
 pub use schema::{
-    ChapterSortingMode, LibrarySortingMode, Settings, SourceSettingValue, StorageSizeLimit,
+    ChapterSortingMode, ConnectivityProbe, ConnectivitySettings, DownloadLimits, HttpCacheSettings,
+    LibrarySortingMode, QuietHours, Settings, SourceLimitsSettings, SourceSandboxOverrides,
+    SourceSandboxSettings, SourceSettingValue, StorageSizeLimit, UpdatePolicy, UpdateScheduleMode,
 };
@@ -44,6 +44,335 @@ pub enum LibrarySortingMode {
     UnreadDesc,
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateScheduleMode {
+    /// Forecast the next chapter release from the chapter history of each manga.
+    #[default]
+    Arima,
+    /// Check every manga at a fixed interval.
+    FixedInterval,
+}
+
+/// A daily time window (in local time) during which no update checks are made.
+/// The window wraps around midnight when `start_hour` is greater than `end_hour`.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct QuietHours {
+    pub start_hour: u8,
+    pub end_hour: u8,
+}
+
+/// Controls when the background update checks are made.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UpdatePolicy {
+    #[serde(default)]
+    pub mode: UpdateScheduleMode,
+
+    /// Interval between checks when using `fixed_interval`, and the fallback when a
+    /// forecast can't be made. Defaults to 24 hours.
+    #[serde(default = "default_update_interval_minutes")]
+    pub interval_minutes: u64,
+
+    /// Checks are never scheduled sooner than this after the previous one.
+    #[serde(default)]
+    pub min_interval_minutes: Option<u64>,
+
+    /// Checks are never scheduled later than this after the previous one.
+    #[serde(default)]
+    pub max_interval_minutes: Option<u64>,
+
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+
+    /// Only check for updates while the device reports an unmetered connection.
+    #[serde(default)]
+    pub only_on_unmetered: bool,
+
+    /// Only check for updates while the device reports that it is charging.
+    #[serde(default)]
+    pub only_when_charging: bool,
+
+    #[serde(default = "default_true")]
+    pub skip_completed: bool,
+
+    /// Skip manga which don't have a single chapter marked as read.
+    #[serde(default)]
+    pub skip_not_started: bool,
+
+    /// Maximum number of manga checked at the same time for a single source.
+    #[serde(default = "default_per_source_concurrency")]
+    pub per_source_concurrency: usize,
+}
+
+impl Default for UpdatePolicy {
+    fn default() -> Self {
+        Self {
+            mode: UpdateScheduleMode::default(),
+            interval_minutes: default_update_interval_minutes(),
+            min_interval_minutes: None,
+            max_interval_minutes: None,
+            quiet_hours: None,
+            only_on_unmetered: false,
+            only_when_charging: false,
+            skip_completed: default_true(),
+            skip_not_started: false,
+            per_source_concurrency: default_per_source_concurrency(),
+        }
+    }
+}
+
+/// Limits shared by every chapter download, whatever started it. The number of pages
+/// downloaded at once by a single download is still set by `concurrent_requests_pages`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DownloadLimits {
+    /// Maximum number of pages downloaded at the same time, across all downloads.
+    #[serde(default = "default_max_concurrent_pages")]
+    pub max_concurrent_pages: usize,
+
+    /// Maximum number of pages downloaded at the same time from a single source.
+    #[serde(default = "default_per_source_page_concurrency")]
+    pub per_source_concurrency: usize,
+
+    /// Maximum number of pages downloaded at the same time from a single host.
+    #[serde(default = "default_per_host_page_concurrency")]
+    pub per_host_concurrency: usize,
+
+    /// Maximum number of page requests made to a single host every minute.
+    #[serde(default)]
+    pub per_host_requests_per_minute: Option<usize>,
+
+    /// Maximum download speed of all downloads together, in KiB/s.
+    #[serde(default)]
+    pub max_bandwidth_kib_per_second: Option<u64>,
+}
+
+impl Default for DownloadLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_pages: default_max_concurrent_pages(),
+            per_source_concurrency: default_per_source_page_concurrency(),
+            per_host_concurrency: default_per_host_page_concurrency(),
+            per_host_requests_per_minute: None,
+            max_bandwidth_kib_per_second: None,
+        }
+    }
+}
+
+/// Disk cache for the HTTP requests made by sources. Cached responses are reused while
+/// they're fresh, revalidated with the server once they're stale, and served as they are
+/// when there's no connection.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HttpCacheSettings {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+
+    /// How long responses fetched while loading manga details stay fresh, in seconds.
+    #[serde(default = "default_manga_details_max_age_seconds")]
+    pub manga_details_max_age_seconds: u64,
+
+    /// How long responses fetched while loading chapter lists stay fresh, in seconds.
+    #[serde(default = "default_chapter_list_max_age_seconds")]
+    pub chapter_list_max_age_seconds: u64,
+
+    /// How long responses fetched while loading page lists stay fresh, in seconds.
+    #[serde(default = "default_page_list_max_age_seconds")]
+    pub page_list_max_age_seconds: u64,
+
+    /// How long responses fetched by any other operation (e.g. searches) stay fresh, in seconds.
+    /// Like for the other operations, nothing is cached when it's 0.
+    #[serde(default)]
+    pub other_max_age_seconds: u64,
+
+    /// The maximum size of the cache, in MB. The oldest responses are removed past it.
+    #[serde(default = "default_http_cache_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+impl Default for HttpCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            manga_details_max_age_seconds: default_manga_details_max_age_seconds(),
+            chapter_list_max_age_seconds: default_chapter_list_max_age_seconds(),
+            page_list_max_age_seconds: default_page_list_max_age_seconds(),
+            other_max_age_seconds: 0,
+            max_size_mb: default_http_cache_max_size_mb(),
+        }
+    }
+}
+
+/// How much work a source may do in a single call before it's stopped as timed out, and how
+/// many calls it may serve at once. Fuel is spent while running the source's WASM code, roughly
+/// one unit per instruction. A limit of 0 disables it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SourceLimitsSettings {
+    #[serde(default = "default_manga_details_fuel")]
+    pub manga_details_fuel: u64,
+
+    #[serde(default = "default_chapter_list_fuel")]
+    pub chapter_list_fuel: u64,
+
+    #[serde(default = "default_page_list_fuel")]
+    pub page_list_fuel: u64,
+
+    /// The fuel of any other call (e.g. searches, or processing page images).
+    #[serde(default = "default_other_fuel")]
+    pub other_fuel: u64,
+
+    /// How many iterations a single loop may run in JS evaluated by the source.
+    #[serde(default = "default_js_loop_iteration_limit")]
+    pub js_loop_iteration_limit: u64,
+
+    /// How deep calls may recurse in JS evaluated by the source.
+    #[serde(default = "default_js_recursion_limit")]
+    pub js_recursion_limit: usize,
+
+    /// How many instances of a source may run at the same time, each one serving a single
+    /// operation. Every instance has its own WASM memory.
+    #[serde(default = "default_instances_per_source")]
+    pub instances_per_source: usize,
+}
+
+impl Default for SourceLimitsSettings {
+    fn default() -> Self {
+        Self {
+            manga_details_fuel: default_manga_details_fuel(),
+            chapter_list_fuel: default_chapter_list_fuel(),
+            page_list_fuel: default_page_list_fuel(),
+            other_fuel: default_other_fuel(),
+            js_loop_iteration_limit: default_js_loop_iteration_limit(),
+            js_recursion_limit: default_js_recursion_limit(),
+            instances_per_source: default_instances_per_source(),
+        }
+    }
+}
+
+/// What sources are allowed to do, on top of how much work they may do (see
+/// `SourceLimitsSettings`). `sources` relaxes (or tightens) any of it for a single source, by
+/// source ID. A limit of 0 disables it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SourceSandboxSettings {
+    /// Only let sources send requests to the hosts of the URLs in their manifest or their
+    /// settings (or their subdomains), plus the ones allowed in `sources`. Sources without URLs
+    /// in their manifest may reach any host. Off by default, as many sources rely on hosts they don't list (like
+    /// the CDNs of their images).
+    #[serde(default = "default_false")]
+    pub restrict_hosts: bool,
+
+    /// Don't let sources send requests to loopback, private or link-local addresses (like the
+    /// ones of the other devices on the LAN), unless they're in their manifest or their settings
+    /// (like the URL of a self-hosted server).
+    #[serde(default = "default_true")]
+    pub block_private_networks: bool,
+
+    /// The largest response a source may receive, in megabytes.
+    #[serde(default = "default_max_response_size_mb")]
+    pub max_response_size_mb: u64,
+
+    /// How much memory an instance of a source may use, in 64 KiB WASM pages.
+    #[serde(default = "default_max_memory_pages")]
+    pub max_memory_pages: u32,
+
+    #[serde(default = "default_true")]
+    pub allow_js: bool,
+
+    #[serde(default = "default_true")]
+    pub allow_canvas: bool,
+
+    #[serde(default = "default_true")]
+    pub allow_webview: bool,
+
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sources: HashMap<String, SourceSandboxOverrides>,
+}
+
+impl Default for SourceSandboxSettings {
+    fn default() -> Self {
+        Self {
+            restrict_hosts: false,
+            block_private_networks: true,
+            max_response_size_mb: default_max_response_size_mb(),
+            max_memory_pages: default_max_memory_pages(),
+            allow_js: true,
+            allow_canvas: true,
+            allow_webview: true,
+            sources: HashMap::new(),
+        }
+    }
+}
+
+/// The sandbox settings of a single source. Anything left unset keeps its global value.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SourceSandboxOverrides {
+    /// Hosts the source may reach on top of the ones in its manifest, subdomains included. They
+    /// may be private.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restrict_hosts: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_private_networks: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_size_mb: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_pages: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_js: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_canvas: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_webview: Option<bool>,
+}
+
+/// A way of checking whether we're online.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ConnectivityProbe {
+    /// Opens a TCP connection to `host:port`.
+    TcpConnect { host: String, port: u16 },
+    /// Sends a HEAD request to `url`. Any response counts, whatever its status.
+    HttpHead { url: Url },
+    /// Resolves `host` through the system resolver.
+    Dns { host: String },
+}
+
+/// How connectivity is checked. We're considered online as soon as one of the probes succeeds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ConnectivitySettings {
+    #[serde(default = "default_connectivity_probes")]
+    pub probes: Vec<ConnectivityProbe>,
+
+    /// Also probe the base URLs of the installed sources, with HEAD requests.
+    #[serde(default = "default_false")]
+    pub probe_source_urls: bool,
+
+    /// How long the result of a check is reused before checking again, in seconds.
+    #[serde(default = "default_connectivity_cache_ttl_seconds")]
+    pub cache_ttl_seconds: u64,
+
+    /// How long to wait for a probe before considering it failed, in seconds.
+    #[serde(default = "default_connectivity_probe_timeout_seconds")]
+    pub probe_timeout_seconds: u64,
+}
+
+impl Default for ConnectivitySettings {
+    fn default() -> Self {
+        Self {
+            probes: default_connectivity_probes(),
+            probe_source_urls: false,
+            cache_ttl_seconds: default_connectivity_cache_ttl_seconds(),
+            probe_timeout_seconds: default_connectivity_probe_timeout_seconds(),
+        }
+    }
+}
+
 /// Settings used to configure rakuyomi's behavior.
 #[derive(Serialize, Deserialize, Default, Clone, Debug, JsonSchema)]
 pub struct Settings {
@@ -93,11 +422,38 @@ pub struct Settings {
     #[serde(default)]
     pub source_skip_cron: Option<String>,
 
+    #[serde(default)]
+    pub update_policy: UpdatePolicy,
+
     #[serde(default)]
     pub preload_chapters: usize,
 
     #[serde(default)]
     pub optimize_image: bool,
+
+    #[serde(default)]
+    pub download_limits: DownloadLimits,
+
+    #[serde(default)]
+    pub http_cache: HttpCacheSettings,
+
+    #[serde(default)]
+    pub connectivity: ConnectivitySettings,
+
+    #[serde(default)]
+    pub source_limits: SourceLimitsSettings,
+
+    #[serde(default)]
+    pub source_sandbox: SourceSandboxSettings,
+
+    /// Forces the offline mode, in which everything is served from the database and the
+    /// chapter storage. Otherwise, it's only enabled while there's no connection.
+    #[serde(default)]
+    pub offline_mode: bool,
+
+    /// How long finished jobs are kept around in the job listing, in minutes.
+    #[serde(default = "default_job_history_retention_minutes")]
+    pub job_history_retention_minutes: u64,
 }
 
 fn default_storage_size_limit() -> StorageSizeLimit {
@@ -112,6 +468,105 @@ fn default_false() -> bool {
     false
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_concurrent_pages() -> usize {
+    8
+}
+
+fn default_per_source_page_concurrency() -> usize {
+    5
+}
+
+fn default_per_host_page_concurrency() -> usize {
+    5
+}
+
+fn default_manga_details_max_age_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_chapter_list_max_age_seconds() -> u64 {
+    60 * 60
+}
+
+fn default_page_list_max_age_seconds() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn default_http_cache_max_size_mb() -> u64 {
+    100
+}
+
+fn default_connectivity_probes() -> Vec<ConnectivityProbe> {
+    ["1.1.1.1", "1.0.0.1"]
+        .into_iter()
+        .map(|host| ConnectivityProbe::TcpConnect {
+            host: host.to_owned(),
+            port: 80,
+        })
+        .collect()
+}
+
+fn default_connectivity_cache_ttl_seconds() -> u64 {
+    10
+}
+
+fn default_connectivity_probe_timeout_seconds() -> u64 {
+    5
+}
+
+fn default_manga_details_fuel() -> u64 {
+    2_000_000_000
+}
+
+fn default_chapter_list_fuel() -> u64 {
+    4_000_000_000
+}
+
+fn default_page_list_fuel() -> u64 {
+    2_000_000_000
+}
+
+fn default_other_fuel() -> u64 {
+    10_000_000_000
+}
+
+fn default_js_loop_iteration_limit() -> u64 {
+    10_000_000
+}
+
+fn default_js_recursion_limit() -> usize {
+    512
+}
+
+fn default_instances_per_source() -> usize {
+    1
+}
+
+fn default_max_response_size_mb() -> u64 {
+    50
+}
+
+fn default_max_memory_pages() -> u32 {
+    // 256 MiB
+    4096
+}
+
+fn default_job_history_retention_minutes() -> u64 {
+    60
+}
+
+fn default_update_interval_minutes() -> u64 {
+    24 * 60
+}
+
+fn default_per_source_concurrency() -> usize {
+    1
+}
+
 impl Default for StorageSizeLimit {
     fn default() -> Self {
         Self(Size::from_bytes(0))
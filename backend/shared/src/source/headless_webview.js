@@ -0,0 +1,338 @@
+// The browser environment the scripts of a headless WebView run in (see `headless_webview.rs`).
+// It's only as much as JS challenges and token generators usually need: the DOM is a read-only
+// snapshot of the loaded page, and timers run in order of their delay without actually waiting.
+(() => {
+  const page = JSON.parse(__rakuyomiPage());
+  const noop = () => {};
+
+  const location = {
+    ...page.location,
+    assign: noop,
+    reload: noop,
+    replace: noop,
+    toString() {
+      return this.href;
+    },
+  };
+
+  const toElement = (node) => ({
+    tagName: node.tagName.toUpperCase(),
+    nodeName: node.tagName.toUpperCase(),
+    id: node.attributes.id ?? '',
+    className: node.attributes.class ?? '',
+    textContent: node.text,
+    innerText: node.text,
+    innerHTML: node.innerHtml,
+    outerHTML: node.outerHtml,
+    attributes: node.attributes,
+    getAttribute(name) {
+      const key = String(name).toLowerCase();
+
+      return key in node.attributes ? node.attributes[key] : null;
+    },
+    hasAttribute(name) {
+      return String(name).toLowerCase() in node.attributes;
+    },
+    addEventListener: noop,
+    removeEventListener: noop,
+  });
+
+  const query = (selector, first) =>
+    JSON.parse(__rakuyomiQuery(String(selector), first)).map(toElement);
+  const quote = (value) => `"${String(value).replace(/["\\]/g, '\\$&')}"`;
+
+  const cookies = new Map();
+  const setCookie = (value) => {
+    const [pair] = String(value).split(';');
+    const separator = pair.indexOf('=');
+
+    if (separator > 0) {
+      cookies.set(pair.slice(0, separator).trim(), pair.slice(separator + 1).trim());
+    }
+  };
+  page.cookies.forEach(setCookie);
+
+  const document = {
+    get documentElement() {
+      return query('html', true)[0] ?? null;
+    },
+    get head() {
+      return query('head', true)[0] ?? null;
+    },
+    get body() {
+      return query('body', true)[0] ?? null;
+    },
+    get title() {
+      return query('title', true)[0]?.textContent ?? '';
+    },
+    get cookie() {
+      return [...cookies].map(([name, value]) => `${name}=${value}`).join('; ');
+    },
+    set cookie(value) {
+      setCookie(value);
+    },
+    get URL() {
+      return location.href;
+    },
+    location,
+    referrer: '',
+    readyState: 'complete',
+    querySelector: (selector) => query(selector, true)[0] ?? null,
+    querySelectorAll: (selector) => query(selector, false),
+    getElementById: (id) => query(`[id=${quote(id)}]`, true)[0] ?? null,
+    getElementsByTagName: (name) => query(name, false),
+    getElementsByClassName: (names) =>
+      query(
+        String(names)
+          .trim()
+          .split(/\s+/)
+          .map((name) => `[class~=${quote(name)}]`)
+          .join(''),
+        false,
+      ),
+    addEventListener: noop,
+    removeEventListener: noop,
+  };
+
+  let now = 0;
+  let nextTimer = 1;
+  const timers = new Map();
+  const setTimer = (callback, delay, args, interval) => {
+    const id = nextTimer++;
+    const wait = Math.max(Number(delay) || 0, interval ? 1 : 0);
+    timers.set(id, { callback, wait, args, interval, at: now + wait });
+
+    return id;
+  };
+  const clearTimer = (id) => timers.delete(id);
+
+  class Headers {
+    constructor(init) {
+      this.map = new Map();
+
+      const entries =
+        init == null
+          ? []
+          : typeof init[Symbol.iterator] === 'function'
+            ? init
+            : Object.entries(init);
+      for (const [name, value] of entries) {
+        this.append(name, value);
+      }
+    }
+
+    append(name, value) {
+      const key = String(name).toLowerCase();
+      const previous = this.map.get(key);
+
+      this.map.set(key, previous == null ? String(value) : `${previous}, ${value}`);
+    }
+
+    set(name, value) {
+      this.map.set(String(name).toLowerCase(), String(value));
+    }
+
+    get(name) {
+      return this.map.get(String(name).toLowerCase()) ?? null;
+    }
+
+    has(name) {
+      return this.map.has(String(name).toLowerCase());
+    }
+
+    delete(name) {
+      this.map.delete(String(name).toLowerCase());
+    }
+
+    forEach(callback) {
+      this.map.forEach((value, name) => callback(value, name, this));
+    }
+
+    entries() {
+      return this.map.entries();
+    }
+
+    [Symbol.iterator]() {
+      return this.map.entries();
+    }
+  }
+
+  class Response {
+    constructor(raw) {
+      this.url = raw.url;
+      this.status = raw.status;
+      this.ok = raw.status >= 200 && raw.status < 300;
+      this.headers = new Headers(raw.headers);
+      this.body = raw.body;
+    }
+
+    text() {
+      return Promise.resolve(this.body);
+    }
+
+    json() {
+      return this.text().then(JSON.parse);
+    }
+  }
+
+  // Requests are sent synchronously, only their result is delivered asynchronously
+  const send = (method, url, headers, body) =>
+    JSON.parse(
+      __rakuyomiFetch(
+        String(method ?? 'GET'),
+        String(url),
+        JSON.stringify(Object.fromEntries(new Headers(headers).entries())),
+        body == null ? undefined : String(body),
+      ),
+    );
+
+  const fetch = (input, init = {}) =>
+    new Promise((resolve) => {
+      const url = typeof input === 'object' && input !== null ? input.url : input;
+
+      resolve(new Response(send(init.method, url, init.headers, init.body)));
+    });
+
+  class XMLHttpRequest {
+    constructor() {
+      this.readyState = 0;
+      this.status = 0;
+      this.responseText = '';
+      this.requestHeaders = new Headers();
+      this.responseHeaders = new Headers();
+    }
+
+    open(method, url) {
+      this.method = method;
+      this.url = url;
+      this.readyState = 1;
+    }
+
+    setRequestHeader(name, value) {
+      this.requestHeaders.append(name, value);
+    }
+
+    getResponseHeader(name) {
+      return this.responseHeaders.get(name);
+    }
+
+    send(body) {
+      const raw = send(this.method, this.url, this.requestHeaders, body);
+
+      this.status = raw.status;
+      this.responseURL = raw.url;
+      this.responseText = raw.body;
+      this.response = raw.body;
+      this.responseHeaders = new Headers(raw.headers);
+      this.readyState = 4;
+
+      setTimer(() => {
+        this.onreadystatechange?.();
+        this.onload?.();
+      }, 0, []);
+    }
+
+    addEventListener(type, listener) {
+      this[`on${type}`] = listener;
+    }
+  }
+
+  const base64 = 'ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/';
+  const btoa = (value) => {
+    const text = String(value);
+    let output = '';
+
+    for (let i = 0; i < text.length; i += 3) {
+      const bytes = [text.charCodeAt(i), text.charCodeAt(i + 1), text.charCodeAt(i + 2)];
+      const bits = (bytes[0] << 16) | ((bytes[1] || 0) << 8) | (bytes[2] || 0);
+
+      output += base64[(bits >> 18) & 63] + base64[(bits >> 12) & 63];
+      output += i + 1 < text.length ? base64[(bits >> 6) & 63] : '=';
+      output += i + 2 < text.length ? base64[bits & 63] : '=';
+    }
+
+    return output;
+  };
+  const atob = (value) => {
+    const text = String(value).replace(/[\s=]/g, '');
+    let output = '';
+    let bits = 0;
+    let count = 0;
+
+    for (const char of text) {
+      bits = (bits << 6) | base64.indexOf(char);
+      count += 6;
+
+      if (count >= 8) {
+        count -= 8;
+        output += String.fromCharCode((bits >> count) & 255);
+      }
+    }
+
+    return output;
+  };
+
+  Object.assign(globalThis, {
+    window: globalThis,
+    self: globalThis,
+    document,
+    location,
+    navigator: {
+      userAgent: page.userAgent,
+      language: 'en-US',
+      languages: ['en-US', 'en'],
+      platform: '',
+      cookieEnabled: true,
+      webdriver: false,
+    },
+    console: { log: noop, info: noop, warn: noop, error: noop, debug: noop },
+    setTimeout: (callback, delay, ...args) => setTimer(callback, delay, args, false),
+    setInterval: (callback, delay, ...args) => setTimer(callback, delay, args, true),
+    clearTimeout: clearTimer,
+    clearInterval: clearTimer,
+    requestAnimationFrame: (callback) => setTimer(() => callback(now), 16, [], false),
+    cancelAnimationFrame: clearTimer,
+    queueMicrotask: (callback) => Promise.resolve().then(callback),
+    addEventListener: noop,
+    removeEventListener: noop,
+    fetch,
+    Headers,
+    Response,
+    XMLHttpRequest,
+    atob,
+    btoa,
+    // Runs the next timer, returning whether there was one
+    __rakuyomiRunTimer: () => {
+      let next;
+      for (const entry of timers) {
+        if (next === undefined || entry[1].at < next[1].at) {
+          next = entry;
+        }
+      }
+
+      if (next === undefined) {
+        return false;
+      }
+
+      const [id, timer] = next;
+      now = timer.at;
+      if (timer.interval) {
+        timer.at += timer.wait;
+      } else {
+        timers.delete(id);
+      }
+
+      try {
+        if (typeof timer.callback === 'function') {
+          timer.callback(...timer.args);
+        } else {
+          (0, eval)(String(timer.callback));
+        }
+      } catch {
+        // Like in a browser, a failing timer doesn't stop the others
+      }
+
+      return true;
+    },
+  });
+})();
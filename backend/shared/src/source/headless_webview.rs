@@ -0,0 +1,555 @@
+// A WebView for the builds without a platform one to call into (`feature = "all"`), good enough
+// for sources that only need a browser step, like solving a JS challenge or generating a token.
+//
+// Pages are requested the same way the source's own requests are (see `RequestSender`), parsed
+// with `dom_query`, and their scripts run in a boa context set up with the shim in
+// `headless_webview.js`. There's no layout, and no events besides timers; the DOM the scripts
+// see is the page as it was loaded.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use anyhow::{anyhow, Context as _, Result};
+use boa_engine::{
+    builtins::promise::PromiseState, object::builtins::JsPromise, Context, JsArgs, JsError,
+    JsNativeError, JsResult, JsString, JsValue, NativeFunction, Source,
+};
+use dom_query::{Document, Matcher, NodeRef, Selection};
+use log::warn;
+use reqwest::{header::SET_COOKIE, Method};
+use serde_json::json;
+use url::Url;
+
+use super::{
+    limits::{is_runtime_limit, source_limits},
+    wasm_imports::net::{RequestSender, DEFAULT_USER_AGENT},
+    wasm_store::{RequestBuildingState, ResponseData},
+};
+
+const SHIM: &str = include_str!("headless_webview.js");
+
+// Timers may keep scheduling each other (or be intervals), so running them has to stop somewhere
+const MAX_TIMERS: usize = 1000;
+
+pub struct HeadlessWebView {
+    context: Context,
+    page: Rc<RefCell<Page>>,
+    sender: Rc<RefCell<Option<RequestSender>>>,
+}
+
+// FIXME same as `JsContext`: boa's context isn't thread safe, but a WebView is only ever used
+// by the instance owning it
+unsafe impl Send for HeadlessWebView {}
+unsafe impl Sync for HeadlessWebView {}
+
+struct Page {
+    url: Option<Url>,
+    document: Document,
+    cookies: Vec<String>,
+}
+
+impl HeadlessWebView {
+    pub fn new() -> Result<Self> {
+        let page = Rc::new(RefCell::new(Page {
+            url: None,
+            document: Document::from(""),
+            cookies: Vec::new(),
+        }));
+        let sender = Rc::new(RefCell::new(None));
+
+        Ok(Self {
+            context: create_context(&page, &sender)?,
+            page,
+            sender,
+        })
+    }
+
+    /// Sets how the requests of the next calls are sent, as it depends on the current operation.
+    pub fn set_sender(&mut self, sender: RequestSender) {
+        *self.sender.borrow_mut() = Some(sender);
+    }
+
+    /// Requests a page, then loads it.
+    pub fn load(&mut self, request: &RequestBuildingState) -> Result<()> {
+        let response = self.send(request)?;
+
+        self.load_response(&response)
+    }
+
+    /// Loads the response of a request that was already sent.
+    pub fn load_response(&mut self, response: &ResponseData) -> Result<()> {
+        let html = String::from_utf8_lossy(response.body.as_deref().unwrap_or_default());
+        let cookies = response
+            .headers
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(str::to_owned)
+            .collect();
+
+        self.navigate(&html, &response.url, cookies)
+    }
+
+    pub fn load_html(&mut self, html: &str, url: &Url) -> Result<()> {
+        self.navigate(html, url, Vec::new())
+    }
+
+    /// Runs the timers the page scripts have set, along with the promise jobs they queue.
+    pub fn wait_for_load(&mut self) -> Result<()> {
+        for _ in 0..MAX_TIMERS {
+            self.context.run_jobs().map_err(js_error)?;
+
+            let has_run = self
+                .context
+                .eval(Source::from_bytes("__rakuyomiRunTimer()"))
+                .map_err(js_error)?;
+            if !has_run.to_boolean() {
+                return Ok(());
+            }
+        }
+
+        warn!("headless WebView stopped running timers after {MAX_TIMERS} of them");
+
+        Ok(())
+    }
+
+    /// Evaluates code in the page, waiting for the result if it's a promise.
+    pub fn eval(&mut self, code: &str) -> JsResult<String> {
+        let mut result = self.context.eval(Source::from_bytes(code))?;
+        self.context.run_jobs()?;
+
+        if let Some(promise) = result
+            .as_object()
+            .and_then(|object| JsPromise::from_object(object.clone()).ok())
+        {
+            result = match promise.state() {
+                PromiseState::Fulfilled(value) => value,
+                PromiseState::Rejected(reason) => return Err(JsError::from_opaque(reason)),
+                PromiseState::Pending => JsValue::undefined(),
+            };
+        }
+
+        Ok(result.to_string(&mut self.context)?.to_std_string_escaped())
+    }
+
+    fn send(&self, request: &RequestBuildingState) -> Result<ResponseData> {
+        self.sender
+            .borrow()
+            .as_ref()
+            .context("WebView used outside of an operation")?
+            .send(request)
+    }
+
+    // Replaces the page, and the context its scripts run in, before running its scripts.
+    fn navigate(&mut self, html: &str, url: &Url, cookies: Vec<String>) -> Result<()> {
+        *self.page.borrow_mut() = Page {
+            url: Some(url.clone()),
+            document: Document::from(html),
+            cookies,
+        };
+        self.context = create_context(&self.page, &self.sender)?;
+
+        for script in self.page_scripts()? {
+            match self.context.eval(Source::from_bytes(&script)) {
+                Ok(_) => {}
+                Err(error) if is_runtime_limit(&error) => return Err(js_error(error)),
+                // Like in a browser, a failing script doesn't stop the others
+                Err(error) => warn!("script of {url} failed: {error}"),
+            }
+        }
+
+        self.context.run_jobs().map_err(js_error)
+    }
+
+    // The source of the page scripts, in order, requesting the external ones.
+    fn page_scripts(&self) -> Result<Vec<String>> {
+        let scripts: Vec<(Option<String>, String)> = {
+            let page = self.page.borrow();
+
+            page.document
+                .select("script")
+                .nodes()
+                .iter()
+                .filter(|node| {
+                    node.attr("type").is_none_or(|kind| {
+                        matches!(
+                            kind.trim().to_lowercase().as_str(),
+                            "" | "text/javascript" | "application/javascript" | "module"
+                        )
+                    })
+                })
+                .map(|node| {
+                    (
+                        node.attr("src").map(|src| src.to_string()),
+                        node.text().to_string(),
+                    )
+                })
+                .collect()
+        };
+
+        let mut sources = Vec::with_capacity(scripts.len());
+        for (src, text) in scripts {
+            let Some(src) = src else {
+                sources.push(text);
+                continue;
+            };
+
+            let request =
+                page_request(&self.page.borrow(), Method::GET, &src, HashMap::new(), None)?;
+            match self.send(&request) {
+                Ok(response) => sources.push(
+                    String::from_utf8_lossy(response.body.as_deref().unwrap_or_default())
+                        .into_owned(),
+                ),
+                Err(error) => warn!("couldn't load script {src}: {error:?}"),
+            }
+        }
+
+        Ok(sources)
+    }
+}
+
+fn create_context(
+    page: &Rc<RefCell<Page>>,
+    sender: &Rc<RefCell<Option<RequestSender>>>,
+) -> Result<Context> {
+    let mut context = Context::default();
+    source_limits().limit_js_context(&mut context);
+
+    let page_info = {
+        let page = page.clone();
+
+        move |_: &JsValue, _: &[JsValue], _: &mut Context| -> JsResult<JsValue> {
+            let page = page.borrow();
+            let url = page.url.as_ref();
+
+            Ok(JsString::from(
+                json!({
+                    "location": {
+                        "href": url.map(Url::as_str).unwrap_or("about:blank"),
+                        "origin": url.map(|url| url.origin().ascii_serialization()),
+                        "protocol": url.map(|url| format!("{}:", url.scheme())),
+                        "host": url.and_then(|url| {
+                            url.host_str().map(|host| match url.port() {
+                                Some(port) => format!("{host}:{port}"),
+                                None => host.to_owned(),
+                            })
+                        }),
+                        "hostname": url.and_then(Url::host_str),
+                        "port": url.and_then(Url::port).map(|port| port.to_string()),
+                        "pathname": url.map(Url::path),
+                        "search": url.and_then(Url::query).map(|query| format!("?{query}")),
+                        "hash": url.and_then(Url::fragment).map(|fragment| format!("#{fragment}")),
+                    },
+                    "cookies": page.cookies,
+                    "userAgent": DEFAULT_USER_AGENT,
+                })
+                .to_string(),
+            )
+            .into())
+        }
+    };
+
+    let query = {
+        let page = page.clone();
+
+        move |_: &JsValue, args: &[JsValue], context: &mut Context| -> JsResult<JsValue> {
+            let selector = args.get_or_undefined(0).to_string(context)?;
+            let first = args.get_or_undefined(1).to_boolean();
+
+            let matcher = Matcher::new(&selector.to_std_string_escaped()).map_err(|error| {
+                JsNativeError::syntax().with_message(format!("invalid selector: {error:?}"))
+            })?;
+
+            let page = page.borrow();
+            let selection = Selection::from(page.document.root());
+            let nodes = if first {
+                selection.select_single_matcher(&matcher)
+            } else {
+                selection.select_matcher(&matcher)
+            };
+
+            Ok(JsString::from(
+                serde_json::Value::from_iter(nodes.nodes().iter().map(node_json)).to_string(),
+            )
+            .into())
+        }
+    };
+
+    let fetch = {
+        let page = page.clone();
+        let sender = sender.clone();
+
+        move |_: &JsValue, args: &[JsValue], context: &mut Context| -> JsResult<JsValue> {
+            let mut string_arg = |index: usize| -> JsResult<Option<String>> {
+                let arg = args.get_or_undefined(index);
+                if arg.is_undefined() {
+                    return Ok(None);
+                }
+
+                Ok(Some(arg.to_string(context)?.to_std_string_escaped()))
+            };
+            let (method, url, headers, body) = (
+                string_arg(0)?,
+                string_arg(1)?,
+                string_arg(2)?,
+                string_arg(3)?,
+            );
+
+            let response = (|| -> Result<ResponseData> {
+                let method =
+                    Method::from_bytes(method.unwrap_or_default().to_uppercase().as_bytes())?;
+                let headers = serde_json::from_str(&headers.unwrap_or_else(|| "{}".into()))?;
+                let request = page_request(
+                    &page.borrow(),
+                    method,
+                    &url.unwrap_or_default(),
+                    headers,
+                    body.map(String::into_bytes),
+                )?;
+
+                sender
+                    .borrow()
+                    .as_ref()
+                    .context("WebView used outside of an operation")?
+                    .send(&request)
+            })()
+            .map_err(|error| JsNativeError::typ().with_message(format!("{error:#}")))?;
+
+            let headers: HashMap<&str, &str> = response
+                .headers
+                .iter()
+                .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)))
+                .collect();
+
+            Ok(JsString::from(
+                json!({
+                    "url": response.url.as_str(),
+                    "status": response.status_code.as_u16(),
+                    "headers": headers,
+                    "body": String::from_utf8_lossy(response.body.as_deref().unwrap_or_default()),
+                })
+                .to_string(),
+            )
+            .into())
+        }
+    };
+
+    // SAFETY: the closures only capture Rust values, none of which hold anything traced by boa's
+    // garbage collector
+    let functions = unsafe {
+        [
+            ("__rakuyomiPage", 0, NativeFunction::from_closure(page_info)),
+            ("__rakuyomiQuery", 2, NativeFunction::from_closure(query)),
+            ("__rakuyomiFetch", 4, NativeFunction::from_closure(fetch)),
+        ]
+    };
+    for (name, length, function) in functions {
+        context
+            .register_global_builtin_callable(JsString::from(name), length, function)
+            .map_err(js_error)?;
+    }
+
+    context
+        .eval(Source::from_bytes(SHIM))
+        .map_err(js_error)
+        .context("failed setting up the WebView shim")?;
+
+    Ok(context)
+}
+
+// A request made by the page, with `url` relative to it.
+fn page_request(
+    page: &Page,
+    method: Method,
+    url: &str,
+    headers: HashMap<String, String>,
+    body: Option<Vec<u8>>,
+) -> Result<RequestBuildingState> {
+    let url = match &page.url {
+        Some(page_url) => page_url.join(url),
+        None => Url::parse(url),
+    }
+    .with_context(|| format!("invalid URL {url}"))?;
+
+    let mut request_headers = HashMap::from([("User-Agent".into(), DEFAULT_USER_AGENT.into())]);
+    if let Some(page_url) = &page.url {
+        request_headers.insert("Referer".into(), page_url.to_string());
+    }
+    request_headers.extend(headers);
+
+    Ok(RequestBuildingState {
+        url: Some(url),
+        method: Some(method),
+        body,
+        headers: request_headers,
+        timeout: None,
+    })
+}
+
+fn node_json(node: &NodeRef) -> serde_json::Value {
+    let attributes: HashMap<String, String> = node
+        .attrs()
+        .iter()
+        .map(|attribute| {
+            (
+                attribute.name.local.to_string(),
+                attribute.value.to_string(),
+            )
+        })
+        .collect();
+
+    json!({
+        "tagName": node.node_name().map(|name| name.to_string()).unwrap_or_default(),
+        "text": node.text().to_string(),
+        "innerHtml": node.inner_html().to_string(),
+        "outerHtml": node.html().to_string(),
+        "attributes": attributes,
+    })
+}
+
+fn js_error(error: JsError) -> anyhow::Error {
+    anyhow!("{error}")
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{
+        header::{HeaderMap, HeaderValue},
+        StatusCode,
+    };
+
+    use super::*;
+
+    fn page_url() -> Url {
+        Url::parse("https://example.com/reader?chapter=1").unwrap()
+    }
+
+    #[test]
+    fn exposes_the_loaded_page_to_its_scripts() {
+        let mut webview = HeadlessWebView::new().unwrap();
+        let html = r#"
+            <html>
+              <head><title>Chapter 1</title></head>
+              <body>
+                <p id="name" class="title big">Some manga</p>
+                <script>
+                  window.found = [
+                    document.title,
+                    document.getElementById('name').textContent,
+                    document.getElementsByClassName('big').length,
+                    location.hostname,
+                    location.search,
+                    document.cookie,
+                  ].join('|');
+                </script>
+              </body>
+            </html>
+        "#;
+
+        webview
+            .load_response(&ResponseData {
+                url: page_url(),
+                status_code: StatusCode::OK,
+                headers: HeaderMap::from_iter([(
+                    SET_COOKIE,
+                    HeaderValue::from_static("session=abc; Path=/; HttpOnly"),
+                )]),
+                body: Some(html.as_bytes().to_vec()),
+                bytes_read: 0,
+            })
+            .unwrap();
+
+        assert_eq!(
+            webview.eval("found").unwrap(),
+            "Chapter 1|Some manga|1|example.com|?chapter=1|session=abc"
+        );
+        assert_eq!(
+            webview
+                .eval("try { document.querySelector('[') } catch (e) { e.name }")
+                .unwrap(),
+            "SyntaxError"
+        );
+    }
+
+    #[test]
+    fn runs_timers_in_order_of_their_delay() {
+        let mut webview = HeadlessWebView::new().unwrap();
+        let html = r#"
+            <script>
+              window.order = '';
+              setTimeout(() => { order += 'c'; }, 30);
+              setTimeout(() => { order += 'a'; Promise.resolve().then(() => { order += 'b'; }); }, 10);
+              clearTimeout(setTimeout(() => { order += 'x'; }, 20));
+            </script>
+        "#;
+
+        webview.load_html(html, &page_url()).unwrap();
+        webview.wait_for_load().unwrap();
+
+        assert_eq!(webview.eval("order").unwrap(), "abc");
+        assert_eq!(webview.eval("Promise.resolve(order.length)").unwrap(), "3");
+    }
+
+    #[test]
+    fn stops_running_timers_after_the_limit() {
+        let mut webview = HeadlessWebView::new().unwrap();
+        let html = "<script>window.ticks = 0; setInterval(() => { ticks++; }, 10);</script>";
+
+        webview.load_html(html, &page_url()).unwrap();
+        webview.wait_for_load().unwrap();
+
+        assert_eq!(webview.eval("ticks").unwrap(), MAX_TIMERS.to_string());
+    }
+
+    #[test]
+    fn fails_loading_when_a_script_goes_past_the_runtime_limits() {
+        let mut webview = HeadlessWebView::new().unwrap();
+
+        // Other errors only stop the script they happen in
+        let html = "<script>throw new Error('broken')</script><script>window.ran = true</script>";
+        webview.load_html(html, &page_url()).unwrap();
+        assert_eq!(webview.eval("ran").unwrap(), "true");
+
+        let html = "<script>const recurse = () => recurse() + 1; recurse();</script>";
+        assert!(webview.load_html(html, &page_url()).is_err());
+    }
+
+    #[test]
+    fn navigating_drops_the_previous_page() {
+        let mut webview = HeadlessWebView::new().unwrap();
+        let html = r#"
+            <script>
+              window.leftover = true;
+              setTimeout(() => { window.late = true; }, 10);
+            </script>
+        "#;
+
+        webview.load_html(html, &page_url()).unwrap();
+        webview
+            .load_html("<p>Next</p>", &page_url().join("/next").unwrap())
+            .unwrap();
+        webview.wait_for_load().unwrap();
+
+        assert_eq!(webview.eval("typeof leftover").unwrap(), "undefined");
+        assert_eq!(webview.eval("typeof late").unwrap(), "undefined");
+        assert_eq!(webview.eval("location.pathname").unwrap(), "/next");
+    }
+
+    #[test]
+    fn rejects_requests_made_outside_of_an_operation() {
+        let mut webview = HeadlessWebView::new().unwrap();
+        webview.load_html("", &page_url()).unwrap();
+
+        assert_eq!(
+            webview
+                .eval("fetch('/api').then(() => 'sent', (error) => error.message)")
+                .unwrap(),
+            "WebView used outside of an operation"
+        );
+        assert!(webview
+            .eval(
+                "const request = new XMLHttpRequest(); request.open('GET', '/api'); request.send()"
+            )
+            .is_err());
+    }
+}
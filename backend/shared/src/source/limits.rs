@@ -0,0 +1,77 @@
+// Limits on how much work a source may do in a single call, so that a source stuck in a loop
+// can't hang a blocking thread forever (the cancellation token is only checked by host
+// functions, so it can't interrupt one). WASM execution is metered with fuel, roughly one unit
+// per instruction, with a budget per operation; JS evaluated through boa is limited in loop
+// iterations and recursion depth. Going past either fails the call with `SourceTimedOutError`.
+//
+// wasmi has no epoch interruption, so there's no wall-clock limit: fuel is what bounds time.
+//
+// This is also where the size of the instance pools (see `pool`) is set.
+
+use std::sync::RwLock;
+
+use boa_engine::{JsError, JsNativeErrorKind};
+use once_cell::sync::Lazy;
+
+use crate::settings::SourceLimitsSettings;
+
+use super::SourceOperation;
+
+static SOURCE_LIMITS: Lazy<SourceLimits> = Lazy::new(|| SourceLimits {
+    settings: RwLock::new(SourceLimitsSettings::default()),
+});
+
+pub fn source_limits() -> &'static SourceLimits {
+    &SOURCE_LIMITS
+}
+
+pub struct SourceLimits {
+    settings: RwLock<SourceLimitsSettings>,
+}
+
+impl SourceLimits {
+    pub fn configure(&self, settings: &SourceLimitsSettings) {
+        *self.settings.write().unwrap() = settings.clone();
+    }
+
+    /// The fuel a call made for `operation` may consume.
+    pub fn fuel(&self, operation: SourceOperation) -> u64 {
+        let settings = self.settings.read().unwrap();
+        let fuel = match operation {
+            SourceOperation::MangaDetails => settings.manga_details_fuel,
+            SourceOperation::ChapterList => settings.chapter_list_fuel,
+            SourceOperation::PageList => settings.page_list_fuel,
+            SourceOperation::Other => settings.other_fuel,
+        };
+
+        if fuel == 0 {
+            u64::MAX
+        } else {
+            fuel
+        }
+    }
+
+    /// How many instances of a source may run at the same time.
+    pub fn instances_per_source(&self) -> usize {
+        self.settings.read().unwrap().instances_per_source.max(1)
+    }
+
+    pub fn limit_js_context(&self, context: &mut boa_engine::Context) {
+        let settings = self.settings.read().unwrap();
+        let limits = context.runtime_limits_mut();
+
+        if settings.js_loop_iteration_limit != 0 {
+            limits.set_loop_iteration_limit(settings.js_loop_iteration_limit);
+        }
+        if settings.js_recursion_limit != 0 {
+            limits.set_recursion_limit(settings.js_recursion_limit);
+        }
+    }
+}
+
+/// Whether a JS evaluation went past the limits set on its context.
+pub fn is_runtime_limit(error: &JsError) -> bool {
+    error
+        .as_native()
+        .is_some_and(|error| matches!(error.kind, JsNativeErrorKind::RuntimeLimit))
+}
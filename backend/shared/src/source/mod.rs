@@ -1,11 +1,13 @@
 use aidoku::FilterValue;
 use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
 use reqwest::{header::HeaderMap, Method, Request, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs,
     io::Read,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 use tokio_util::bytes::Bytes;
@@ -15,7 +17,9 @@ use wasmi::*;
 use zip::ZipArchive;
 
 use crate::{
-    settings::SourceSettingValue,
+    download_scheduler::RateLimit,
+    model::{SourceCrashes, SourceId},
+    settings::{Settings, SourceSettingValue},
     source::{
         next_reader::read_next,
         wasm_imports::next as sdk_next,
@@ -25,7 +29,10 @@ use crate::{
 };
 
 use self::{
+    limits::source_limits,
     model::{Chapter, Filter, Manga, MangaPageResult, Page, SettingDefinition},
+    pool::SourcePool,
+    sandbox::SourcePolicy,
     source_settings::SourceSettings,
     wasm_imports::{
         aidoku::register_aidoku_imports,
@@ -37,12 +44,15 @@ use self::{
         std::register_std_imports,
     },
     wasm_store::{
-        ObjectValue, OperationContext, OperationContextObject, RequestBuildingState, RequestState,
+        ObjectValue, OperationContext, OperationContextObject, RequestState, SharedRateLimit,
         Value, ValueMap, WasmStore,
     },
 };
 
 pub(crate) mod decode_image;
+#[cfg(feature = "all")]
+mod headless_webview;
+pub mod limits;
 
 #[cfg(not(feature = "all"))]
 pub mod html_element;
@@ -53,6 +63,10 @@ pub mod model;
 pub mod next_reader;
 #[cfg(feature = "all")]
 mod next_reader;
+pub mod pool;
+pub mod request_log;
+pub mod request_observer;
+pub mod sandbox;
 #[cfg(not(feature = "all"))]
 pub mod source_settings;
 #[cfg(feature = "all")]
@@ -66,13 +80,14 @@ pub mod wasm_store;
 #[cfg(feature = "all")]
 mod wasm_store;
 
+pub(crate) use self::wasm_store::{RequestBuildingState, ResponseData, SourceOperation};
+
 /**
  * params need mark encode
  * handle_notification
  * handle_deep_link
  * handle_basic_login
  * handle_web_login
- * handle_key_migration
  *
  */
 
@@ -85,11 +100,11 @@ pub struct Source(
     /// program to panic (see https://github.com/seanmonstar/reqwest/issues/1017), and we do call
     /// them inside the `net` module.
     ///
-    /// This also provides interior mutability, but we probably could also do it inside the
-    /// `BlockingSource` itself, by placing things inside a mutex. It might be a cleaner design.
+    /// Each source keeps a small pool of `BlockingSource`s sharing the same compiled module, so
+    /// that concurrent operations on the same source don't wait for each other.
     #[cfg(feature = "all")]
-    Arc<Mutex<BlockingSource>>,
-    #[cfg(not(feature = "all"))] pub Arc<Mutex<BlockingSource>>,
+    Arc<SourcePool>,
+    #[cfg(not(feature = "all"))] pub Arc<SourcePool>,
     pub SourceFeatures,
 );
 
@@ -97,9 +112,14 @@ pub struct Source(
 macro_rules! wrap_blocking_source_fn {
     ($fn_name:ident, $return_type:ty, $($param:ident : $type:ty),*) => {
         pub async fn $fn_name(&self, $($param: $type),*) -> $return_type {
-            let blocking_source = self.0.clone();
+            let pool = self.0.clone();
 
-            ::tokio::task::spawn_blocking(move || blocking_source.lock().unwrap().$fn_name($($param),*)).await?
+            ::tokio::task::spawn_blocking(move || {
+                let mut blocking_source = pool.take()?;
+
+                blocking_source.$fn_name($($param),*)
+            })
+            .await?
         }
     };
 }
@@ -114,8 +134,9 @@ macro_rules! call_cleanup {
         as $result_ty:ty,
         parse = $parse_fn:expr
     ) => {{
-        let result_descriptor = {$func.call(&mut $blocking.store, ($($args),*))
-            .expect("wasm call failed")};
+        let result_descriptor = $func
+            .call(&mut $blocking.store, ($($args),*))
+            .map_err(|error| $blocking.mark_crashed(error))?;
 
         let parsed: Result<$result_ty> = {
             let store: &mut Store<WasmStore> = &mut $blocking.store;
@@ -139,7 +160,8 @@ impl Source {
         arc_manager: &Arc<tokio::sync::Mutex<SourceManager>>,
     ) -> Result<Self> {
         #[cfg(feature = "all")]
-        let mut blocking_source = BlockingSource::from_aix_file(path, manager, arc_manager, None)?;
+        let (template, mut blocking_source) =
+            SourceTemplate::load(path, &manager.settings, arc_manager, None)?;
 
         #[cfg(feature = "all")]
         if blocking_source.next_sdk {
@@ -147,20 +169,28 @@ impl Source {
         }
 
         #[cfg(not(feature = "all"))]
-        let blocking_source = BlockingSource::from_aix_file(path, manager, arc_manager, None)?;
+        let (template, blocking_source) =
+            SourceTemplate::load(path, &manager.settings, arc_manager, None)?;
 
         let features = { blocking_source.features.clone() };
 
-        Ok(Self(Arc::new(Mutex::new(blocking_source)), features))
+        Ok(Self(
+            Arc::new(SourcePool::new(template, blocking_source)),
+            features,
+        ))
     }
 
     pub fn manifest(&self) -> SourceManifest {
         // FIXME we dont actually need to clone here but yeah it's easier
-        self.0.lock().unwrap().manifest.clone()
+        self.0.manifest().clone()
     }
 
     pub fn setting_definitions(&self) -> Vec<SettingDefinition> {
-        self.0.lock().unwrap().setting_definitions.clone()
+        self.0.setting_definitions().to_vec()
+    }
+
+    pub fn policy(&self) -> SourcePolicy {
+        self.0.policy().clone()
     }
 
     pub fn write_meta_file(path: &Path, source_of_source: String) -> anyhow::Result<()> {
@@ -174,6 +204,11 @@ impl Source {
         .context("while writing meta file")
     }
 
+    /// The rate limit declared by the source through `net.set_rate_limit`, if any.
+    pub async fn declared_rate_limit(&self) -> Result<Option<RateLimit>> {
+        Ok(self.0.declared_rate_limit())
+    }
+
     wrap_blocking_source_fn!(
         get_manga_list,
         Result<Vec<Manga>>,
@@ -275,6 +310,26 @@ impl Source {
         cancellation_token: CancellationToken,
         key: String
     );
+
+    wrap_blocking_source_fn!(
+        handle_key_migration_next,
+        Result<String>,
+        cancellation_token: CancellationToken,
+        manga_key: String,
+        chapter_key: Option<String>
+    );
+
+    wrap_blocking_source_fn!(
+        get_listings_next,
+        Result<Vec<aidoku::Listing>>,
+        cancellation_token: CancellationToken
+    );
+
+    wrap_blocking_source_fn!(
+        get_home_next,
+        Result<aidoku::HomeLayout>,
+        cancellation_token: CancellationToken
+    );
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -312,6 +367,9 @@ pub struct SourceManifest {
 #[derive(Debug, Clone)]
 pub struct SourceFeatures {
     pub process_page_image: bool,
+    /// Whether the source can tell the new keys of mangas and chapters stored with the keys of
+    /// a previous version.
+    pub handle_key_migration: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -321,6 +379,55 @@ pub struct SourceMeta {
     pub is_next_sdk: Option<bool>,
 }
 
+/// A call into the source's WASM module trapped (or the source aborted), leaving its instance
+/// in an unknown state. The instance is dropped, and replaced by a new one when needed.
+#[derive(thiserror::Error, Debug)]
+#[error("source {source_id} crashed: {reason}")]
+pub struct SourceCrashError {
+    pub source_id: String,
+    pub reason: String,
+}
+
+/// A call into the source went past its limits (see `limits`), and was stopped.
+#[derive(thiserror::Error, Debug)]
+#[error("source timed out")]
+pub struct SourceTimedOutError {
+    pub source_id: String,
+}
+
+static CRASHES: Lazy<Mutex<HashMap<String, SourceCrashes>>> = Lazy::new(Default::default);
+
+/// How many times the source crashed, and how many of its instances were replaced since the
+/// server started.
+pub fn source_crashes(source_id: &SourceId) -> SourceCrashes {
+    CRASHES
+        .lock()
+        .unwrap()
+        .get(source_id.value())
+        .cloned()
+        .unwrap_or_else(|| SourceCrashes::new(source_id.clone()))
+}
+
+fn record_crash(source_id: &str, reason: String) {
+    eprintln!("Warn[{source_id}]: source crashed: {reason}");
+
+    let mut crashes = CRASHES.lock().unwrap();
+    let entry = crashes
+        .entry(source_id.to_owned())
+        .or_insert_with(|| SourceCrashes::new(SourceId::new(source_id.to_owned())));
+
+    entry.crashes += 1;
+    entry.last_error = Some(reason);
+    entry.last_crashed_at = Some(chrono::Utc::now().timestamp());
+}
+
+// Counts at most one recovery per crash, so that the calls going fine afterwards don't add up.
+fn record_recovery(source_id: &str) {
+    if let Some(entry) = CRASHES.lock().unwrap().get_mut(source_id) {
+        entry.recoveries = entry.crashes.min(entry.recoveries + 1);
+    }
+}
+
 fn get_memory(instance: Instance, store: &mut Store<WasmStore>) -> Result<Memory> {
     match instance.get_export(store, "memory") {
         Some(Extern::Memory(memory)) => Ok(memory),
@@ -347,6 +454,7 @@ pub struct BlockingSource {
     pub setting_definitions: Vec<SettingDefinition>,
     pub next_sdk: bool,
     pub features: SourceFeatures,
+    pub crashed: bool,
 }
 #[cfg(feature = "all")]
 struct BlockingSource {
@@ -357,15 +465,34 @@ struct BlockingSource {
     setting_definitions: Vec<SettingDefinition>,
     pub next_sdk: bool,
     pub features: SourceFeatures,
+    // Set when a call into the module fails, as its memory may be left inconsistent
+    crashed: bool,
 }
 
-impl BlockingSource {
-    pub fn from_aix_file(
+// Everything needed to create instances of a source, shared by all of them: the compiled
+// module, the source settings, the rate limit and the sandbox policy.
+struct SourceTemplate {
+    id: String,
+    path: PathBuf,
+    engine: Engine,
+    module: Module,
+    manifest: SourceManifest,
+    setting_definitions: Vec<SettingDefinition>,
+    next_sdk: bool,
+    source_settings: Arc<SourceSettings>,
+    settings: Settings,
+    rate_limit: SharedRateLimit,
+    policy: Arc<SourcePolicy>,
+}
+
+impl SourceTemplate {
+    // Loads the source from its `.aix` file, along with its first instance.
+    fn load(
         path: &Path,
-        manager: &SourceManager,
+        settings: &Settings,
         arc_manager: &Arc<tokio::sync::Mutex<SourceManager>>,
         force_mode: Option<bool>,
-    ) -> Result<Self> {
+    ) -> Result<(Self, BlockingSource)> {
         let file =
             fs::File::open(path).with_context(|| format!("couldn't open {}", path.display()))?;
         let mut archive = ZipArchive::new(file)
@@ -377,7 +504,7 @@ impl BlockingSource {
         let (manifest, aidoku_sdk_next_from_meta): (SourceManifest, Option<bool>) = {
             let mut manifest: SourceManifest = serde_json::from_reader(manifest_file)?;
 
-            let meta_file = Self::meta_source_path(path)?;
+            let meta_file = BlockingSource::meta_source_path(path)?;
 
             let mut is_next_sdk = None;
             if fs::exists(&meta_file).unwrap_or(false) {
@@ -424,12 +551,12 @@ impl BlockingSource {
         }
 
         let aidoku_sdk_next = force_mode.unwrap_or_else(|| {
-            aidoku_sdk_next_from_meta
-                .unwrap_or_else(|| Self::is_aidoku_sdk_next(&manifest.info.min_app_version))
+            aidoku_sdk_next_from_meta.unwrap_or_else(|| {
+                BlockingSource::is_aidoku_sdk_next(&manifest.info.min_app_version)
+            })
         });
 
-        let stored_source_settings = manager
-            .settings
+        let stored_source_settings = settings
             .source_settings
             .get(&manifest.info.id)
             .cloned()
@@ -456,20 +583,79 @@ impl BlockingSource {
             .read_to_end(&mut wasm_bytes)
             .with_context(|| format!("failed reading wasm from zip entry {}", path.display()))?;
 
-        let engine = Engine::default();
-        let wasm_store = WasmStore::new(
-            manifest.info.id.clone(),
-            source_settings,
-            manager.settings.clone(),
-        );
-        let mut store = Store::new(&engine, wasm_store);
+        let mut config = Config::default();
+        config.consume_fuel(true);
 
+        let engine = Engine::new(&config);
         let module = Module::new(&engine, &wasm_bytes)
             .with_context(|| format!("failed loading module from {}", path.display()))?;
 
-        let mut linker = Linker::new(&engine);
+        let policy = SourcePolicy::new(
+            &manifest.info,
+            &settings.source_sandbox,
+            &stored_source_settings,
+        );
 
-        if aidoku_sdk_next {
+        let mut template = Self {
+            id,
+            path: path.to_owned(),
+            engine,
+            module,
+            manifest,
+            setting_definitions,
+            next_sdk: aidoku_sdk_next,
+            source_settings: Arc::new(source_settings),
+            settings: settings.clone(),
+            rate_limit: SharedRateLimit::default(),
+            policy: Arc::new(policy),
+        };
+
+        let mut blocking_source = template.instantiate();
+        if blocking_source.is_err() && force_mode.is_none() {
+            println!(
+                "Info: failed instantiating {} retry mode {}",
+                template.id,
+                if template.next_sdk { "legacy" } else { "next" }
+            );
+
+            template.next_sdk = !template.next_sdk;
+            blocking_source = template.instantiate();
+        }
+        let blocking_source =
+            blocking_source.inspect_err(|error| eprintln!("Error instantiating: {:?}", error))?;
+
+        if aidoku_sdk_next_from_meta.is_none()
+            || aidoku_sdk_next_from_meta.unwrap() != template.next_sdk
+        {
+            let meta_file = BlockingSource::meta_source_path(path)?;
+
+            let _ = fs::write(
+                &meta_file,
+                serde_json::to_string(&SourceMeta {
+                    source_of_source: template.manifest.source_of_source.clone(),
+                    is_next_sdk: Some(template.next_sdk),
+                })?,
+            );
+        }
+
+        Ok((template, blocking_source))
+    }
+
+    fn instantiate(&self) -> Result<BlockingSource> {
+        let wasm_store = WasmStore::new(
+            self.id.clone(),
+            self.source_settings.clone(),
+            self.settings.clone(),
+            self.rate_limit.clone(),
+            self.policy.clone(),
+        );
+        let mut store = Store::new(&self.engine, wasm_store);
+        store.set_fuel(source_limits().fuel(SourceOperation::Other))?;
+        store.limiter(|wasm_store| &mut wasm_store.limits);
+
+        let mut linker = Linker::new(&self.engine);
+
+        if self.next_sdk {
             // register_aidoku_imports(&mut linker)?;
             // register_json_imports(&mut linker)?;
             sdk_next::register_std_imports(&mut linker)?; // ok
@@ -489,25 +675,9 @@ impl BlockingSource {
             register_std_imports(&mut linker)?;
         }
 
-        let instance = match linker
-            .instantiate_and_start(&mut store, &module)
-            .with_context(|| format!("failed creating instance from {}", path.display()))
-        {
-            Ok(instance) => instance,
-            Err(error) => {
-                if force_mode.is_none() {
-                    println!(
-                        "Info: failed instantiating {id} retry mode {}",
-                        if aidoku_sdk_next { "legacy" } else { "next" }
-                    );
-
-                    return Self::from_aix_file(path, manager, arc_manager, Some(!aidoku_sdk_next));
-                }
-
-                eprintln!("Error instantiating: {:?}", error);
-                return Err(error);
-            }
-        };
+        let instance = linker
+            .instantiate_and_start(&mut store, &self.module)
+            .with_context(|| format!("failed creating instance from {}", self.path.display()))?;
 
         let features = SourceFeatures {
             process_page_image: instance
@@ -515,33 +685,86 @@ impl BlockingSource {
                 .map(|_| true)
                 .ok()
                 .unwrap_or(false),
+            handle_key_migration: self.next_sdk
+                && instance
+                    .get_typed_func::<(i32, i32), i32>(&mut store, "handle_key_migration")
+                    .is_ok(),
         };
 
-        if aidoku_sdk_next_from_meta.is_none()
-            || aidoku_sdk_next_from_meta.unwrap() != aidoku_sdk_next
-        {
-            let meta_file = Self::meta_source_path(path)?;
-
-            let _ = fs::write(
-                &meta_file,
-                serde_json::to_string(&SourceMeta {
-                    source_of_source: manifest.source_of_source.clone(),
-                    is_next_sdk: Some(aidoku_sdk_next),
-                })?,
-            );
-        }
-
-        Ok(Self {
-            id,
+        Ok(BlockingSource {
+            id: self.id.clone(),
             store,
             instance,
-            manifest,
-            next_sdk: aidoku_sdk_next,
-            setting_definitions,
+            manifest: self.manifest.clone(),
+            next_sdk: self.next_sdk,
+            setting_definitions: self.setting_definitions.clone(),
             features,
+            crashed: false,
         })
     }
 
+    // Creates an instance ready to be used, for the pool to add.
+    fn create_instance(&self) -> Result<BlockingSource> {
+        let mut blocking_source = self
+            .instantiate()
+            .with_context(|| format!("while creating an instance of {}", self.id))?;
+
+        if blocking_source.next_sdk {
+            blocking_source.start()?;
+        }
+
+        Ok(blocking_source)
+    }
+}
+
+impl BlockingSource {
+    #[cfg(not(feature = "all"))]
+    pub fn from_aix_file(
+        path: &Path,
+        manager: &SourceManager,
+        arc_manager: &Arc<tokio::sync::Mutex<SourceManager>>,
+        force_mode: Option<bool>,
+    ) -> Result<Self> {
+        SourceTemplate::load(path, &manager.settings, arc_manager, force_mode)
+            .map(|(_, blocking_source)| blocking_source)
+    }
+
+    // Gives the module the fuel budget of the operation about to run.
+    fn refuel(&mut self, operation: SourceOperation) {
+        // Fuel metering is always enabled on the engine, so this can't fail
+        let _ = self.store.set_fuel(source_limits().fuel(operation));
+    }
+
+    // Marks the instance as crashed, so that the pool replaces it instead of reusing it.
+    fn mark_crashed(&mut self, error: wasmi::Error) -> anyhow::Error {
+        let reason = error.to_string();
+
+        // The call was stopped halfway, so the instance is replaced either way, but running out
+        // of fuel is a timeout and not a crash of the source
+        self.crashed = true;
+        if let Some(wasmi::core::TrapCode::OutOfFuel) = error.as_trap_code() {
+            return SourceTimedOutError {
+                source_id: self.id.clone(),
+            }
+            .into();
+        }
+
+        record_crash(&self.id, reason.clone());
+
+        match error.as_trap_code() {
+            Some(wasmi::core::TrapCode::GrowthOperationLimited) => {
+                return self.store.data().policy.memory_limit_exceeded();
+            }
+            _ => {}
+        }
+
+        SourceCrashError {
+            source_id: self.id.clone(),
+            reason,
+        }
+        .into()
+    }
+
     pub fn meta_source_path(path: &Path) -> anyhow::Result<std::path::PathBuf> {
         let parent = path
             .parent()
@@ -672,11 +895,12 @@ impl BlockingSource {
                 )
                 .map(|v| Manga::from(v, self.id.clone()));
         }
-        self.run_under_context(
+        self.run_operation(
             cancellation_token,
             OperationContextObject::Manga {
                 id: manga_id.clone(),
             },
+            SourceOperation::MangaDetails,
             |this| this.get_manga_details_inner(manga_id),
         )
     }
@@ -776,11 +1000,12 @@ impl BlockingSource {
                         .collect::<Vec<_>>()
                 });
         }
-        self.run_under_context(
+        self.run_operation(
             cancellation_token,
             OperationContextObject::Manga {
                 id: manga_id.clone(),
             },
+            SourceOperation::ChapterList,
             |this| this.get_chapter_list_inner(manga_id),
         )
     }
@@ -869,11 +1094,13 @@ impl BlockingSource {
                         .collect()
                 });
         }
-        self.run_under_context(
+        self.run_operation(
             cancellation_token,
             OperationContextObject::Chapter {
                 id: chapter_id.clone(),
+                manga_id: manga_id.clone(),
             },
+            SourceOperation::PageList,
             |this| this.get_page_list_inner(manga_id, chapter_id, chapter_num),
         )
     }
@@ -982,7 +1209,9 @@ impl BlockingSource {
                 .instance
                 .get_typed_func::<i32, ()>(&mut wasm_store, "modify_image_request")
             {
-                wasm_function.call(&mut wasm_store, request_descriptor as i32)?;
+                wasm_function
+                    .call(&mut wasm_store, request_descriptor as i32)
+                    .map_err(|error| self.mark_crashed(error))?;
             }
         }
 
@@ -1008,7 +1237,9 @@ impl BlockingSource {
             .instance
             .get_typed_func::<(), ()>(&mut self.store, "start")?;
 
-        wasm_function.call(&mut self.store, ())?;
+        wasm_function
+            .call(&mut self.store, ())
+            .map_err(|error| self.mark_crashed(error))?;
 
         Ok(())
     }
@@ -1017,7 +1248,9 @@ impl BlockingSource {
             .instance
             .get_typed_func::<i32, ()>(&mut self.store, "free_memory")?;
 
-        wasm_function.call(&mut self.store, pointer)?;
+        wasm_function
+            .call(&mut self.store, pointer)
+            .map_err(|error| self.mark_crashed(error))?;
 
         Ok(())
     }
@@ -1078,9 +1311,20 @@ impl BlockingSource {
         needs_details: bool,
         needs_chapters: bool,
     ) -> Result<aidoku::Manga> {
-        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
-            this.get_manga_update_next_inner(manga, needs_details, needs_chapters)
-        })
+        let operation = if needs_chapters {
+            SourceOperation::ChapterList
+        } else {
+            SourceOperation::MangaDetails
+        };
+
+        self.run_operation(
+            cancellation_token,
+            OperationContextObject::Manga {
+                id: manga.key.clone(),
+            },
+            operation,
+            |this| this.get_manga_update_next_inner(manga, needs_details, needs_chapters),
+        )
     }
 
     fn get_manga_update_next_inner(
@@ -1119,9 +1363,15 @@ impl BlockingSource {
         manga: aidoku::Manga,
         chapter: aidoku::Chapter,
     ) -> Result<Vec<aidoku::Page>> {
-        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
-            this.get_page_list_next_inner(manga, chapter)
-        })
+        self.run_operation(
+            cancellation_token,
+            OperationContextObject::Chapter {
+                id: chapter.key.clone(),
+                manga_id: manga.key.clone(),
+            },
+            SourceOperation::PageList,
+            |this| this.get_page_list_next_inner(manga, chapter),
+        )
     }
 
     fn get_page_list_next_inner(
@@ -1188,7 +1438,10 @@ impl BlockingSource {
                 .get_typed_func::<(i32, i32), i32>(&mut self.store, "get_image_request");
 
             match wasm_function {
-                Ok(func) => Some(func.call(&mut self.store, (url_key, context_key))?),
+                Ok(func) => Some(
+                    func.call(&mut self.store, (url_key, context_key))
+                        .map_err(|error| self.mark_crashed(error))?,
+                ),
                 Err(_) => None,
             }
         };
@@ -1432,6 +1685,49 @@ impl BlockingSource {
         Ok(result)
     }
 
+    /// The listings the source provides besides the ones in its manifest.
+    pub fn get_listings_next(
+        &mut self,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<aidoku::Listing>> {
+        if !self.next_sdk {
+            bail!("listings are only supported by sources using the new SDK");
+        }
+
+        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+            this.call_next_export("get_listings")
+        })
+    }
+
+    pub fn get_home_next(
+        &mut self,
+        cancellation_token: CancellationToken,
+    ) -> Result<aidoku::HomeLayout> {
+        if !self.next_sdk {
+            bail!("home is only supported by sources using the new SDK");
+        }
+
+        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+            this.call_next_export("get_home")
+        })
+    }
+
+    // Calls an export taking no arguments, reading its result.
+    fn call_next_export<T: serde::de::DeserializeOwned>(&mut self, name: &str) -> Result<T> {
+        let wasm_function = self
+            .instance
+            .get_typed_func::<(), i32>(&mut self.store, name)?;
+        let pointer = wasm_function
+            .call(&mut self.store, ())
+            .map_err(|error| self.mark_crashed(error))?;
+
+        let memory = get_memory(self.instance, &mut self.store)?;
+        let result = read_next::<T>(&memory, &self.store, pointer);
+        let _ = self.free_result(pointer);
+
+        result
+    }
+
     pub fn handle_notification_next(
         &mut self,
         cancellation_token: CancellationToken,
@@ -1451,12 +1747,78 @@ impl BlockingSource {
 
         let key = store.store_std_value(Value::from(key).into(), None);
 
-        wasm_function.call(&mut self.store, key as i32)?;
+        wasm_function
+            .call(&mut self.store, key as i32)
+            .map_err(|error| self.mark_crashed(error))?;
         let _ = &self.store.data_mut().take_std_value(key);
 
         Ok(())
     }
 
+    /// Asks the source for the current key of a manga (or of one of its chapters, when
+    /// `chapter_key` is set) stored with the key of a previous version.
+    pub fn handle_key_migration_next(
+        &mut self,
+        cancellation_token: CancellationToken,
+        manga_key: String,
+        chapter_key: Option<String>,
+    ) -> Result<String> {
+        let current_object = match &chapter_key {
+            Some(chapter_key) => OperationContextObject::Chapter {
+                id: chapter_key.clone(),
+                manga_id: manga_key.clone(),
+            },
+            None => OperationContextObject::Manga {
+                id: manga_key.clone(),
+            },
+        };
+
+        self.run_under_context(cancellation_token, current_object, |this| {
+            this.handle_key_migration_next_inner(manga_key, chapter_key)
+        })
+    }
+
+    fn handle_key_migration_next_inner(
+        &mut self,
+        manga_key: String,
+        chapter_key: Option<String>,
+    ) -> Result<String> {
+        let wasm_function = self
+            .instance
+            .get_typed_func::<(i32, i32), i32>(&mut self.store, "handle_key_migration")?;
+
+        let store = self.store.data_mut();
+
+        let manga_key = store.store_std_value(Value::from(manga_key).into(), None);
+        // A negative descriptor asks for the key of the manga itself
+        let chapter_key = chapter_key
+            .map(|chapter_key| store.store_std_value(Value::from(chapter_key).into(), None) as i32)
+            .unwrap_or(-1);
+
+        // `call_cleanup!` returns early when the call traps, so the keys are freed out of it
+        let key = (|| -> Result<String> {
+            call_cleanup!(
+            blocking = self,
+            func = wasm_function,
+            args = (manga_key as i32, chapter_key),
+            free = [],
+            as String,
+            parse = |pointer, store: &mut Store<WasmStore>, instance| {
+                let memory = get_memory(instance, store)?;
+
+                read_next::<String>(&memory, &store, pointer)
+            })
+        })();
+
+        let store = self.store.data_mut();
+        store.take_std_value(manga_key);
+        if chapter_key >= 0 {
+            store.take_std_value(chapter_key as usize);
+        }
+
+        key
+    }
+
     pub fn run_under_context<T, F>(
         &mut self,
         cancellation_token: CancellationToken,
@@ -1466,9 +1828,29 @@ impl BlockingSource {
     where
         F: FnOnce(&mut Self) -> T,
     {
+        self.run_operation(
+            cancellation_token,
+            current_object,
+            SourceOperation::Other,
+            f,
+        )
+    }
+
+    pub fn run_operation<T, F>(
+        &mut self,
+        cancellation_token: CancellationToken,
+        current_object: OperationContextObject,
+        operation: SourceOperation,
+        f: F,
+    ) -> T
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        self.refuel(operation);
         self.store.data_mut().context = OperationContext {
             cancellation_token,
             current_object,
+            operation,
         };
 
         let result = f(self);
@@ -0,0 +1,157 @@
+// The instances of a source. Each call takes an idle instance, creates a new one if there are
+// less than `instances_per_source`, or waits for one to be given back otherwise. Instances
+// share the compiled module, the source settings and the rate limit (see `SourceTemplate`), so
+// a call behaves the same whichever instance it gets.
+//
+// Instances that crashed (or panicked) are dropped when given back instead of being reused,
+// and replaced by new ones when needed.
+
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Condvar, Mutex},
+};
+
+use anyhow::Result;
+
+use crate::download_scheduler::RateLimit;
+
+use super::{
+    limits::source_limits, model::SettingDefinition, record_crash, record_recovery,
+    sandbox::SourcePolicy, BlockingSource, SourceManifest, SourceOperation, SourceTemplate,
+};
+
+pub struct SourcePool {
+    template: SourceTemplate,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+struct PoolState {
+    idle: Vec<BlockingSource>,
+    // Idle instances, and the ones currently in use
+    created: usize,
+}
+
+impl SourcePool {
+    pub(super) fn new(template: SourceTemplate, blocking_source: BlockingSource) -> Self {
+        Self {
+            template,
+            state: Mutex::new(PoolState {
+                idle: vec![blocking_source],
+                created: 1,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    pub fn manifest(&self) -> &SourceManifest {
+        &self.template.manifest
+    }
+
+    pub fn setting_definitions(&self) -> &[SettingDefinition] {
+        &self.template.setting_definitions
+    }
+
+    pub fn policy(&self) -> &SourcePolicy {
+        &self.template.policy
+    }
+
+    /// The rate limit declared by the source through `net.set_rate_limit`, if any.
+    pub fn declared_rate_limit(&self) -> Option<RateLimit> {
+        self.template.rate_limit.declared()
+    }
+
+    /// Takes an idle instance, creating one if there's room for it, or waiting for one to be
+    /// given back otherwise.
+    pub(super) fn take(&self) -> Result<PooledSource<'_>> {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(blocking_source) = state.idle.pop() {
+                return Ok(PooledSource::new(self, blocking_source));
+            }
+
+            if state.created < source_limits().instances_per_source() {
+                state.created += 1;
+                drop(state);
+
+                return match self.template.create_instance() {
+                    Ok(blocking_source) => Ok(PooledSource::new(self, blocking_source)),
+                    Err(error) => {
+                        self.state.lock().unwrap().created -= 1;
+                        self.available.notify_one();
+
+                        Err(error)
+                    }
+                };
+            }
+
+            state = self.available.wait(state).unwrap();
+        }
+    }
+
+    fn give_back(&self, blocking_source: BlockingSource) {
+        let mut state = self.state.lock().unwrap();
+
+        // A call which went fine after a crash means the source recovered from it
+        if !blocking_source.crashed {
+            record_recovery(&blocking_source.id);
+        }
+
+        // Also shrinks the pool when `instances_per_source` was lowered
+        if blocking_source.crashed || state.created > source_limits().instances_per_source() {
+            state.created -= 1;
+        } else {
+            state.idle.push(blocking_source);
+        }
+
+        drop(state);
+        self.available.notify_one();
+    }
+}
+
+/// An instance taken from the pool, given back once dropped.
+pub(super) struct PooledSource<'a> {
+    pool: &'a SourcePool,
+    blocking_source: Option<BlockingSource>,
+}
+
+impl<'a> PooledSource<'a> {
+    fn new(pool: &'a SourcePool, mut blocking_source: BlockingSource) -> Self {
+        blocking_source.refuel(SourceOperation::Other);
+
+        Self {
+            pool,
+            blocking_source: Some(blocking_source),
+        }
+    }
+}
+
+impl Deref for PooledSource<'_> {
+    type Target = BlockingSource;
+
+    fn deref(&self) -> &Self::Target {
+        self.blocking_source.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledSource<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.blocking_source.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledSource<'_> {
+    fn drop(&mut self) {
+        let Some(mut blocking_source) = self.blocking_source.take() else {
+            return;
+        };
+
+        if std::thread::panicking() && !blocking_source.crashed {
+            blocking_source.crashed = true;
+            record_crash(&blocking_source.id, "panicked while running".to_owned());
+        }
+
+        self.pool.give_back(blocking_source);
+    }
+}
@@ -0,0 +1,68 @@
+// The latest requests made by (or for) each source, kept in memory so that users can attach them
+// to bug reports when a source breaks. Only a bounded number of requests is kept per source,
+// oldest ones being dropped first.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::model::SourceId;
+
+use super::request_observer::{ObservedRequest, RequestOperation};
+
+const MAX_REQUESTS_PER_SOURCE: usize = 200;
+
+static REQUEST_LOG: Lazy<Mutex<HashMap<String, VecDeque<LoggedRequest>>>> =
+    Lazy::new(Default::default);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LoggedRequest {
+    pub method: Option<String>,
+    pub url: Option<String>,
+    pub status: Option<u16>,
+    /// The size of the response body, in bytes.
+    pub size: Option<usize>,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+    pub operation: RequestOperation,
+    pub manga_id: Option<String>,
+    pub chapter_id: Option<String>,
+    /// When the request was done, as a UNIX timestamp.
+    pub logged_at: i64,
+}
+
+pub(crate) fn log(request: &ObservedRequest) {
+    let logged = LoggedRequest {
+        method: request.method.as_ref().map(ToString::to_string),
+        url: request.url.as_ref().map(ToString::to_string),
+        status: request.status.map(|status| status.as_u16()),
+        size: request.size,
+        elapsed_ms: request.elapsed.as_millis() as u64,
+        error: request.error.clone(),
+        operation: request.operation,
+        manga_id: request.manga_id.clone(),
+        chapter_id: request.chapter_id.clone(),
+        logged_at: chrono::Utc::now().timestamp(),
+    };
+
+    let mut log = REQUEST_LOG.lock().unwrap();
+    let requests = log.entry(request.source_id.clone()).or_default();
+    if requests.len() == MAX_REQUESTS_PER_SOURCE {
+        requests.pop_front();
+    }
+    requests.push_back(logged);
+}
+
+/// The latest requests made by (or for) a source since the server started, oldest first.
+pub fn source_requests(source_id: &SourceId) -> Vec<LoggedRequest> {
+    REQUEST_LOG
+        .lock()
+        .unwrap()
+        .get(source_id.value())
+        .map(|requests| requests.iter().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,70 @@
+// Lets tools built on top of the sources, like the source development CLI, see the requests
+// sources make as they're made. The requests sent through `RequestSender` are observed, so
+// responses served by the HTTP cache show up too, as well as the page images fetched when
+// downloading chapters. Every observed request also ends up in the request log of its source
+// (see `request_log`).
+
+use std::{sync::RwLock, time::Duration};
+
+use once_cell::sync::Lazy;
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use url::Url;
+
+use super::{request_log, SourceOperation};
+
+type Observer = Box<dyn Fn(&ObservedRequest) + Send + Sync>;
+
+static OBSERVERS: Lazy<RwLock<Vec<Observer>>> = Lazy::new(Default::default);
+
+#[derive(Clone, Debug)]
+pub struct ObservedRequest {
+    pub source_id: String,
+    pub method: Option<Method>,
+    pub url: Option<Url>,
+    pub status: Option<StatusCode>,
+    /// The size of the response body, in bytes.
+    pub size: Option<usize>,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+    pub operation: RequestOperation,
+    /// The manga the operation was about, if any.
+    pub manga_id: Option<String>,
+    pub chapter_id: Option<String>,
+}
+
+/// What the source was doing when the request was made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestOperation {
+    MangaDetails,
+    ChapterList,
+    PageList,
+    Other,
+    /// Fetching the image of a page, while downloading a chapter.
+    PageImage,
+}
+
+impl From<SourceOperation> for RequestOperation {
+    fn from(operation: SourceOperation) -> Self {
+        match operation {
+            SourceOperation::MangaDetails => Self::MangaDetails,
+            SourceOperation::ChapterList => Self::ChapterList,
+            SourceOperation::PageList => Self::PageList,
+            SourceOperation::Other => Self::Other,
+        }
+    }
+}
+
+/// Calls `observer` for every request sent by a source from now on.
+pub fn observe_requests(observer: impl Fn(&ObservedRequest) + Send + Sync + 'static) {
+    OBSERVERS.write().unwrap().push(Box::new(observer));
+}
+
+pub(crate) fn notify(request: ObservedRequest) {
+    request_log::log(&request);
+
+    for observer in OBSERVERS.read().unwrap().iter() {
+        observer(&request);
+    }
+}
@@ -0,0 +1,373 @@
+// What a source is allowed to do, on top of how much work it may do (see `limits`). Sources are
+// third-party code running on the user's network, so by default a source may never send requests
+// to private addresses like the ones of a WebDAV sync server on the LAN, unless they're the hosts
+// of the URLs in its manifest or its settings (e.g. of a self-hosted server), and its responses
+// and memory are capped. Sources can also be restricted to those hosts (and their subdomains).
+// The user can relax or tighten any of it per source through the `source_sandbox` settings.
+//
+// The policy is enforced by the imports (see `wasm_imports`). Violations fail the offending
+// call, and are logged and kept around so that they can be shown to the user.
+//
+// Hosts are checked as written in the URL: a public host name resolving to a private address
+// isn't caught, as resolving it here would mean hitting the network before the HTTP cache (and
+// the fixtures, when replaying them) had a chance to answer.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{Ipv4Addr, Ipv6Addr},
+    sync::Mutex,
+};
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use url::{Host, Url};
+use wasmi::{StoreLimits, StoreLimitsBuilder};
+
+use crate::{
+    model::SourceId,
+    settings::{SourceSandboxSettings, SourceSettingValue},
+};
+
+use super::SourceInfo;
+
+const WASM_PAGE_SIZE: usize = 64 * 1024;
+// How many violations are kept per source.
+const MAX_VIOLATIONS: usize = 20;
+
+static VIOLATIONS: Lazy<Mutex<HashMap<String, VecDeque<SourcePolicyViolation>>>> =
+    Lazy::new(Default::default);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SourcePolicy {
+    #[serde(skip)]
+    source_id: String,
+    /// The hosts the source may reach, subdomains included. Any host may be reached when empty.
+    pub allowed_hosts: Vec<String>,
+    /// The hosts of the source (of its manifest, settings and overrides), subdomains included,
+    /// which it may reach even if they're private.
+    pub exempt_hosts: Vec<String>,
+    pub block_private_networks: bool,
+    /// In bytes.
+    pub max_response_size: Option<usize>,
+    pub max_memory_pages: Option<u32>,
+    pub allow_js: bool,
+    pub allow_canvas: bool,
+    pub allow_webview: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SourcePolicyViolation {
+    pub action: String,
+    pub at: i64,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("source {source_id} isn't allowed to {action}")]
+pub struct SourcePolicyViolationError {
+    pub source_id: String,
+    pub action: String,
+}
+
+impl SourcePolicy {
+    /// `source_settings` are the stored settings of the source, whose URLs (e.g. of a
+    /// self-hosted server) are hosts of the source too.
+    pub fn new(
+        info: &SourceInfo,
+        settings: &SourceSandboxSettings,
+        source_settings: &HashMap<String, SourceSettingValue>,
+    ) -> Self {
+        let overrides = settings.sources.get(&info.id).cloned().unwrap_or_default();
+        let listed_hosts: Vec<String> = info
+            .url
+            .iter()
+            .chain(info.urls.iter().flatten())
+            .filter_map(|url| host_of(url))
+            .chain(
+                overrides
+                    .allowed_hosts
+                    .iter()
+                    .map(|host| host.to_lowercase()),
+            )
+            .collect();
+        let settings_hosts = source_settings.values().flat_map(|value| match value {
+            SourceSettingValue::String(value) => host_of(value).into_iter().collect(),
+            SourceSettingValue::Vec(values) => {
+                values.iter().filter_map(|value| host_of(value)).collect()
+            }
+            _ => Vec::new(),
+        });
+
+        let restrict_hosts =
+            overrides.restrict_hosts.unwrap_or(settings.restrict_hosts) && !listed_hosts.is_empty();
+        let mut exempt_hosts: Vec<String> =
+            listed_hosts.into_iter().chain(settings_hosts).collect();
+        exempt_hosts.sort();
+        exempt_hosts.dedup();
+
+        let max_response_size_mb = overrides
+            .max_response_size_mb
+            .unwrap_or(settings.max_response_size_mb);
+        let max_memory_pages = overrides
+            .max_memory_pages
+            .unwrap_or(settings.max_memory_pages);
+
+        Self {
+            source_id: info.id.clone(),
+            allowed_hosts: if restrict_hosts {
+                exempt_hosts.clone()
+            } else {
+                Vec::new()
+            },
+            exempt_hosts,
+            block_private_networks: overrides
+                .block_private_networks
+                .unwrap_or(settings.block_private_networks),
+            max_response_size: (max_response_size_mb != 0)
+                .then(|| max_response_size_mb as usize * 1024 * 1024),
+            max_memory_pages: (max_memory_pages != 0).then_some(max_memory_pages),
+            allow_js: overrides.allow_js.unwrap_or(settings.allow_js),
+            allow_canvas: overrides.allow_canvas.unwrap_or(settings.allow_canvas),
+            allow_webview: overrides.allow_webview.unwrap_or(settings.allow_webview),
+        }
+    }
+
+    /// Fails when the source may not send a request to `url`.
+    pub fn check_url(&self, url: &Url) -> anyhow::Result<()> {
+        let Some(host) = url.host() else {
+            return Ok(());
+        };
+        let host_name = host.to_string().to_lowercase();
+        let listed = self
+            .exempt_hosts
+            .iter()
+            .any(|exempt| is_same_or_subdomain(&host_name, exempt));
+
+        if !self.allowed_hosts.is_empty() && !listed {
+            return Err(self.violation(format!(
+                "send a request to {host_name}, which isn't one of its hosts"
+            )));
+        }
+        if self.block_private_networks && !listed && is_private(&host) {
+            return Err(self.violation(format!(
+                "send a request to {host_name}, which is a private address"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Fails when a response of `size` bytes is too large for the source.
+    pub fn check_response_size(&self, url: &Url, size: usize) -> anyhow::Result<()> {
+        match self.max_response_size {
+            Some(max_size) if size > max_size => Err(self.violation(format!(
+                "receive a response of {size} bytes from {url}, over the {max_size} bytes limit"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_js(&self) -> anyhow::Result<()> {
+        self.check_allowed(self.allow_js, "run JS")
+    }
+
+    pub fn check_canvas(&self) -> anyhow::Result<()> {
+        self.check_allowed(self.allow_canvas, "use the canvas")
+    }
+
+    pub fn check_webview(&self) -> anyhow::Result<()> {
+        self.check_allowed(self.allow_webview, "use a WebView")
+    }
+
+    /// The limits of the store of every instance of the source. Growing the memory past the
+    /// limit traps.
+    pub fn store_limits(&self) -> StoreLimits {
+        match self.max_memory_pages {
+            Some(max_memory_pages) => StoreLimitsBuilder::new()
+                .memory_size(max_memory_pages as usize * WASM_PAGE_SIZE)
+                .trap_on_grow_failure(true)
+                .build(),
+            None => StoreLimitsBuilder::new().build(),
+        }
+    }
+
+    /// Records that an instance of the source tried to grow its memory past the limit.
+    pub(crate) fn memory_limit_exceeded(&self) -> anyhow::Error {
+        self.violation(format!(
+            "grow its memory past {} pages",
+            self.max_memory_pages.unwrap_or_default()
+        ))
+    }
+
+    fn check_allowed(&self, allowed: bool, action: &str) -> anyhow::Result<()> {
+        if allowed {
+            Ok(())
+        } else {
+            Err(self.violation(action.to_owned()))
+        }
+    }
+
+    // Logs and records a violation, returning the error to fail the call with.
+    fn violation(&self, action: String) -> anyhow::Error {
+        warn!("source {} isn't allowed to {action}", self.source_id);
+
+        let mut violations = VIOLATIONS.lock().unwrap();
+        let entry = violations.entry(self.source_id.clone()).or_default();
+        if entry.len() == MAX_VIOLATIONS {
+            entry.pop_front();
+        }
+        entry.push_back(SourcePolicyViolation {
+            action: action.clone(),
+            at: chrono::Utc::now().timestamp(),
+        });
+
+        SourcePolicyViolationError {
+            source_id: self.source_id.clone(),
+            action,
+        }
+        .into()
+    }
+}
+
+/// The latest violations of the policy of a source since the server started, oldest first.
+pub fn source_policy_violations(source_id: &SourceId) -> Vec<SourcePolicyViolation> {
+    VIOLATIONS
+        .lock()
+        .unwrap()
+        .get(source_id.value())
+        .map(|violations| violations.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+// The host of a URL, without its `www.` prefix. Anything but a URL has none.
+fn host_of(url: &str) -> Option<String> {
+    let host = Url::parse(url).ok()?.host_str()?.to_lowercase();
+
+    match host.strip_prefix("www.") {
+        Some(domain) => Some(domain.to_owned()),
+        None => Some(host),
+    }
+}
+
+fn is_same_or_subdomain(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+fn is_private(host: &Host<&str>) -> bool {
+    match host {
+        Host::Domain(domain) => {
+            let domain = domain.to_lowercase();
+
+            ["localhost", "local", "lan", "internal", "home.arpa"]
+                .iter()
+                .any(|suffix| is_same_or_subdomain(&domain, suffix))
+        }
+        Host::Ipv4(address) => is_private_ipv4(address),
+        Host::Ipv6(address) => is_private_ipv6(address),
+    }
+}
+
+fn is_private_ipv4(address: &Ipv4Addr) -> bool {
+    let [first, second, ..] = address.octets();
+
+    address.is_private()
+        || address.is_loopback()
+        || address.is_link_local()
+        || address.is_unspecified()
+        || address.is_broadcast()
+        // Shared address space (RFC 6598), used by carrier-grade NATs
+        || (first == 100 && (64..128).contains(&second))
+}
+
+fn is_private_ipv6(address: &Ipv6Addr) -> bool {
+    if let Some(address) = address.to_ipv4_mapped() {
+        return is_private_ipv4(&address);
+    }
+
+    address.is_loopback()
+        || address.is_unspecified()
+        || address.is_unique_local()
+        || address.is_unicast_link_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(settings: SourceSandboxSettings) -> SourcePolicy {
+        policy_with_source_settings(settings, HashMap::new())
+    }
+
+    fn policy_with_source_settings(
+        settings: SourceSandboxSettings,
+        source_settings: HashMap<String, SourceSettingValue>,
+    ) -> SourcePolicy {
+        let info: SourceInfo = serde_json::from_value(serde_json::json!({
+            "id": "en.example",
+            "name": "Example",
+            "version": 1,
+            "url": "https://www.example.com",
+            "urls": ["https://example.org", "http://192.168.1.10:8080"],
+        }))
+        .unwrap();
+
+        SourcePolicy::new(&info, &settings, &source_settings)
+    }
+
+    fn allowed(policy: &SourcePolicy, url: &str) -> bool {
+        policy.check_url(&Url::parse(url).unwrap()).is_ok()
+    }
+
+    #[test]
+    fn only_allows_the_hosts_of_the_manifest() {
+        let policy = policy(SourceSandboxSettings {
+            restrict_hosts: true,
+            ..Default::default()
+        });
+
+        assert!(allowed(&policy, "https://example.com/search"));
+        assert!(allowed(&policy, "https://api.example.com/manga/1"));
+        assert!(allowed(&policy, "https://example.org"));
+        assert!(allowed(&policy, "http://192.168.1.10:8080/api"));
+        assert!(!allowed(&policy, "https://notexample.com"));
+        assert!(!allowed(&policy, "http://192.168.1.11"));
+    }
+
+    #[test]
+    fn blocks_private_addresses_when_hosts_are_not_restricted() {
+        let policy = policy(SourceSandboxSettings {
+            restrict_hosts: false,
+            ..Default::default()
+        });
+
+        assert!(allowed(&policy, "https://cdn.elsewhere.net/image.png"));
+        assert!(allowed(&policy, "http://192.168.1.10:8080/api"));
+        assert!(!allowed(&policy, "http://localhost:5000"));
+        assert!(!allowed(&policy, "http://10.0.0.2/dav"));
+        assert!(!allowed(&policy, "http://[::1]/"));
+        assert!(!allowed(&policy, "http://nas.local/"));
+    }
+
+    #[test]
+    fn exempts_the_hosts_of_the_source_settings() {
+        let policy = policy_with_source_settings(
+            SourceSandboxSettings::default(),
+            HashMap::from([
+                (
+                    "url".to_owned(),
+                    SourceSettingValue::String("http://komga.lan:25600".to_owned()),
+                ),
+                (
+                    "title".to_owned(),
+                    SourceSettingValue::String("http://10.0.0.2 is not a URL".to_owned()),
+                ),
+            ]),
+        );
+
+        assert!(allowed(&policy, "http://komga.lan:25600/api/v1/series"));
+        assert!(!allowed(&policy, "http://nas.lan/"));
+    }
+}
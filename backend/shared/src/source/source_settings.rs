@@ -1,4 +1,7 @@
-use std::{cell::RefCell, collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 use tokio::sync::Mutex;
 
 use anyhow::Result;
@@ -10,10 +13,11 @@ use super::model::SettingDefinition;
 pub struct SourceSettings {
     source_id: String,
     defaults: HashMap<String, SourceSettingValue>,
+    // Shared by all the instances of the source
     #[cfg(feature = "all")]
-    stored: RefCell<HashMap<String, SourceSettingValue>>,
+    stored: RwLock<HashMap<String, SourceSettingValue>>,
     #[cfg(not(feature = "all"))]
-    pub stored: RefCell<HashMap<String, SourceSettingValue>>,
+    pub stored: RwLock<HashMap<String, SourceSettingValue>>,
     arc_manager: Arc<Mutex<SourceManager>>,
 }
 impl std::fmt::Debug for SourceSettings {
@@ -43,26 +47,27 @@ impl SourceSettings {
         Ok(Self {
             source_id,
             defaults,
-            stored: RefCell::new(stored_settings.clone()),
+            stored: RwLock::new(stored_settings.clone()),
             arc_manager: arc_manager.clone(),
         })
     }
 
     pub fn get(&self, key: &String) -> Option<SourceSettingValue> {
         self.stored
-            .borrow()
+            .read()
+            .unwrap()
             .get(key)
             .cloned()
             .or_else(|| self.defaults.get(key).cloned())
     }
 
     pub fn set(&self, key: &str, value: SourceSettingValue) {
-        self.stored.borrow_mut().insert(key.to_owned(), value);
+        self.stored.write().unwrap().insert(key.to_owned(), value);
     }
 
     pub fn save(&self, key: &str, value: SourceSettingValue) -> Result<()> {
         let snapshot = {
-            let mut store = self.stored.borrow_mut();
+            let mut store = self.stored.write().unwrap();
             store.insert(key.to_owned(), value);
             store.clone()
         };
@@ -1,4 +1,7 @@
-use crate::{source::html_element::HTMLElement, util::has_internet_connection};
+use crate::{
+    connectivity::connectivity, http_cache::http_cache, http_fixtures::http_fixtures,
+    source::html_element::HTMLElement,
+};
 use anyhow::{Context, Result};
 use dom_query::Document;
 use futures::executor;
@@ -12,8 +15,14 @@ use wasm_macros::{aidoku_wasm_function, register_wasm_function};
 use wasm_shared::{get_memory, memory_reader::write_bytes};
 use wasmi::{Caller, Linker};
 
-use crate::source::wasm_store::ResponseData;
+use crate::source::request_observer::{self, ObservedRequest};
+use crate::source::sandbox::SourcePolicy;
+use crate::source::wasm_store::{
+    OperationContextObject, RequestBuildingState, ResponseData, SharedRateLimit, SourceOperation,
+};
 use crate::source::wasm_store::{RequestState, Value, WasmStore};
+use std::{sync::Arc, time::Instant};
+use tokio_util::sync::CancellationToken;
 
 #[cfg(not(feature = "all"))]
 pub static NET_SEND: std::sync::OnceLock<
@@ -51,6 +60,10 @@ pub fn register_net_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
 pub const DEFAULT_USER_AGENT: &str =
     "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:107.0) Gecko/20100101 Firefox/107.0";
 
+// Same as reqwest's default.
+#[cfg(feature = "all")]
+const MAX_REDIRECTS: usize = 10;
+
 #[derive(Debug, Default, FromPrimitive)]
 #[repr(u8)]
 enum AidokuHttpMethod {
@@ -185,29 +198,148 @@ pub fn send(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> R
     let request_descriptor_i32: usize = request_descriptor_i32
         .try_into()
         .context("invalid descriptor")?;
-    let wasm_store = caller.data_mut();
-    wasm_store.rate_limit_acquire();
-
-    let cancellation_token = wasm_store.context.cancellation_token.clone();
-    let request_builder = get_building_request(wasm_store, request_descriptor_i32)?;
-
-    // HACK Before everything, we want to fail fast if no internet connection is available.
-    // In theory, it would be easier to just let things fail naturally and move on
-    // with our lives; but DNS resolution takes forever (~5s or so) when we have no connection
-    // available - due to musl's `getaddrinfo()` call not realizing we have no connection and
-    // timing out (EAI_AGAIN). The overhead of checking for a connection here seems worth it.
-    let has_internet_connection =
-        executor::block_on(cancellation_token.run_until_cancelled(has_internet_connection()))
-            .context("failed to check internet connection")?;
-    if !has_internet_connection {
-        anyhow::bail!("no internet connection available");
+
+    send_request(caller.data_mut(), request_descriptor_i32)
+}
+
+/// Sends a request built by the source, through the HTTP cache when it's enabled. The response
+/// may then come from the cache, without the network (nor the rate limit) being involved.
+pub fn send_request(wasm_store: &mut WasmStore, request_descriptor: usize) -> Result<()> {
+    let request_builder = get_building_request(wasm_store, request_descriptor)?.clone();
+    let response_data = RequestSender::new(wasm_store).send(&request_builder)?;
+
+    *wasm_store
+        .get_mut_request(request_descriptor)
+        .context("failed to get request state")? = RequestState::Sent(response_data);
+    Ok(())
+}
+
+/// Sends requests on behalf of a source during the current operation, the same way the source's
+/// own requests are sent. Used for the requests that don't go through a request descriptor, like
+/// the ones made by the scripts of a headless WebView.
+#[derive(Clone)]
+pub struct RequestSender {
+    source_id: String,
+    operation: SourceOperation,
+    cancellation_token: CancellationToken,
+    rate_limit: SharedRateLimit,
+    policy: Arc<SourcePolicy>,
+    manga_id: Option<String>,
+    chapter_id: Option<String>,
+}
+
+impl RequestSender {
+    pub fn new(wasm_store: &WasmStore) -> Self {
+        let (manga_id, chapter_id) = match &wasm_store.context.current_object {
+            OperationContextObject::None => (None, None),
+            OperationContextObject::Manga { id } => (Some(id.clone()), None),
+            OperationContextObject::Chapter { id, manga_id } => {
+                (Some(manga_id.clone()), Some(id.clone()))
+            }
+        };
+
+        Self {
+            source_id: wasm_store.id.clone(),
+            operation: wasm_store.context.operation,
+            cancellation_token: wasm_store.context.cancellation_token.clone(),
+            rate_limit: wasm_store.shared_rate_limit(),
+            policy: wasm_store.policy.clone(),
+            manga_id,
+            chapter_id,
+        }
+    }
+
+    pub fn send(&self, request_builder: &RequestBuildingState) -> Result<ResponseData> {
+        let started_at = Instant::now();
+
+        let response = self.send_within_policy(request_builder);
+        request_observer::notify(ObservedRequest {
+            source_id: self.source_id.clone(),
+            method: request_builder.method.clone(),
+            url: request_builder.url.clone(),
+            status: response.as_ref().ok().map(|response| response.status_code),
+            size: response
+                .as_ref()
+                .ok()
+                .and_then(|response| response.body.as_ref().map(Vec::len)),
+            elapsed: started_at.elapsed(),
+            error: response.as_ref().err().map(|error| format!("{error:#}")),
+            operation: self.operation.into(),
+            manga_id: self.manga_id.clone(),
+            chapter_id: self.chapter_id.clone(),
+        });
+
+        response
     }
 
+    fn send_within_policy(&self, request_builder: &RequestBuildingState) -> Result<ResponseData> {
+        if let Some(url) = &request_builder.url {
+            self.policy.check_url(url)?;
+        }
+
+        // When replaying fixtures, neither the cache nor the network are involved at all
+        let response = http_fixtures().send(&self.source_id, request_builder, |request| {
+            self.send_through_cache(request)
+        })?;
+        self.policy
+            .check_response_size(&response.url, response.body.as_ref().map_or(0, Vec::len))?;
+
+        Ok(response)
+    }
+
+    fn send_through_cache(&self, request_builder: &RequestBuildingState) -> Result<ResponseData> {
+        let cancellation_token = &self.cancellation_token;
+
+        http_cache().send(
+            &self.source_id,
+            self.operation,
+            request_builder,
+            // HACK Before everything, we want to fail fast if no internet connection is
+            // available. In theory, it would be easier to just let things fail naturally and move
+            // on with our lives; but DNS resolution takes forever (~5s or so) when we have no
+            // connection available - due to musl's `getaddrinfo()` call not realizing we have no
+            // connection and timing out (EAI_AGAIN). The overhead of checking for a connection
+            // here seems worth it. The check also lets us notice when we go offline (or back
+            // online).
+            || {
+                executor::block_on(cancellation_token.run_until_cancelled(connectivity().check()))
+                    .context("failed to check internet connection")
+            },
+            |request_builder| {
+                self.rate_limit.acquire();
+
+                fetch(cancellation_token, &self.policy, request_builder)
+            },
+        )
+    }
+}
+
+fn fetch(
+    cancellation_token: &tokio_util::sync::CancellationToken,
+    #[cfg_attr(not(feature = "all"), allow(unused_variables))] policy: &Arc<SourcePolicy>,
+    request_builder: &crate::source::wasm_store::RequestBuildingState,
+) -> Result<ResponseData> {
+    // reqwest follows redirects by itself, so they have to be checked against the policy here
     #[cfg(feature = "all")]
-    let client = reqwest::Client::new();
+    let client = {
+        let policy = policy.clone();
+
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() > MAX_REDIRECTS {
+                    return attempt.error("too many redirects");
+                }
+
+                match policy.check_url(attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(error) => attempt.error(error),
+                }
+            }))
+            .build()
+            .context("failed to build HTTP client")?
+    };
     #[cfg(feature = "all")]
-    let request =
-        reqwest::Request::try_from(&*request_builder).context("failed to build request")?;
+    let request = reqwest::Request::try_from(request_builder).context("failed to build request")?;
 
     #[cfg(feature = "all")]
     let warn_cancellation = || {
@@ -236,11 +368,10 @@ pub fn send(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> R
         url: response.url().clone(),
         headers: response.headers().clone(),
         status_code: response.status(),
-        body: match executor::block_on(cancellation_token.run_until_cancelled(response.bytes())) {
-            Some(bytes) => bytes
-                .context("failed to read response bytes")
-                .map(|bytes| bytes.to_vec())
-                .ok(),
+        body: match executor::block_on(
+            cancellation_token.run_until_cancelled(read_body(response, policy.max_response_size)),
+        ) {
+            Some(bytes) => bytes.context("failed to read response bytes").ok(),
             _ => {
                 warn_cancellation();
                 anyhow::bail!("request was cancelled mid-flight while reading body");
@@ -251,17 +382,30 @@ pub fn send(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> R
 
     #[cfg(not(feature = "all"))]
     let response_data =
-        (NET_SEND.get().context("Please set NET_SEND")?)(&cancellation_token, &request_builder)
+        (NET_SEND.get().context("Please set NET_SEND")?)(cancellation_token, request_builder)
             .map_err(|err| {
                 println!("request failed: {err}");
                 err
             })
             .context("failed to execute request")?;
 
-    *wasm_store
-        .get_mut_request(request_descriptor_i32)
-        .context("failed to get request state")? = RequestState::Sent(response_data);
-    Ok(())
+    Ok(response_data)
+}
+
+// Reads the body of a response, stopping once it goes over `max_size`: the body is then too large
+// for the policy anyway, and rejected as such once the response is returned.
+#[cfg(feature = "all")]
+async fn read_body(mut response: reqwest::Response, max_size: Option<usize>) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+
+        if max_size.is_some_and(|max_size| body.len() > max_size) {
+            break;
+        }
+    }
+
+    Ok(body)
 }
 
 #[aidoku_wasm_function]
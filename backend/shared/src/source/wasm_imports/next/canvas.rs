@@ -47,7 +47,6 @@ enum ResultContext {
     Success,
     InvalidContext,
     InvalidImagePointer,
-    #[allow(dead_code)]
     InvalidImage,
     // InvalidSrcRec,
     // InvalidResult,
@@ -83,6 +82,9 @@ impl From<ResultContext> for i32 {
 #[aidoku_wasm_function]
 fn new_context(mut caller: Caller<'_, WasmStore>, width: f32, height: f32) -> Result<i32> {
     let store = caller.data_mut();
+    if store.policy.check_canvas().is_err() {
+        return Ok(ResultContext::InvalidContext.into());
+    }
     if width <= 0.0 || height <= 0.0 {
         bail!("Invalid bougus")
     }
@@ -419,6 +421,13 @@ fn load_font(mut caller: Caller<'_, WasmStore>, url: Option<String>) -> Result<i
     let Some(url) = url else {
         return Ok(ResultContext::InvalidPath.into());
     };
+    let Ok(parsed_url) = url::Url::parse(&url) else {
+        return Ok(ResultContext::InvalidPath.into());
+    };
+    let policy = &caller.data().policy;
+    if policy.check_canvas().is_err() || policy.check_url(&parsed_url).is_err() {
+        return Ok(ResultContext::FontLoadFailed.into());
+    }
 
     let bytes = match reqwest::blocking::get(&url) {
         Ok(resp) => match resp.bytes() {
@@ -436,6 +445,9 @@ fn load_font(mut caller: Caller<'_, WasmStore>, url: Option<String>) -> Result<i
 #[aidoku_wasm_function]
 fn new_image(mut caller: Caller<'_, WasmStore>, bytes: Option<Vec<u8>>) -> Result<i32> {
     let store = caller.data_mut();
+    if store.policy.check_canvas().is_err() {
+        return Ok(ResultContext::InvalidImage.into());
+    }
 
     let Some(bytes) = bytes else {
         return Ok(ResultContext::InvalidData.into());
@@ -1,13 +1,13 @@
-#![cfg_attr(feature = "all", allow(unused_variables))]
-#![cfg_attr(feature = "all", allow(unused_mut))]
-
 use anyhow::Result;
 
 use boa_engine::{JsString, Source};
 use wasm_macros::{aidoku_wasm_function, register_wasm_function};
 use wasmi::{Caller, Linker};
 
-use crate::source::wasm_store::{Value, WasmStore};
+use crate::source::{
+    limits::is_runtime_limit,
+    wasm_store::{Value, WasmStore},
+};
 
 pub fn register_js_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "js", "context_create", context_create)?;
@@ -50,6 +50,9 @@ impl From<ResultContext> for i32 {
 #[aidoku_wasm_function]
 fn context_create(mut caller: Caller<'_, WasmStore>) -> FFIResult {
     let store = caller.data_mut();
+    if store.policy.check_js().is_err() {
+        return Ok(ResultContext::InvalidContext.into());
+    }
 
     Ok(store.create_js_context() as i32)
 }
@@ -64,8 +67,16 @@ fn context_eval(mut caller: Caller<'_, WasmStore>, ctx_id: i32, src: Option<Stri
         return Ok(ResultContext::InvalidString.into());
     };
 
-    let Ok(result) = context.eval(Source::from_bytes(&src)) else {
-        return Ok(ResultContext::MissingResult.into());
+    let result = match context.eval(Source::from_bytes(&src)) {
+        Ok(result) => result,
+        Err(error) => {
+            if is_runtime_limit(&error) {
+                // Drains the fuel, so that the call fails as timed out once back in the source
+                let _ = caller.set_fuel(0);
+            }
+
+            return Ok(ResultContext::MissingResult.into());
+        }
     };
     let Some(result_string) = result
         .to_string(context)
@@ -106,6 +117,10 @@ fn context_get(mut caller: Caller<'_, WasmStore>, ctx_id: i32, name: Option<Stri
 
 #[aidoku_wasm_function]
 fn webview_create(mut caller: Caller<'_, WasmStore>) -> FFIResult {
+    if caller.data().policy.check_webview().is_err() {
+        return Ok(ResultContext::InvalidContext.into());
+    }
+
     #[cfg(not(feature = "all"))]
     {
         let store = caller.data_mut();
@@ -114,7 +129,7 @@ fn webview_create(mut caller: Caller<'_, WasmStore>) -> FFIResult {
     }
 
     #[cfg(feature = "all")]
-    Ok(-1)
+    Ok(caller.data_mut().create_webview()? as i32)
 }
 #[aidoku_wasm_function]
 fn webview_load(
@@ -122,17 +137,11 @@ fn webview_load(
     webview_ptr: i32,
     request_ptr: i32,
 ) -> FFIResult {
-    #[cfg(not(feature = "all"))]
-    {
-        let store = caller.data_mut();
-
-        store.load_webview(webview_ptr as usize, request_ptr as usize)?;
+    let store = caller.data_mut();
 
-        Ok(0)
-    }
+    store.load_webview(webview_ptr as usize, request_ptr as usize)?;
 
-    #[cfg(feature = "all")]
-    Ok(-1)
+    Ok(0)
 }
 #[aidoku_wasm_function]
 fn webview_load_html(
@@ -141,44 +150,36 @@ fn webview_load_html(
     html: Option<String>,
     url: Option<String>,
 ) -> FFIResult {
-    #[cfg(not(feature = "all"))]
-    {
-        let store = caller.data_mut();
-
-        let Some(webview) = store.get_webview(webview_ptr as usize) else {
-            return Ok(ResultContext::InvalidContext as i32);
-        };
-        let Some(url) = url.and_then(|s| url::Url::parse(&s).ok()) else {
-            return Ok(ResultContext::InvalidString as i32);
-        };
-
-        webview.load(html, &url)?;
+    let store = caller.data_mut();
 
-        Ok(0)
-    }
+    let Some(webview) = store.get_webview(webview_ptr as usize) else {
+        return Ok(ResultContext::InvalidContext as i32);
+    };
+    let Some(url) = url.and_then(|s| url::Url::parse(&s).ok()) else {
+        return Ok(ResultContext::InvalidString as i32);
+    };
 
+    #[cfg(not(feature = "all"))]
+    webview.load(html, &url)?;
     #[cfg(feature = "all")]
-    Ok(-1)
+    webview.load_html(&html.unwrap_or_default(), &url)?;
+
+    Ok(0)
 }
 #[aidoku_wasm_function]
 fn webview_wait_for_load(mut caller: Caller<'_, WasmStore>, webview_ptr: i32) -> FFIResult {
-    #[cfg(not(feature = "all"))]
-    {
-        use futures::executor;
-
-        let store = caller.data_mut();
-
-        let Some(webview) = store.get_webview(webview_ptr as usize) else {
-            return Ok(ResultContext::InvalidContext as i32);
-        };
-
-        executor::block_on(webview.wait_for_load())?;
+    let store = caller.data_mut();
 
-        Ok(0)
-    }
+    let Some(webview) = store.get_webview(webview_ptr as usize) else {
+        return Ok(ResultContext::InvalidContext as i32);
+    };
 
+    #[cfg(not(feature = "all"))]
+    futures::executor::block_on(webview.wait_for_load())?;
     #[cfg(feature = "all")]
-    Ok(-1)
+    webview.wait_for_load()?;
+
+    Ok(0)
 }
 #[aidoku_wasm_function]
 fn webview_eval(
@@ -186,24 +187,28 @@ fn webview_eval(
     webview_ptr: i32,
     code: Option<String>,
 ) -> FFIResult {
-    #[cfg(not(feature = "all"))]
-    {
-        use futures::executor;
-
-        let store = caller.data_mut();
-
-        let Some(webview) = store.get_webview(webview_ptr as usize) else {
-            return Ok(ResultContext::InvalidContext as i32);
-        };
-        let Some(code) = code else {
-            return Ok(ResultContext::InvalidString as i32);
-        };
-
-        let value = executor::block_on(webview.eval(&code))?;
+    let store = caller.data_mut();
 
-        Ok(store.store_std_value(Value::String(value).into(), None) as i32)
-    }
+    let Some(webview) = store.get_webview(webview_ptr as usize) else {
+        return Ok(ResultContext::InvalidContext as i32);
+    };
+    let Some(code) = code else {
+        return Ok(ResultContext::InvalidString as i32);
+    };
 
+    #[cfg(not(feature = "all"))]
+    let value = futures::executor::block_on(webview.eval(&code))?;
     #[cfg(feature = "all")]
-    Ok(-1)
+    let value = match webview.eval(&code) {
+        Ok(value) => value,
+        Err(error) => {
+            if is_runtime_limit(&error) {
+                let _ = caller.set_fuel(0);
+            }
+
+            return Ok(ResultContext::MissingResult.into());
+        }
+    };
+
+    Ok(store.store_std_value(Value::String(value).into(), None) as i32)
 }
@@ -1,13 +1,5 @@
-#[cfg(feature = "all")]
-use crate::source::wasm_store::ResponseData;
-use crate::{
-    source::wasm_imports::net::{get_building_request, DEFAULT_USER_AGENT},
-    util::has_internet_connection,
-};
+use crate::source::wasm_imports::net::{get_building_request, send_request, DEFAULT_USER_AGENT};
 use anyhow::{Context, Result};
-use futures::executor;
-#[cfg(feature = "all")]
-use log::warn;
 use reqwest::Method;
 
 use wasm_macros::{aidoku_wasm_function, register_wasm_function};
@@ -170,85 +162,13 @@ fn send_all(mut caller: Caller<'_, WasmStore>, rd: i32, len: i32) -> FFIResult {
     };
 
     let store = caller.data_mut();
-    let cancellation_token = store.context.cancellation_token.clone();
-
-    let has_internet_connection =
-        executor::block_on(cancellation_token.run_until_cancelled(has_internet_connection()))
-            .context("failed to check internet connection")?;
-    if !has_internet_connection {
-        anyhow::bail!("no internet connection available");
-    }
 
     for request_descriptor_i32 in ids {
         let Some(request_descriptor_i32) = usize::try_from(request_descriptor_i32).ok() else {
             return ResultContext::InvalidDescriptor.into();
         };
-        store.rate_limit_acquire();
-
-        let request_builder = get_building_request(store, request_descriptor_i32)?;
-        #[cfg(feature = "all")]
-        let client = reqwest::Client::new();
-        #[cfg(feature = "all")]
-        let request =
-            reqwest::Request::try_from(&*request_builder).context("failed to build request")?;
-
-        #[cfg(feature = "all")]
-        let warn_cancellation = || {
-            warn!(
-                "request to {:?} was cancelled mid-flight!",
-                &request_builder.url
-            );
-        };
-
-        #[cfg(feature = "all")]
-        let response = match executor::block_on(
-            cancellation_token.run_until_cancelled(client.execute(request)),
-        ) {
-            Some(response) => response
-                .map_err(|err| {
-                    println!("request failed: {err}");
-                    err
-                })
-                .context("failed to execute request")?,
-            _ => {
-                warn_cancellation();
-                anyhow::bail!("request was cancelled mid-flight");
-            }
-        };
-
-        #[cfg(feature = "all")]
-        let response_data = ResponseData {
-            url: response.url().clone(),
-            headers: response.headers().clone(),
-            status_code: response.status(),
-            body: match executor::block_on(cancellation_token.run_until_cancelled(response.bytes()))
-            {
-                Some(bytes) => bytes
-                    .context("failed to read response bytes")
-                    .map(|bytes| bytes.to_vec())
-                    .ok(),
-                _ => {
-                    warn_cancellation();
-                    anyhow::bail!("request was cancelled mid-flight while reading body");
-                }
-            },
-            bytes_read: 0,
-        };
 
-        #[cfg(not(feature = "all"))]
-        let response_data =
-            (crate::source::wasm_imports::net::NET_SEND
-                .get()
-                .context("Please set NET_SEND")?)(&cancellation_token, &request_builder)
-            .map_err(|err| {
-                println!("request failed: {err}");
-                err
-            })
-            .context("failed to execute request")?;
-
-        *store
-            .get_mut_request(request_descriptor_i32)
-            .context("failed to get request state")? = RequestState::Sent(response_data);
+        send_request(store, request_descriptor_i32)?;
     }
 
     ResultContext::Success.into()
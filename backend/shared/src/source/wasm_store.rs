@@ -6,6 +6,7 @@ use raqote::DrawTarget;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     io::Cursor,
+    sync::{Arc, Mutex},
     thread::sleep,
     time::{Duration, Instant},
 };
@@ -21,12 +22,17 @@ use reqwest::{
 };
 
 use crate::{
+    download_scheduler,
     settings::{Settings, SourceSettingValue},
     source::html_element::HTMLElement,
 };
 
+#[cfg(feature = "all")]
+use super::{headless_webview::HeadlessWebView, wasm_imports::net::RequestSender};
 use super::{
+    limits::source_limits,
     model::{Chapter, DeepLink, Filter, Manga, MangaPageResult, Page},
+    sandbox::SourcePolicy,
     source_settings::SourceSettings,
 };
 
@@ -93,7 +99,7 @@ pub enum Value {
 
 pub type ValueRef = Parc<Value>;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct RequestBuildingState {
     pub url: Option<Url>,
     pub method: Option<Method>,
@@ -127,6 +133,48 @@ pub struct RateLimit {
     pub available: usize,
 }
 
+/// The rate limit set by a source, shared by all of its instances so that they draw from the
+/// same permits.
+#[derive(Clone, Debug, Default)]
+pub struct SharedRateLimit(Arc<Mutex<Option<RateLimit>>>);
+
+impl SharedRateLimit {
+    pub fn acquire(&self) {
+        // Held while waiting, so that the other instances of the source wait in line
+        if let Some(inner) = &mut *self.0.lock().unwrap() {
+            loop {
+                // リセット必要チェック
+                let now = Instant::now();
+                if now.duration_since(inner.last_reset) >= inner.period {
+                    inner.available = inner.permits;
+                    inner.last_reset = now;
+                }
+
+                // トークンがあれば消費して終了
+                if inner.available > 0 {
+                    inner.available -= 1;
+                    return;
+                }
+
+                // トークン無し → 次回リセット時間まで正確に待機
+                let next_reset = inner.last_reset + inner.period;
+                sleep(next_reset.saturating_duration_since(now));
+            }
+        }
+    }
+
+    pub fn declared(&self) -> Option<download_scheduler::RateLimit> {
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|rate_limit| download_scheduler::RateLimit {
+                permits: rate_limit.permits,
+                period: rate_limit.period,
+            })
+    }
+}
+
 // Determines the current object in which operations are being done.
 // TODO think about stuff??
 #[derive(Debug, Default)]
@@ -138,13 +186,26 @@ pub enum OperationContextObject {
     },
     Chapter {
         id: String,
+        manga_id: String,
     },
 }
 
+// The kind of data being fetched by the current operation, used to pick how long the
+// responses to its requests may be cached.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SourceOperation {
+    #[default]
+    Other,
+    MangaDetails,
+    ChapterList,
+    PageList,
+}
+
 #[derive(Default, Debug)]
 pub struct OperationContext {
     pub cancellation_token: CancellationToken,
     pub current_object: OperationContextObject,
+    pub operation: SourceOperation,
 }
 
 pub struct ImageData {
@@ -243,10 +304,13 @@ impl WebView {
 pub struct WasmStore {
     pub id: String,
     pub context: OperationContext,
-    pub source_settings: SourceSettings,
+    pub source_settings: Arc<SourceSettings>,
     // FIXME this probably should be source-specific, and not a copy of all settigns
     // we do rely on the `languages` global setting right now, so maybe this is really needed? idk
     pub settings: Settings,
+    pub policy: Arc<SourcePolicy>,
+    // memory limit, from the policy
+    pub(crate) limits: wasmi::StoreLimits,
     std_descriptor_pointer: usize,
     std_descriptors: HashMap<usize, ValueRef>,
     std_references: HashMap<usize, Vec<usize>>,
@@ -254,7 +318,7 @@ pub struct WasmStore {
 
     requests: HashMap<usize, RequestState>,
     // net rate limit
-    rate_limit: Option<RateLimit>,
+    rate_limit: SharedRateLimit,
     // canvas
     canvass: HashMap<usize, Canvas>,
     // image
@@ -270,6 +334,8 @@ pub struct WasmStore {
     // webview
     #[cfg(not(feature = "all"))]
     webviews: HashMap<usize, WebView>,
+    #[cfg(feature = "all")]
+    webviews: HashMap<usize, HeadlessWebView>,
 }
 impl std::fmt::Debug for WasmStore {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -278,6 +344,7 @@ impl std::fmt::Debug for WasmStore {
             .field("context", &self.context)
             .field("source_settings", &self.source_settings)
             .field("settings", &self.settings)
+            .field("policy", &self.policy)
             .field("std_descriptor_pointer", &self.std_descriptor_pointer)
             .field("std_descriptors", &self.std_descriptors)
             .field("std_references", &self.std_references)
@@ -290,7 +357,7 @@ impl std::fmt::Debug for WasmStore {
     }
 }
 impl WasmStore {
-    pub fn default(source_settings: SourceSettings) -> Self {
+    pub fn default(source_settings: Arc<SourceSettings>, policy: Arc<SourcePolicy>) -> Self {
         Self {
             id: String::new(),
             context: OperationContext::default(),
@@ -299,12 +366,15 @@ impl WasmStore {
 
             settings: Settings::default(),
 
+            limits: policy.store_limits(),
+            policy,
+
             std_descriptor_pointer: 0,
             std_descriptors: HashMap::new(),
             std_references: HashMap::new(),
             std_strs_encode: HashSet::new(),
             requests: HashMap::new(),
-            rate_limit: None,
+            rate_limit: SharedRateLimit::default(),
 
             canvass: HashMap::new(),
 
@@ -318,18 +388,24 @@ impl WasmStore {
             htmls: HashMap::new(),
             html_references: HashMap::new(),
 
-            #[cfg(not(feature = "all"))]
             webviews: HashMap::new(),
         }
     }
 }
 
 impl WasmStore {
-    pub fn new(id: String, source_settings: SourceSettings, settings: Settings) -> Self {
+    pub fn new(
+        id: String,
+        source_settings: Arc<SourceSettings>,
+        settings: Settings,
+        rate_limit: SharedRateLimit,
+        policy: Arc<SourcePolicy>,
+    ) -> Self {
         Self {
             id,
             settings,
-            ..WasmStore::default(source_settings)
+            rate_limit,
+            ..WasmStore::default(source_settings, policy)
         }
     }
 
@@ -371,6 +447,8 @@ impl WasmStore {
         if let Some(webview) = self.webviews.remove(&descriptor) {
             webview.destroy();
         }
+        #[cfg(feature = "all")]
+        try_remove!(self.webviews);
     }
 
     pub fn mark_str_encode(&mut self, pointer: usize) {
@@ -438,45 +516,40 @@ impl WasmStore {
     }
 
     pub fn set_rate_limit(&mut self, permits: Option<usize>, period_secs: Option<usize>) {
-        let permits = permits.unwrap_or_else(|| {
-            self.rate_limit
+        let mut rate_limit = self.rate_limit.0.lock().unwrap();
+
+        let permits =
+            permits.unwrap_or_else(|| rate_limit.as_ref().map(|v| v.permits).unwrap_or(usize::MAX));
+        let period = Duration::from_secs(period_secs.unwrap_or_else(|| {
+            rate_limit
                 .as_ref()
-                .map(|v| v.permits)
-                .unwrap_or(usize::MAX)
-        });
-        self.rate_limit = Some(RateLimit {
+                .map(|v| v.period.as_secs() as usize)
+                .unwrap_or(1)
+        }) as u64);
+
+        // Every instance of the source declares it again when started, which shouldn't give
+        // back the permits already used
+        if rate_limit
+            .as_ref()
+            .is_some_and(|v| v.permits == permits && v.period == period)
+        {
+            return;
+        }
+
+        *rate_limit = Some(RateLimit {
             permits,
-            period: Duration::from_secs(period_secs.unwrap_or_else(|| {
-                self.rate_limit
-                    .as_ref()
-                    .map(|v| v.period.as_secs() as usize)
-                    .unwrap_or(1)
-            }) as u64),
+            period,
             available: permits,
             last_reset: Instant::now(),
         });
     }
-    pub fn rate_limit_acquire(&mut self) {
-        if let Some(inner) = &mut self.rate_limit {
-            loop {
-                // リセット必要チェック
-                let now = Instant::now();
-                if now.duration_since(inner.last_reset) >= inner.period {
-                    inner.available = inner.permits;
-                    inner.last_reset = now;
-                }
-
-                // トークンがあれば消費して終了
-                if inner.available > 0 {
-                    inner.available -= 1;
-                    return;
-                }
+    /// The rate limit set by the source, if any.
+    pub fn declared_rate_limit(&self) -> Option<download_scheduler::RateLimit> {
+        self.rate_limit.declared()
+    }
 
-                // トークン無し → 次回リセット時間まで正確に待機
-                let next_reset = inner.last_reset + inner.period;
-                sleep(next_reset.saturating_duration_since(now));
-            }
-        }
+    pub fn shared_rate_limit(&self) -> SharedRateLimit {
+        self.rate_limit.clone()
     }
 
     fn increase_and_get_std_desciptor_pointer(&mut self) -> usize {
@@ -582,8 +655,10 @@ impl WasmStore {
     pub fn create_js_context(&mut self) -> usize {
         let idx = self.increase_and_get_std_desciptor_pointer();
 
-        self.jscontexts
-            .insert(idx, JsContext(boa_engine::Context::default()));
+        let mut context = boa_engine::Context::default();
+        source_limits().limit_js_context(&mut context);
+
+        self.jscontexts.insert(idx, JsContext(context));
 
         idx
     }
@@ -669,6 +744,44 @@ impl WasmStore {
 
         webview.load(None, &url)
     }
+
+    #[cfg(feature = "all")]
+    pub fn create_webview(&mut self) -> anyhow::Result<usize> {
+        let idx = self.increase_and_get_std_desciptor_pointer();
+
+        self.webviews.insert(idx, HeadlessWebView::new()?);
+
+        Ok(idx)
+    }
+    #[cfg(feature = "all")]
+    pub fn get_webview(&mut self, idx: usize) -> Option<&mut HeadlessWebView> {
+        let sender = RequestSender::new(self);
+        let webview = self.webviews.get_mut(&idx)?;
+        webview.set_sender(sender);
+
+        Some(webview)
+    }
+    #[cfg(feature = "all")]
+    pub fn load_webview(&mut self, idx: usize, req_idx: usize) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let sender = RequestSender::new(self);
+        let webview = self
+            .webviews
+            .get_mut(&idx)
+            .context(format!("WebView {idx} not found"))?;
+        webview.set_sender(sender);
+
+        match self
+            .requests
+            .get(&req_idx)
+            .context(format!("request {req_idx} not found"))?
+        {
+            RequestState::Building(request) => webview.load(request),
+            RequestState::Sent(response) => webview.load_response(response),
+            RequestState::Closed => anyhow::bail!("request {req_idx} closed"),
+        }
+    }
 }
 
 impl TryFrom<&RequestBuildingState> for BlockingRequest {
@@ -2,8 +2,10 @@ use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
 use tokio::sync::Mutex;
 
 use anyhow::{Context, Result};
+use url::Url;
 
 use crate::{
+    connectivity::connectivity,
     model::SourceId,
     settings::{Settings, SourceSettingValue},
     source::Source,
@@ -60,6 +62,7 @@ impl SourceManager {
 
         let source = Source::from_aix_file(&target_path, self, arc_manager)?;
         self.sources_by_id.insert(id.clone(), source);
+        register_probe_urls(self.sources_by_id.values());
         #[cfg(not(feature = "all"))]
         self.file_sources.insert(
             id.value().to_owned(),
@@ -74,6 +77,7 @@ impl SourceManager {
         fs::remove_file(&source_path)?;
 
         self.sources_by_id.remove(&id.clone());
+        register_probe_urls(self.sources_by_id.values());
         #[cfg(not(feature = "all"))]
         self.file_sources.remove(id.value());
 
@@ -137,6 +141,8 @@ impl SourceManager {
             })
             .collect::<Result<Vec<_>, _>>()?;
 
+        register_probe_urls(&sources);
+
         let sources_by_id = sources
             .into_iter()
             .map(|source| (SourceId::new(source.manifest().info.id.clone()), source))
@@ -150,6 +156,22 @@ impl SourceManager {
     }
 }
 
+// Lets the connectivity checks probe the sources themselves (see `probe_source_urls`).
+fn register_probe_urls<'a>(sources: impl IntoIterator<Item = &'a Source>) {
+    let urls = sources
+        .into_iter()
+        .filter_map(|source| {
+            let info = source.manifest().info;
+
+            info.url
+                .or_else(|| info.urls.and_then(|urls| urls.into_iter().next()))
+        })
+        .filter_map(|url| Url::parse(&url).ok())
+        .collect();
+
+    connectivity().set_source_urls(urls);
+}
+
 impl SourceCollection for SourceManager {
     fn get_by_id(&self, id: &SourceId) -> Option<&Source> {
         self.sources_by_id.get(id)
@@ -0,0 +1,187 @@
+// Scheduling rules applied on top of the chapter release forecast made by `arima_light`.
+// The forecast only tells us when a manga is *likely* to get a new chapter; the user's
+// `UpdatePolicy` decides when we are actually allowed to go and check it:
+//   - the next check is clamped between the minimum and maximum intervals
+//   - checks never land inside the quiet hours (local time)
+//   - checks are deferred while the device is on a metered connection or not charging,
+//     if the user asked for it. The device state is reported by the frontend.
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local, TimeZone, Timelike};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{QuietHours, UpdatePolicy, UpdateScheduleMode};
+
+/// Network and power state of the device, as last reported by the frontend.
+/// `None` means the frontend never told us, in which case the condition is not enforced.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct DeviceConditions {
+    pub unmetered: Option<bool>,
+    pub charging: Option<bool>,
+}
+
+static DEVICE_CONDITIONS: Lazy<Mutex<DeviceConditions>> =
+    Lazy::new(|| Mutex::new(DeviceConditions::default()));
+
+pub fn set_device_conditions(conditions: DeviceConditions) {
+    *DEVICE_CONDITIONS.lock().unwrap() = conditions;
+}
+
+pub fn device_conditions() -> DeviceConditions {
+    *DEVICE_CONDITIONS.lock().unwrap()
+}
+
+/// Why update checks can't be made right now.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Deferral {
+    QuietHours,
+    MeteredConnection,
+    NotCharging,
+}
+
+pub fn deferral_reason(
+    policy: &UpdatePolicy,
+    conditions: DeviceConditions,
+    now: DateTime<Local>,
+) -> Option<Deferral> {
+    if policy
+        .quiet_hours
+        .is_some_and(|quiet_hours| is_in_quiet_hours(&quiet_hours, now.hour()))
+    {
+        return Some(Deferral::QuietHours);
+    }
+
+    if policy.only_on_unmetered && conditions.unmetered == Some(false) {
+        return Some(Deferral::MeteredConnection);
+    }
+
+    if policy.only_when_charging && conditions.charging == Some(false) {
+        return Some(Deferral::NotCharging);
+    }
+
+    None
+}
+
+/// Computes when a manga should be checked next, given the time of the current check and
+/// the forecasted release of its next chapter (if any).
+pub fn next_check_time(policy: &UpdatePolicy, forecast: Option<i64>, now: i64) -> i64 {
+    let fallback = now + minutes_to_secs(policy.interval_minutes);
+    let next = match policy.mode {
+        UpdateScheduleMode::Arima => forecast.unwrap_or(fallback),
+        UpdateScheduleMode::FixedInterval => fallback,
+    };
+
+    let next = match policy.min_interval_minutes {
+        Some(min) => next.max(now + minutes_to_secs(min)),
+        None => next,
+    };
+    let next = match policy.max_interval_minutes {
+        Some(max) => next.min(now + minutes_to_secs(max)),
+        None => next,
+    };
+
+    match &policy.quiet_hours {
+        Some(quiet_hours) => postpone_past_quiet_hours(quiet_hours, next),
+        None => next,
+    }
+}
+
+fn is_in_quiet_hours(quiet_hours: &QuietHours, hour: u32) -> bool {
+    let start = quiet_hours.start_hour as u32;
+    let end = quiet_hours.end_hour as u32;
+
+    match start.cmp(&end) {
+        std::cmp::Ordering::Equal => false,
+        std::cmp::Ordering::Less => hour >= start && hour < end,
+        std::cmp::Ordering::Greater => hour >= start || hour < end,
+    }
+}
+
+/// Moves `timestamp` to the end of the quiet hours if it falls inside them.
+fn postpone_past_quiet_hours(quiet_hours: &QuietHours, timestamp: i64) -> i64 {
+    let Some(mut local) = Local.timestamp_opt(timestamp, 0).single() else {
+        return timestamp;
+    };
+
+    // Quiet hours span at most 23 hours, so we'll be out of them after as many steps
+    for _ in 0..24 {
+        if !is_in_quiet_hours(quiet_hours, local.hour()) {
+            break;
+        }
+
+        let Some(top_of_hour) = local
+            .with_minute(0)
+            .and_then(|dt| dt.with_second(0))
+            .map(|dt| dt + chrono::Duration::hours(1))
+        else {
+            break;
+        };
+        local = top_of_hour;
+    }
+
+    local.timestamp()
+}
+
+fn minutes_to_secs(minutes: u64) -> i64 {
+    (minutes as i64).saturating_mul(60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_hours_wrap_around_midnight() {
+        let quiet_hours = QuietHours {
+            start_hour: 22,
+            end_hour: 7,
+        };
+
+        assert!(is_in_quiet_hours(&quiet_hours, 23));
+        assert!(is_in_quiet_hours(&quiet_hours, 3));
+        assert!(!is_in_quiet_hours(&quiet_hours, 7));
+        assert!(!is_in_quiet_hours(&quiet_hours, 12));
+    }
+
+    #[test]
+    fn next_check_is_clamped_to_the_interval_bounds() {
+        let policy = UpdatePolicy {
+            min_interval_minutes: Some(60),
+            max_interval_minutes: Some(12 * 60),
+            ..Default::default()
+        };
+        let now = 1_700_000_000;
+
+        assert_eq!(next_check_time(&policy, Some(now + 10), now), now + 60 * 60);
+        assert_eq!(
+            next_check_time(&policy, Some(now + 7 * 24 * 60 * 60), now),
+            now + 12 * 60 * 60
+        );
+        assert_eq!(next_check_time(&policy, None, now), now + 12 * 60 * 60);
+    }
+
+    #[test]
+    fn next_check_skips_quiet_hours() {
+        let policy = UpdatePolicy {
+            mode: UpdateScheduleMode::FixedInterval,
+            interval_minutes: 0,
+            quiet_hours: Some(QuietHours {
+                start_hour: 0,
+                end_hour: 23,
+            }),
+            ..Default::default()
+        };
+        let midday = Local
+            .with_ymd_and_hms(2025, 6, 1, 12, 30, 0)
+            .single()
+            .unwrap()
+            .timestamp();
+
+        let next = Local.timestamp_opt(next_check_time(&policy, None, midday), 0);
+
+        assert_eq!(next.single().unwrap().hour(), 23);
+    }
+}
@@ -1,20 +1,37 @@
 use std::sync::atomic::Ordering;
-use std::{collections::HashSet, sync::atomic::AtomicBool};
+use std::sync::Arc;
+use std::time::Instant;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::AtomicBool,
+};
 
 use anyhow::{bail, Result};
+use futures::{stream, StreamExt};
 use once_cell::sync::Lazy;
+use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 
-use crate::settings::Settings;
+use crate::settings::{Settings, UpdateScheduleMode};
+use crate::update_policy::{deferral_reason, device_conditions, next_check_time};
 use crate::{
     arima_light::{fit_arima_from_chapters, ArimaSpec},
     chapter_storage::ChapterStorage,
+    connectivity::connectivity,
     database::Database,
-    model::MangaId,
+    download_queue::notify_queue_changed,
+    events::{self, Event},
+    model::{
+        AutoDownloadMode, ChapterInformation, MangaId, MangaState, PendingAction, SourceId,
+        UpdateRunTrigger,
+    },
     source::model::PublishingStatus,
     source_collection::SourceCollection,
     source_manager::SourceManager,
-    usecases::{refresh_manga_chapters, refresh_manga_details},
+    usecases::{
+        list_available_sources, list_installed_sources, notify_source_updates,
+        refresh_manga_chapters, refresh_manga_details,
+    },
 };
 
 pub async fn check_mangas_update(
@@ -22,7 +39,23 @@ pub async fn check_mangas_update(
     db: &Database,
     chapter_storage: &ChapterStorage,
     source_manager: &SourceManager,
+    settings: &Settings,
+    trigger: UpdateRunTrigger,
 ) {
+    if connectivity().is_offline() {
+        // Scheduled checks just happen again later, manual ones are replayed once back online
+        if trigger == UpdateRunTrigger::Manual {
+            if let Err(e) = db
+                .queue_pending_action(PendingAction::CheckMangasUpdate)
+                .await
+            {
+                eprintln!("Failed to queue update check: {}", e);
+            }
+        }
+
+        return;
+    }
+
     let mangas_library = match db.get_manga_library_and_status().await {
         Ok(v) => v,
         Err(e) => {
@@ -31,36 +64,219 @@ pub async fn check_mangas_update(
         }
     };
 
-    for (manga, status) in mangas_library {
-        if let Err(error) =
-            check_manga_update(token, db, chapter_storage, source_manager, &manga, &status).await
-        {
-            eprintln!("Warn[{}]: {}", manga.value(), error);
-            continue;
+    let run_id = start_update_run(db, trigger).await;
+
+    check_mangas_concurrently(
+        token,
+        db,
+        chapter_storage,
+        source_manager,
+        settings,
+        run_id,
+        mangas_library,
+    )
+    .await;
+
+    check_source_updates(db, source_manager, settings, trigger).await;
+
+    finish_update_run(db, run_id, trigger).await;
+}
+
+// Listing the sources hits the network for every source list, so the cron's full re-checks
+// only do it once in a while. Manual checks always do.
+const SOURCE_UPDATE_CHECK_INTERVAL: std::time::Duration =
+    std::time::Duration::from_secs(12 * 60 * 60);
+
+static LAST_SOURCE_UPDATE_CHECK: Lazy<std::sync::Mutex<Option<Instant>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
+
+/// Notifies about the installed sources which have a newer version in the source lists.
+async fn check_source_updates(
+    db: &Database,
+    source_manager: &SourceManager,
+    settings: &Settings,
+    trigger: UpdateRunTrigger,
+) {
+    {
+        let mut last_check = LAST_SOURCE_UPDATE_CHECK.lock().unwrap();
+        let checked_recently = last_check
+            .is_some_and(|last_check| last_check.elapsed() < SOURCE_UPDATE_CHECK_INTERVAL);
+        if trigger == UpdateRunTrigger::Cron && checked_recently {
+            return;
+        }
+
+        *last_check = Some(Instant::now());
+    }
+
+    let available_sources = match list_available_sources(settings.source_lists.clone()).await {
+        Ok(sources) => sources,
+        Err(e) => {
+            eprintln!("Failed to list available sources: {}", e);
+            return;
         }
+    };
+
+    let installed_sources = list_installed_sources(source_manager);
+    if let Err(e) = notify_source_updates(db, &installed_sources, &available_sources).await {
+        eprintln!("Failed to create source update notifications: {}", e);
     }
 }
 
-async fn check_manga_update(
+/// Checks the given manga, running up to `per_source_concurrency` checks at once for each
+/// source. Different sources are always checked in parallel.
+async fn check_mangas_concurrently(
     token: &CancellationToken,
     db: &Database,
     chapter_storage: &ChapterStorage,
     source_manager: &SourceManager,
+    settings: &Settings,
+    run_id: Option<i64>,
+    mangas: Vec<(MangaId, PublishingStatus)>,
+) {
+    let per_source_concurrency = settings.update_policy.per_source_concurrency.max(1);
+    let mut source_permits: HashMap<SourceId, Arc<Semaphore>> = HashMap::new();
+    let mangas: Vec<_> = mangas
+        .into_iter()
+        .map(|(manga, status)| {
+            let permits = source_permits
+                .entry(manga.source_id().clone())
+                .or_insert_with(|| Arc::new(Semaphore::new(per_source_concurrency)))
+                .clone();
+
+            (manga, status, permits)
+        })
+        .collect();
+
+    stream::iter(mangas)
+        .for_each_concurrent(None, |(manga, status, permits)| async move {
+            let Ok(_permit) = permits.acquire().await else {
+                return;
+            };
+
+            if let Err(error) = check_and_record_manga_update(
+                token,
+                db,
+                chapter_storage,
+                source_manager,
+                settings,
+                run_id,
+                &manga,
+                &status,
+            )
+            .await
+            {
+                eprintln!("Warn[{}]: {}", manga.value(), error);
+            }
+        })
+        .await;
+}
+
+async fn start_update_run(db: &Database, trigger: UpdateRunTrigger) -> Option<i64> {
+    db.start_update_run(trigger)
+        .await
+        .inspect_err(|e| eprintln!("Failed to start update run report: {}", e))
+        .ok()
+}
+
+async fn finish_update_run(db: &Database, run_id: Option<i64>, trigger: UpdateRunTrigger) {
+    if let Some(run_id) = run_id {
+        if let Err(e) = db.finish_update_run(run_id).await {
+            eprintln!("Failed to finish update run report {}: {}", run_id, e);
+        }
+    }
+
+    if let Err(e) = db.purge_read_notifications().await {
+        eprintln!("Failed to purge read notifications: {}", e);
+    }
+
+    events::publish(Event::UpdateRunFinished { run_id, trigger });
+}
+
+/// Checks a manga for updates and, if a check actually happened, stores its outcome
+/// in the update run report.
+#[allow(clippy::too_many_arguments)]
+async fn check_and_record_manga_update(
+    token: &CancellationToken,
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+    source_manager: &SourceManager,
+    settings: &Settings,
+    run_id: Option<i64>,
 
     manga: &MangaId,
     status: &PublishingStatus,
 ) -> Result<()> {
-    let spec = ArimaSpec {
-        p: 1,
-        d: 1,
-        q: 1,
-        rolling_window: Some(30),
-        min_points: 6,
+    let started_at = Instant::now();
+    let result = check_manga_update(
+        token,
+        db,
+        chapter_storage,
+        source_manager,
+        settings,
+        manga,
+        status,
+    )
+    .await;
+    let duration_ms = started_at.elapsed().as_millis() as i64;
+
+    let (new_chapters, error) = match &result {
+        Ok(Some(new_chapters)) => (*new_chapters, None),
+        // Skipped by the update policy, nothing was checked
+        Ok(None) => return Ok(()),
+        Err(e) => (0, Some(e.to_string())),
     };
 
-    if *status == PublishingStatus::Completed {
+    events::publish(Event::MangaUpdateChecked {
+        manga_id: manga.clone(),
+        new_chapters,
+        error: error.clone(),
+    });
+
+    if let Some(run_id) = run_id {
+        if let Err(e) = db
+            .insert_update_run_result(run_id, manga, new_chapters, error, duration_ms)
+            .await
+        {
+            eprintln!(
+                "Warn[{}]: failed to record update result: {}",
+                manga.value(),
+                e
+            );
+        }
+    }
+
+    result.map(|_| ())
+}
+
+async fn check_manga_update(
+    token: &CancellationToken,
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+    source_manager: &SourceManager,
+    settings: &Settings,
+
+    manga: &MangaId,
+    status: &PublishingStatus,
+) -> Result<Option<usize>> {
+    let policy = &settings.update_policy;
+
+    if policy.skip_completed && *status == PublishingStatus::Completed {
         db.delete_last_check_update_manga(manga).await?;
-        return Ok(());
+        return Ok(None);
+    }
+
+    if policy.skip_not_started && !db.has_read_chapters(manga).await? {
+        // Push the manga out of the due list, as if it had been checked
+        let now = chrono::Utc::now().timestamp();
+        let last_check = db
+            .get_last_check_update_manga(manga)
+            .await?
+            .map(|(last_check, _)| last_check)
+            .unwrap_or(now);
+
+        db.set_last_check_update_manga(manga, last_check, next_check_time(policy, None, now))
+            .await?;
+        return Ok(None);
     }
 
     let Some(source) = source_manager.get_by_id(manga.source_id()) else {
@@ -73,7 +289,7 @@ async fn check_manga_update(
 
     let status = match refresh_manga_details(token, db, chapter_storage, source, manga, 60).await {
         Ok(status) => {
-            if status == PublishingStatus::Completed {
+            if policy.skip_completed && status == PublishingStatus::Completed {
                 db.delete_last_check_update_manga(manga).await?;
             }
 
@@ -107,40 +323,112 @@ async fn check_manga_update(
         compute_new_chapters(&old_chapters, &new_chapters)
     };
 
-    if status != PublishingStatus::Completed {
-        let maybe_model = fit_arima_from_chapters(&new_chapters, spec);
-
-        let next_ts_update = match maybe_model {
-            Ok(model) => {
-                let last_check_no_update = if added_chapters.is_empty() {
-                    Some(chrono::Utc::now().timestamp())
-                } else {
-                    db.get_last_check_update_manga(manga)
-                        .await
-                        .ok()
-                        .flatten()
-                        .map(|t| t.0)
-                };
-
-                model.forecast_1_from_chapters(&new_chapters, last_check_no_update)
+    if !(policy.skip_completed && status == PublishingStatus::Completed) {
+        let forecast = match policy.mode {
+            UpdateScheduleMode::Arima => {
+                forecast_next_chapter(db, manga, &new_chapters, &added_chapters).await
             }
-            Err(err) => {
-                eprintln!("{}", err);
-
-                None
-            }
-        }
-        .unwrap_or_else(|| chrono::Utc::now().timestamp() + 24 * 60 * 60);
+            UpdateScheduleMode::FixedInterval => None,
+        };
 
-        db.set_last_check_update_manga(manga, chrono::Utc::now().timestamp(), next_ts_update)
+        let now = chrono::Utc::now().timestamp();
+        db.set_last_check_update_manga(manga, now, next_check_time(policy, forecast, now))
             .await?;
     }
 
     let _ = db.insert_notification(manga, &added_chapters).await;
 
+    if let Err(err) = enqueue_auto_downloads(db, manga, &added_chapters).await {
+        eprintln!("Warn[{}]: auto-download failed: {}", manga.value(), err);
+    }
+
+    Ok(Some(added_chapters.len()))
+}
+
+// Auto-downloads are queued behind the chapters the user asked for.
+const AUTO_DOWNLOAD_PRIORITY: i64 = -1;
+
+async fn enqueue_auto_downloads(
+    db: &Database,
+    manga: &MangaId,
+    added_chapters: &[ChapterInformation],
+) -> Result<()> {
+    let Some(state) = db.find_manga_state(manga).await? else {
+        return Ok(());
+    };
+
+    let chapter_ids: Vec<_> = select_chapters_to_auto_download(&state, added_chapters)
+        .into_iter()
+        .map(|chapter| chapter.id)
+        .collect();
+    if chapter_ids.is_empty() {
+        return Ok(());
+    }
+
+    db.enqueue_auto_downloads(&chapter_ids, AUTO_DOWNLOAD_PRIORITY)
+        .await?;
+    notify_queue_changed();
+
     Ok(())
 }
 
+/// Picks which of the newly found chapters should be downloaded according to the manga's
+/// auto-download mode, oldest first. Locked chapters are never picked.
+fn select_chapters_to_auto_download(
+    state: &MangaState,
+    added_chapters: &[ChapterInformation],
+) -> Vec<ChapterInformation> {
+    added_chapters
+        .iter()
+        .rev()
+        .filter(|chapter| !chapter.locked.unwrap_or_default())
+        .filter(|chapter| match &state.auto_download {
+            AutoDownloadMode::Off => false,
+            AutoDownloadMode::AllNewChapters => true,
+            AutoDownloadMode::PreferredScanlator => match &state.preferred_scanlator {
+                Some(preferred_scanlator) => chapter
+                    .scanlator
+                    .as_ref()
+                    .is_none_or(|scanlator| scanlator == preferred_scanlator),
+                None => true,
+            },
+            AutoDownloadMode::Languages { languages } => {
+                let lang = chapter.lang.as_deref().unwrap_or("unknown");
+                languages.iter().any(|l| l == lang)
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+async fn forecast_next_chapter(
+    db: &Database,
+    manga: &MangaId,
+    new_chapters: &[ChapterInformation],
+    added_chapters: &[ChapterInformation],
+) -> Option<i64> {
+    match fit_arima_from_chapters(new_chapters, ArimaSpec::default()) {
+        Ok(model) => {
+            let last_check_no_update = if added_chapters.is_empty() {
+                Some(chrono::Utc::now().timestamp())
+            } else {
+                db.get_last_check_update_manga(manga)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|t| t.0)
+            };
+
+            model.forecast_1_from_chapters(new_chapters, last_check_no_update)
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+
+            None
+        }
+    }
+}
+
 fn compute_new_chapters(
     old_chapters: &[crate::model::ChapterInformation],
     new_chapters: &[crate::model::ChapterInformation],
@@ -157,6 +445,8 @@ fn compute_new_chapters(
 // ===== cron =====
 static CRON_RUNNING: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
 
+const DEFERRAL_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
 pub async fn run_manga_cron(
     db: &Database,
     chapter_storage: &ChapterStorage,
@@ -187,9 +477,22 @@ pub async fn run_manga_cron(
         };
 
         if next_manga.is_none() {
+            if cron_is_deferred(settings) {
+                tokio::time::sleep(DEFERRAL_RETRY_INTERVAL).await;
+                continue;
+            }
+
             println!("Next manga not found. Re-check all mangas");
 
-            check_mangas_update(token, db, chapter_storage, source_manager).await;
+            check_mangas_update(
+                token,
+                db,
+                chapter_storage,
+                source_manager,
+                settings,
+                UpdateRunTrigger::Cron,
+            )
+            .await;
             next_manga = match db.get_next_ts_arima_min(&skip_sources).await {
                 Ok(v) => v,
                 Err(e) => {
@@ -217,6 +520,11 @@ pub async fn run_manga_cron(
             .await;
         }
 
+        if cron_is_deferred(settings) {
+            tokio::time::sleep(DEFERRAL_RETRY_INTERVAL).await;
+            continue;
+        }
+
         let due_mangas = match db.get_due_mangas().await {
             Ok(v) => v,
             Err(e) => {
@@ -225,29 +533,50 @@ pub async fn run_manga_cron(
             }
         };
 
-        for (manga_id, status) in due_mangas {
-            if skip_sources.contains(&manga_id.source_id().value().as_str()) {
-                continue;
-            }
-
-            if let Err(err) = check_manga_update(
-                token,
-                db,
-                chapter_storage,
-                source_manager,
-                &manga_id,
-                &status,
-            )
-            .await
-            {
-                eprintln!(
-                    "[ERROR] check_mangas_update failed for {}: {:?}",
-                    manga_id.value(),
-                    err
-                );
-            }
+        if due_mangas.is_empty() {
+            continue;
         }
+
+        let run_id = start_update_run(db, UpdateRunTrigger::Cron).await;
+
+        let due_mangas = due_mangas
+            .into_iter()
+            .filter(|(manga_id, _)| !skip_sources.contains(&manga_id.source_id().value().as_str()))
+            .collect();
+
+        check_mangas_concurrently(
+            token,
+            db,
+            chapter_storage,
+            source_manager,
+            settings,
+            run_id,
+            due_mangas,
+        )
+        .await;
+
+        finish_update_run(db, run_id, UpdateRunTrigger::Cron).await;
     }
 
     CRON_RUNNING.store(false, Ordering::SeqCst);
 }
+
+/// Whether the update policy (quiet hours, metered network, charging) or being offline
+/// keeps the cron from checking for updates right now.
+fn cron_is_deferred(settings: &Settings) -> bool {
+    if let Some(reason) = deferral_reason(
+        &settings.update_policy,
+        device_conditions(),
+        chrono::Local::now(),
+    ) {
+        println!("Cron deferred ({reason:?}), retrying later");
+        return true;
+    }
+
+    if connectivity().is_offline() {
+        println!("Cron deferred (offline), retrying later");
+        return true;
+    }
+
+    false
+}
@@ -0,0 +1,16 @@
+use anyhow::Result;
+
+use crate::{database::Database, download_queue::notify_queue_changed, model::ChapterId};
+
+/// Adds chapters to the persistent download queue. Chapters with a higher `priority` are
+/// downloaded first.
+pub async fn enqueue_chapter_downloads(
+    db: &Database,
+    chapter_ids: &[ChapterId],
+    priority: i64,
+) -> Result<()> {
+    db.enqueue_downloads(chapter_ids, priority).await?;
+    notify_queue_changed();
+
+    Ok(())
+}
@@ -6,9 +6,11 @@ use tokio_util::sync::CancellationToken;
 use crate::{
     chapter_downloader::{
         ensure_chapter_is_in_storage, DownloadError, Error as ChapterDownloaderError,
+        ProgressCallback,
     },
     chapter_storage::ChapterStorage,
     database::Database,
+    events::{self, Event},
     model::ChapterId,
     source::Source,
 };
@@ -21,6 +23,7 @@ pub async fn fetch_manga_chapter(
     chapter_id: &ChapterId,
     concurrent_requests_pages: usize,
     optimize_image: bool,
+    on_progress: Option<ProgressCallback>,
 ) -> Result<(PathBuf, Vec<DownloadError>), Error> {
     let manga = database
         .find_cached_manga_information(chapter_id.manga_id())
@@ -32,7 +35,7 @@ pub async fn fetch_manga_chapter(
         .await?
         .ok_or_else(|| anyhow!("Expected chapter to be in the database"))?;
 
-    ensure_chapter_is_in_storage(
+    let result = ensure_chapter_is_in_storage(
         token,
         chapter_storage,
         source,
@@ -40,9 +43,21 @@ pub async fn fetch_manga_chapter(
         &chapter,
         concurrent_requests_pages,
         optimize_image,
+        on_progress,
     )
-    .await
-    .map_err(|e| match e {
+    .await;
+
+    events::publish(match &result {
+        Ok(_) => Event::DownloadFinished {
+            chapter_id: chapter_id.clone(),
+        },
+        Err(e) => Event::DownloadFailed {
+            chapter_id: chapter_id.clone(),
+            error: e.to_string(),
+        },
+    });
+
+    result.map_err(|e| match e {
         ChapterDownloaderError::DownloadError(e) => Error::DownloadError(e),
         ChapterDownloaderError::Other(e) => Error::Other(e),
     })
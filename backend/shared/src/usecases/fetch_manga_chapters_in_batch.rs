@@ -1,14 +1,15 @@
 use async_stream::stream;
-use futures::Stream;
-use std::collections::HashSet;
-use tokio::select;
+use futures::{pin_mut, Stream};
+use std::{collections::HashSet, sync::Arc};
+use tokio::{select, sync::watch};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
     chapter_downloader::ensure_chapter_is_in_storage,
-    chapter_downloader::Error as ChapterDownloaderError,
+    chapter_downloader::{DownloadProgress, Error as ChapterDownloaderError, ProgressCallback},
     chapter_storage::ChapterStorage,
     database::Database,
+    events::{self, Event},
     model::{ChapterInformation, MangaId},
     source::Source,
 };
@@ -47,29 +48,54 @@ pub fn fetch_manga_chapters_in_batch<'a>(
         let chapters_to_download = apply_chapter_filter(db, all_chapters, filter, langs).await;
 
         let total = chapters_to_download.len();
-        yield ProgressReport::Progressing { downloaded: 0, total };
+        yield ProgressReport::Progressing { downloaded: 0, total, chapter: None };
 
         for (index, information) in chapters_to_download.into_iter().enumerate() {
-            let ensure_in_storage_result = select! {
-                _ = cancellation_token.cancelled() => {
-                    yield ProgressReport::Cancelled;
-
-                    return;
-                },
-                result = ensure_chapter_is_in_storage(
-                    &cancellation_token,
-                    chapter_storage,
-                    source,
-                    &manga,
-                    &information,
-                    concurrent_requests_pages,
-                    optimize_image,
-                ) => result
+            let (chapter_progress_tx, mut chapter_progress_rx) = watch::channel(DownloadProgress::default());
+            let on_progress: ProgressCallback = Arc::new(move |progress| {
+                chapter_progress_tx.send_replace(progress);
+            });
+
+            let download = ensure_chapter_is_in_storage(
+                &cancellation_token,
+                chapter_storage,
+                source,
+                &manga,
+                &information,
+                concurrent_requests_pages,
+                optimize_image,
+                Some(on_progress),
+            );
+            pin_mut!(download);
+
+            let ensure_in_storage_result = loop {
+                select! {
+                    _ = cancellation_token.cancelled() => {
+                        yield ProgressReport::Cancelled;
+
+                        return;
+                    },
+                    result = &mut download => break result,
+                    Ok(()) = chapter_progress_rx.changed() => {
+                        let chapter = *chapter_progress_rx.borrow_and_update();
+
+                        yield ProgressReport::Progressing { downloaded: index, total, chapter: Some(chapter) };
+                    },
+                }
             };
 
             match ensure_in_storage_result {
-                Ok(_) => yield ProgressReport::Progressing { downloaded: index + 1, total },
+                Ok(_) => {
+                    events::publish(Event::DownloadFinished { chapter_id: information.id.clone() });
+
+                    yield ProgressReport::Progressing { downloaded: index + 1, total, chapter: None };
+                },
                 Err(e) => {
+                    events::publish(Event::DownloadFailed {
+                        chapter_id: information.id.clone(),
+                        error: e.to_string(),
+                    });
+
                     let error = match e {
                         ChapterDownloaderError::DownloadError(e) => Error::DownloadError(e),
                         ChapterDownloaderError::Other(e) => Error::Other(e),
@@ -199,7 +225,12 @@ pub enum Filter {
 }
 
 pub enum ProgressReport {
-    Progressing { downloaded: usize, total: usize },
+    /// `chapter` is the progress of the chapter currently being downloaded, if any.
+    Progressing {
+        downloaded: usize,
+        total: usize,
+        chapter: Option<DownloadProgress>,
+    },
     Finished,
     Cancelled,
     Errored(Error),
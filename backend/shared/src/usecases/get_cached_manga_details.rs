@@ -1,7 +1,10 @@
 use anyhow::Result;
 use tokio_util::sync::CancellationToken;
 
-use crate::{chapter_storage::ChapterStorage, database::Database, model::MangaId, source::Source};
+use crate::{
+    chapter_storage::ChapterStorage, connectivity::connectivity, database::Database,
+    model::MangaId, source::Source,
+};
 
 pub async fn get_cached_manga_details(
     token: &CancellationToken,
@@ -12,13 +15,22 @@ pub async fn get_cached_manga_details(
 ) -> Result<Option<(crate::source::model::Manga, f64)>> {
     match db.find_cached_manga_details(&id).await? {
         Some((mut details, per_read)) => {
-            if let Some(url) = &details.cover_url {
-                let output = chapter_storage
-                    .cached_poster(token, url, || {
-                        source.get_image_request(url.to_owned(), None)
-                    })
-                    .await?;
+            let poster = match &details.cover_url {
+                // Only posters that were already downloaded can be shown while offline
+                Some(url) if connectivity().is_offline() => {
+                    chapter_storage.stored_poster(url).await?
+                }
+                Some(url) => Some(
+                    chapter_storage
+                        .cached_poster(token, url, || {
+                            source.get_image_request(url.to_owned(), None)
+                        })
+                        .await?,
+                ),
+                None => None,
+            };
 
+            if let Some(output) = poster {
                 details.url = match url::Url::from_file_path(output.clone()) {
                     Ok(url) => Some(url),
                     Err(_) => url::Url::from_file_path(output.canonicalize()?)
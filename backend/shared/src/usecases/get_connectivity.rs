@@ -0,0 +1,23 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    connectivity::{connectivity, ConnectivityState},
+    database::Database,
+    model::QueuedAction,
+};
+
+#[derive(Serialize)]
+pub struct Connectivity {
+    #[serde(flatten)]
+    pub state: ConnectivityState,
+    /// Actions waiting for the connection to come back.
+    pub pending_actions: Vec<QueuedAction>,
+}
+
+pub async fn get_connectivity(db: &Database) -> Result<Connectivity> {
+    Ok(Connectivity {
+        state: connectivity().state().await,
+        pending_actions: db.find_pending_actions().await?,
+    })
+}
@@ -0,0 +1,17 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{database::Database, model::DownloadQueueItem};
+
+#[derive(Serialize)]
+pub struct DownloadQueue {
+    pub paused: bool,
+    pub items: Vec<DownloadQueueItem>,
+}
+
+pub async fn get_download_queue(db: &Database) -> Result<DownloadQueue> {
+    Ok(DownloadQueue {
+        paused: db.is_download_queue_paused().await?,
+        items: db.get_download_queue().await?,
+    })
+}
@@ -0,0 +1,13 @@
+use crate::{
+    database::Database,
+    model::{AutoDownloadMode, MangaId},
+};
+use anyhow::Result;
+
+pub async fn get_manga_auto_download(
+    db: &Database,
+    manga_id: &MangaId,
+) -> Result<AutoDownloadMode> {
+    let state = db.find_manga_state(manga_id).await?;
+    Ok(state.map(|s| s.auto_download).unwrap_or_default())
+}
@@ -0,0 +1,6 @@
+use crate::{database::Database, model::MangaId};
+use anyhow::Result;
+
+pub async fn get_manga_pinned(db: &Database, manga_id: &MangaId) -> Result<bool> {
+    db.is_manga_pinned(manga_id).await
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+
+use crate::{
+    chapter_storage::ChapterStorage,
+    database::Database,
+    model::{NotificationGroup, NotificationKind},
+    usecases::get_notifications,
+};
+
+/// Groups the notifications of the same kind about the same manga (or source) together.
+/// Groups with unread notifications come first, then the most recent ones.
+pub async fn get_notification_groups(
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+) -> Result<Vec<NotificationGroup>> {
+    let notifications = get_notifications(db, chapter_storage, true).await?;
+
+    let mut groups: Vec<NotificationGroup> = Vec::new();
+    for notification in notifications {
+        let group = groups.iter_mut().find(|group| {
+            group.kind == notification.kind
+                && group.source_id == notification.source_id
+                && group.manga_id == notification.manga_id
+        });
+
+        match group {
+            Some(group) => {
+                group.latest_at = group.latest_at.max(notification.created_at);
+                if !notification.is_read {
+                    group.unread_count += 1;
+                }
+                group.notifications.push(notification);
+            }
+            None => groups.push(NotificationGroup {
+                kind: notification.kind,
+                source_id: notification.source_id.clone(),
+                manga_id: notification.manga_id.clone(),
+                manga_title: notification
+                    .manga_id
+                    .as_ref()
+                    .map(|_| notification.manga_title.clone()),
+                manga_cover: notification.manga_cover.clone(),
+                summary: String::new(),
+                unread_count: if notification.is_read { 0 } else { 1 },
+                latest_at: notification.created_at,
+                notifications: vec![notification],
+            }),
+        }
+    }
+
+    for group in groups.iter_mut() {
+        group.summary = summarize(group);
+    }
+
+    groups.sort_by_key(|group| (group.unread_count == 0, -group.latest_at));
+
+    Ok(groups)
+}
+
+fn summarize(group: &NotificationGroup) -> String {
+    let count = group.notifications.len();
+    let chapters = if count == 1 { "chapter" } else { "chapters" };
+    let manga_title = group.manga_title.as_deref().unwrap_or("Unknown");
+
+    match group.kind {
+        NotificationKind::NewChapter => format!("{count} new {chapters} of {manga_title}"),
+        NotificationKind::DownloadFinished => {
+            format!("{count} {chapters} of {manga_title} downloaded")
+        }
+        NotificationKind::DownloadFailed => {
+            format!("{count} {chapters} of {manga_title} failed to download")
+        }
+        NotificationKind::SourceUpdateAvailable | NotificationKind::SyncConflict => group
+            .notifications
+            .first()
+            .and_then(|notification| notification.message.clone())
+            .unwrap_or_default(),
+    }
+}
@@ -5,8 +5,9 @@ use crate::{chapter_storage::ChapterStorage, database::Database, model::Notifica
 pub async fn get_notifications(
     db: &Database,
     chapter_storage: &ChapterStorage,
+    include_read: bool,
 ) -> Result<Vec<NotificationInformation>> {
-    let mut notifications = db.get_notifications().await?;
+    let mut notifications = db.get_notifications(include_read).await?;
 
     futures::future::join_all(notifications.iter_mut().map(|notify| async {
         if let Some(url) = &notify.manga_cover {
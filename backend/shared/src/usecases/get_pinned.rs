@@ -0,0 +1,119 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    chapter_storage::ChapterStorage,
+    database::Database,
+    model::{ChapterId, MangaId},
+};
+
+#[derive(Serialize)]
+pub struct Pinned {
+    /// Size of all the stored chapters which are pinned, to compare with `limit_bytes`.
+    pub pinned_bytes: u64,
+    pub limit_bytes: u64,
+    pub mangas: Vec<PinnedMangaUsage>,
+    pub chapters: Vec<PinnedChapterUsage>,
+}
+
+#[derive(Serialize)]
+pub struct PinnedMangaUsage {
+    pub manga_id: MangaId,
+    pub manga_title: Option<String>,
+    pub pinned_at: i64,
+    pub bytes: u64,
+    pub stored_chapters: usize,
+}
+
+#[derive(Serialize)]
+pub struct PinnedChapterUsage {
+    pub chapter_id: ChapterId,
+    pub manga_title: Option<String>,
+    pub chapter_title: Option<String>,
+    pub chapter_number: Option<f32>,
+    pub pinned_at: i64,
+    pub bytes: u64,
+    pub stored: bool,
+}
+
+pub async fn get_pinned(db: &Database, chapter_storage: &ChapterStorage) -> Result<Pinned> {
+    let pinned_mangas = db.find_pinned_mangas().await?;
+    let pinned_chapters = db.find_pinned_chapters().await?;
+    let pinned_manga_ids: HashSet<_> = pinned_mangas
+        .iter()
+        .map(|manga| manga.manga_id.clone())
+        .collect();
+    let entries: HashMap<_, _> = db
+        .find_chapter_storage_entries()
+        .await?
+        .into_iter()
+        .filter(|entry| entry.pinned)
+        .map(|entry| (entry.id.clone(), entry))
+        .collect();
+
+    let mut pinned_bytes = 0;
+    let mut manga_usage: HashMap<MangaId, (u64, usize)> = HashMap::new();
+    let mut chapter_bytes: HashMap<ChapterId, u64> = HashMap::new();
+
+    for chapter in chapter_storage.stored_chapter_files(entries.keys()) {
+        let Some(chapter_id) = chapter.chapter_id else {
+            continue;
+        };
+
+        pinned_bytes += chapter.size;
+
+        if pinned_manga_ids.contains(chapter_id.manga_id()) {
+            let usage = manga_usage
+                .entry(chapter_id.manga_id().clone())
+                .or_default();
+            usage.0 += chapter.size;
+            usage.1 += 1;
+        }
+
+        *chapter_bytes.entry(chapter_id).or_default() += chapter.size;
+    }
+
+    let mangas = pinned_mangas
+        .into_iter()
+        .map(|manga| {
+            let (bytes, stored_chapters) = manga_usage
+                .get(&manga.manga_id)
+                .copied()
+                .unwrap_or_default();
+
+            PinnedMangaUsage {
+                manga_id: manga.manga_id,
+                manga_title: manga.manga_title,
+                pinned_at: manga.pinned_at,
+                bytes,
+                stored_chapters,
+            }
+        })
+        .collect();
+
+    let chapters = pinned_chapters
+        .into_iter()
+        .map(|chapter| {
+            let bytes = chapter_bytes.get(&chapter.chapter_id).copied();
+
+            PinnedChapterUsage {
+                chapter_id: chapter.chapter_id,
+                manga_title: chapter.manga_title,
+                chapter_title: chapter.chapter_title,
+                chapter_number: chapter.chapter_number,
+                pinned_at: chapter.pinned_at,
+                bytes: bytes.unwrap_or_default(),
+                stored: bytes.is_some(),
+            }
+        })
+        .collect();
+
+    Ok(Pinned {
+        pinned_bytes,
+        limit_bytes: chapter_storage.storage_size_limit().bytes() as u64,
+        mangas,
+        chapters,
+    })
+}
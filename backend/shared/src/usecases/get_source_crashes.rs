@@ -0,0 +1,18 @@
+use crate::{
+    model::{SourceCrashes, SourceId},
+    source::source_crashes,
+    source_collection::SourceCollection,
+};
+
+/// Lists how many times each installed source crashed since the server started.
+pub fn get_source_crashes(source_collection: &impl SourceCollection) -> Vec<SourceCrashes> {
+    let mut crashes: Vec<SourceCrashes> = source_collection
+        .sources()
+        .into_iter()
+        .map(|source| source_crashes(&SourceId::new(source.manifest().info.id)))
+        .collect();
+
+    crashes.sort_by(|a, b| a.source_id.value().cmp(b.source_id.value()));
+
+    crashes
+}
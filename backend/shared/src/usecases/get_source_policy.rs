@@ -0,0 +1,15 @@
+use crate::{
+    model::{SourceId, SourcePolicyReport},
+    source::{sandbox::source_policy_violations, Source},
+};
+
+/// Tells what the source is allowed to do, and what it recently tried to do that it wasn't.
+pub fn get_source_policy(source: &Source) -> SourcePolicyReport {
+    let source_id = SourceId::new(source.manifest().info.id);
+
+    SourcePolicyReport {
+        violations: source_policy_violations(&source_id),
+        policy: source.policy(),
+        source_id,
+    }
+}
@@ -0,0 +1,12 @@
+use crate::{
+    model::SourceId,
+    source::{
+        request_log::{source_requests, LoggedRequest},
+        Source,
+    },
+};
+
+/// Lists the latest requests made by (or for) the source, oldest first.
+pub fn get_source_requests(source: &Source) -> Vec<LoggedRequest> {
+    source_requests(&SourceId::new(source.manifest().info.id))
+}
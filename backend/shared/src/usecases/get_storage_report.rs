@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    chapter_storage::ChapterStorage,
+    database::Database,
+    model::{MangaId, SourceId},
+};
+
+#[derive(Serialize)]
+pub struct StorageReport {
+    /// Size of everything in the storage, posters and sidecar files included.
+    pub used_bytes: u64,
+    pub limit_bytes: u64,
+    pub posters_bytes: u64,
+    /// Chapters whose manga isn't known by the database anymore.
+    pub unknown_chapters_bytes: u64,
+    pub sources: Vec<SourceStorageUsage>,
+    pub mangas: Vec<MangaStorageUsage>,
+}
+
+#[derive(Serialize)]
+pub struct SourceStorageUsage {
+    pub source_id: SourceId,
+    pub bytes: u64,
+    pub chapters: usize,
+}
+
+#[derive(Serialize)]
+pub struct MangaStorageUsage {
+    pub manga_id: MangaId,
+    pub manga_title: Option<String>,
+    pub in_library: bool,
+    pub bytes: u64,
+    pub chapters: usize,
+    pub read_chapters: usize,
+}
+
+pub async fn get_storage_report(
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+) -> Result<StorageReport> {
+    let entries: HashMap<_, _> = db
+        .find_chapter_storage_entries()
+        .await?
+        .into_iter()
+        .map(|entry| (entry.id.clone(), entry))
+        .collect();
+
+    let mut unknown_chapters_bytes = 0;
+    let mut mangas: HashMap<MangaId, MangaStorageUsage> = HashMap::new();
+
+    for chapter in chapter_storage.stored_chapter_files(entries.keys()) {
+        let Some(entry) = chapter.chapter_id.as_ref().and_then(|id| entries.get(id)) else {
+            unknown_chapters_bytes += chapter.size;
+
+            continue;
+        };
+
+        let usage = mangas
+            .entry(entry.id.manga_id().clone())
+            .or_insert_with(|| MangaStorageUsage {
+                manga_id: entry.id.manga_id().clone(),
+                manga_title: entry.manga_title.clone(),
+                in_library: entry.in_library,
+                bytes: 0,
+                chapters: 0,
+                read_chapters: 0,
+            });
+
+        usage.bytes += chapter.size;
+        usage.chapters += 1;
+        if entry.read {
+            usage.read_chapters += 1;
+        }
+    }
+
+    let mut sources: HashMap<SourceId, SourceStorageUsage> = HashMap::new();
+    for usage in mangas.values() {
+        let source_id = usage.manga_id.source_id();
+        let source_usage = sources
+            .entry(source_id.clone())
+            .or_insert_with(|| SourceStorageUsage {
+                source_id: source_id.clone(),
+                bytes: 0,
+                chapters: 0,
+            });
+
+        source_usage.bytes += usage.bytes;
+        source_usage.chapters += usage.chapters;
+    }
+
+    // Biggest first
+    let mut mangas: Vec<_> = mangas.into_values().collect();
+    mangas.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    let mut sources: Vec<_> = sources.into_values().collect();
+    sources.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+
+    Ok(StorageReport {
+        used_bytes: chapter_storage.storage_size().bytes() as u64,
+        limit_bytes: chapter_storage.storage_size_limit().bytes() as u64,
+        posters_bytes: chapter_storage
+            .stored_poster_files()
+            .iter()
+            .map(|poster| poster.size)
+            .sum(),
+        unknown_chapters_bytes,
+        sources,
+        mangas,
+    })
+}
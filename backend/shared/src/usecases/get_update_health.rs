@@ -0,0 +1,30 @@
+use anyhow::Result;
+
+use crate::{database::Database, model::SourceUpdateHealth};
+
+/// Lists the library manga that failed to update at least `min_failures` times in a row,
+/// grouped by source.
+pub async fn get_update_health(
+    db: &Database,
+    min_failures: i64,
+) -> Result<Vec<SourceUpdateHealth>> {
+    let failing_mangas = db.find_failing_mangas(min_failures).await?;
+
+    let mut health: Vec<SourceUpdateHealth> = Vec::new();
+    for failing_manga in failing_mangas {
+        let source_id = failing_manga.manga_id.source_id();
+
+        // Rows are ordered by source, so we only need to look at the last group
+        match health.last_mut() {
+            Some(source_health) if &source_health.source_id == source_id => {
+                source_health.failing_mangas.push(failing_manga);
+            }
+            _ => health.push(SourceUpdateHealth {
+                source_id: source_id.clone(),
+                failing_mangas: vec![failing_manga],
+            }),
+        }
+    }
+
+    Ok(health)
+}
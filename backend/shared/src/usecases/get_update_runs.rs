@@ -0,0 +1,11 @@
+use anyhow::Result;
+
+use crate::{database::Database, model::UpdateRun};
+
+pub async fn get_update_runs(db: &Database, limit: i64) -> Result<Vec<UpdateRun>> {
+    db.get_update_runs(limit).await
+}
+
+pub async fn get_update_run(db: &Database, run_id: i64) -> Result<Option<UpdateRun>> {
+    db.find_update_run(run_id).await
+}
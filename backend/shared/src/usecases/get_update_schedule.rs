@@ -0,0 +1,33 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    database::Database,
+    model::MangaUpdateSchedule,
+    settings::Settings,
+    update_policy::{deferral_reason, device_conditions, Deferral, DeviceConditions},
+};
+
+#[derive(Serialize)]
+pub struct UpdateSchedule {
+    /// Why the scheduled checks are currently on hold, if they are.
+    pub deferred: Option<Deferral>,
+    pub device_conditions: DeviceConditions,
+    pub mangas: Vec<MangaUpdateSchedule>,
+}
+
+pub async fn get_update_schedule(db: &Database, settings: &Settings) -> Result<UpdateSchedule> {
+    let device_conditions = device_conditions();
+    let deferred = deferral_reason(
+        &settings.update_policy,
+        device_conditions,
+        chrono::Local::now(),
+    );
+    let mangas = db.get_update_schedule().await?;
+
+    Ok(UpdateSchedule {
+        deferred,
+        device_conditions,
+        mangas,
+    })
+}
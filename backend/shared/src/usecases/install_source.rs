@@ -7,15 +7,21 @@ use serde::Deserialize;
 use serde_json::Value;
 use url::Url;
 
-use crate::{model::SourceId, source_manager::SourceManager};
+use crate::{
+    model::SourceId, source::Source, source_collection::SourceCollection,
+    source_manager::SourceManager,
+};
 
+/// Installs a source from the source lists, or updates it when it's already installed. Returns
+/// the source when it replaced a different version of itself, in which case the keys of its
+/// stored mangas and chapters may need to be migrated (see `migrate_source_keys`).
 pub async fn install_source(
     source_manager: &mut SourceManager,
     arc_manager: &Arc<Mutex<SourceManager>>,
     source_lists: &[Url],
     source_id: SourceId,
     source_of_source: String,
-) -> Result<()> {
+) -> Result<Option<Source>> {
     let (source_list, source_list_item, source_of_source) =
         stream::iter(source_lists.iter().filter(|url| {
             let domain = url.domain().unwrap_or("").to_string();
@@ -68,9 +74,17 @@ pub async fn install_source(
     };
     let aix_content = reqwest::get(aix_url).await?.bytes().await?;
 
+    let previous_version = source_manager
+        .get_by_id(&source_id)
+        .map(|source| source.manifest().info.version);
     source_manager.install_source(&source_id, aix_content, source_of_source, arc_manager)?;
 
-    Ok(())
+    let source = source_manager
+        .get_by_id(&source_id)
+        .context("source wasn't installed")?;
+    let updated = previous_version.is_some_and(|version| version != source.manifest().info.version);
+
+    Ok(updated.then(|| source.clone()))
 }
 
 #[derive(Deserialize)]
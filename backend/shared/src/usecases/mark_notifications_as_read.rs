@@ -0,0 +1,13 @@
+use anyhow::Result;
+
+use crate::{database::Database, model::MangaId};
+
+/// Marks a single notification as read, returning whether it exists.
+pub async fn mark_notification_as_read(db: &Database, id: i64) -> Result<bool> {
+    db.mark_notification_as_read(id).await
+}
+
+/// Marks all notifications as read, or only the ones about `manga_id` if given.
+pub async fn mark_notifications_as_read(db: &Database, manga_id: Option<MangaId>) -> Result<()> {
+    db.mark_notifications_as_read(manga_id.as_ref()).await
+}
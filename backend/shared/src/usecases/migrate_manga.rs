@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    chapter_storage::ChapterStorage,
+    connectivity::connectivity,
+    database::Database,
+    model::{ChapterId, ChapterInformation, ChapterState, Manga, MangaId, SourceId},
+    source_collection::SourceCollection,
+    usecases::{enqueue_chapter_downloads, refresh_manga_chapters, search_mangas},
+};
+
+// How similar the title of a search result must be to the one of the migrated manga for it to
+// be picked automatically, from 0 (nothing in common) to 1 (same title).
+const MIN_AUTO_PICK_SIMILARITY: f32 = 0.8;
+const SEARCH_TIMEOUT_SECONDS: u64 = 30;
+const CHAPTERS_TIMEOUT_SECONDS: u64 = 60;
+
+pub struct MigrationCandidate {
+    pub manga: Manga,
+    /// How similar its title is to the one of the migrated manga, from 0 to 1.
+    pub similarity: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum MigrationTarget {
+    /// Migrates to the given manga, usually a candidate picked by the user.
+    Manga(MangaId),
+    /// Migrates to the candidate from the given sources whose title is the most similar, if
+    /// it's similar enough.
+    BestMatch(Vec<SourceId>),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MigrationReport {
+    pub from: MangaId,
+    pub to: MangaId,
+    /// How many chapters of the migrated manga were matched with one of the target.
+    pub matched_chapters: usize,
+    pub unmatched_chapters: usize,
+    /// How many chapters of the target were marked as read.
+    pub read_chapters: usize,
+    pub enqueued_downloads: usize,
+}
+
+/// Searches the given sources for mangas with the same title as `manga_id`, the most similar
+/// ones first.
+pub async fn find_migration_candidates(
+    source_collection: &impl SourceCollection,
+    db: &Database,
+    cancellation_token: CancellationToken,
+    manga_id: &MangaId,
+    source_ids: &[SourceId],
+) -> Result<Vec<MigrationCandidate>> {
+    if connectivity().is_offline() {
+        bail!("can't search for mangas to migrate to while offline");
+    }
+
+    let title = db
+        .find_cached_manga_information(manga_id)
+        .await?
+        .and_then(|information| information.title)
+        .ok_or_else(|| anyhow!("the manga to migrate has no known title"))?;
+
+    // `search_mangas` only takes the sources to leave out
+    let exclude = source_collection
+        .sources()
+        .into_iter()
+        .map(|source| source.manifest().info.id)
+        .filter(|id| {
+            !source_ids.iter().any(|source_id| source_id.value() == id)
+                || id == manga_id.source_id().value()
+        })
+        .collect();
+
+    let (mangas, _) = search_mangas(
+        source_collection,
+        db,
+        cancellation_token,
+        title.clone(),
+        &Some(exclude),
+        SEARCH_TIMEOUT_SECONDS,
+    )
+    .await?;
+
+    let mut candidates: Vec<_> = mangas
+        .into_iter()
+        .map(|manga| MigrationCandidate {
+            similarity: title_similarity(&title, manga.information.title.as_deref()),
+            manga,
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+
+    Ok(candidates)
+}
+
+/// Moves a manga of the library to another source: the chapters read are matched by their
+/// number, the manga state (preferred scanlator, automatic downloads) is carried over, and the
+/// library entry is replaced. When `redownload` is set, the chapters that were stored are
+/// enqueued for download from the target.
+pub async fn migrate_manga(
+    source_collection: &impl SourceCollection,
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+    cancellation_token: CancellationToken,
+    from: MangaId,
+    target: MigrationTarget,
+    redownload: bool,
+) -> Result<MigrationReport> {
+    let to = match target {
+        MigrationTarget::Manga(to) => to,
+        MigrationTarget::BestMatch(source_ids) => {
+            let candidates = find_migration_candidates(
+                source_collection,
+                db,
+                cancellation_token.clone(),
+                &from,
+                &source_ids,
+            )
+            .await?;
+
+            candidates
+                .into_iter()
+                .find(|candidate| candidate.similarity >= MIN_AUTO_PICK_SIMILARITY)
+                .map(|candidate| candidate.manga.information.id)
+                .ok_or_else(|| anyhow!("couldn't find a manga with a similar title"))?
+        }
+    };
+    if to == from {
+        bail!("can't migrate a manga to itself");
+    }
+
+    let target_source = source_collection
+        .get_by_id(to.source_id())
+        .with_context(|| format!("source {} isn't installed", to.source_id().value()))?;
+    let target_chapters = refresh_manga_chapters(
+        &cancellation_token,
+        db,
+        target_source,
+        &to,
+        CHAPTERS_TIMEOUT_SECONDS,
+    )
+    .await?;
+
+    let chapters = db.find_cached_chapters(&from, chapter_storage).await?;
+    let mut states: HashMap<u32, ChapterState> = HashMap::new();
+    let mut stored_numbers = Vec::new();
+    let mut matched_chapters = 0;
+    for chapter in &chapters {
+        let Some(number) = chapter.information.chapter_number.filter(|number| {
+            target_chapters
+                .iter()
+                .any(|target| target.chapter_number == Some(*number))
+        }) else {
+            continue;
+        };
+        matched_chapters += 1;
+
+        // The same chapter may be listed once per scanlator
+        let state = states.entry(number.to_bits()).or_default();
+        state.read |= chapter.state.read;
+        state.last_read = state.last_read.max(chapter.state.last_read);
+
+        if chapter.downloaded {
+            stored_numbers.push(number);
+        }
+    }
+
+    let mut chapter_states = Vec::new();
+    let mut read_chapters = 0;
+    for target_chapter in &target_chapters {
+        let Some(state) = target_chapter
+            .chapter_number
+            .and_then(|number| states.get(&number.to_bits()))
+        else {
+            continue;
+        };
+        if !state.read && state.last_read.is_none() {
+            continue;
+        }
+
+        chapter_states.push((
+            target_chapter.id.clone(),
+            ChapterState {
+                read: state.read,
+                last_read: state.last_read,
+            },
+        ));
+        if state.read {
+            read_chapters += 1;
+        }
+    }
+
+    let mut manga_state = db.find_manga_state(&from).await?.unwrap_or_default();
+    // A scanlator the target doesn't know of would hide all of its chapters
+    if !target_chapters.iter().any(|chapter| {
+        chapter.scanlator.is_some() && chapter.scanlator == manga_state.preferred_scanlator
+    }) {
+        manga_state.preferred_scanlator = None;
+    }
+    let preferred_scanlator = manga_state.preferred_scanlator.clone();
+
+    // Nothing is carried over if the manga can't be moved
+    db.migrate_manga_library_entry(&from, &to, &chapter_states, manga_state)
+        .await?;
+
+    let mut enqueued_downloads = 0;
+    if redownload {
+        stored_numbers.sort_by(f32::total_cmp);
+        stored_numbers.dedup();
+
+        let chapter_ids: Vec<ChapterId> = stored_numbers
+            .into_iter()
+            .filter_map(|number| {
+                pick_chapter(&target_chapters, number, preferred_scanlator.as_deref())
+            })
+            .collect();
+        enqueue_chapter_downloads(db, &chapter_ids, 0).await?;
+        enqueued_downloads = chapter_ids.len();
+    }
+
+    Ok(MigrationReport {
+        from,
+        to,
+        matched_chapters,
+        unmatched_chapters: chapters.len() - matched_chapters,
+        read_chapters,
+        enqueued_downloads,
+    })
+}
+
+// Picks the chapter with the given number to download, preferring the preferred scanlator.
+fn pick_chapter(
+    chapters: &[ChapterInformation],
+    number: f32,
+    preferred_scanlator: Option<&str>,
+) -> Option<ChapterId> {
+    let matching: Vec<_> = chapters
+        .iter()
+        .filter(|chapter| chapter.chapter_number == Some(number))
+        .collect();
+
+    matching
+        .iter()
+        .find(|chapter| {
+            preferred_scanlator.is_some() && chapter.scanlator.as_deref() == preferred_scanlator
+        })
+        .or(matching.first())
+        .map(|chapter| chapter.id.clone())
+}
+
+// Normalized Levenshtein similarity of two titles, ignoring case, punctuation and spacing.
+fn title_similarity(title: &str, other: Option<&str>) -> f32 {
+    let simplify = |title: &str| -> Vec<char> {
+        search_mangas::normalize(Some(title))
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect()
+    };
+    let a = simplify(title);
+    let b = simplify(other.unwrap_or_default());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut previous = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous + usize::from(a_char != b_char);
+            previous = distances[j + 1];
+            distances[j + 1] = substitution.min(previous + 1).min(distances[j] + 1);
+        }
+    }
+
+    1.0 - distances[b.len()] as f32 / longest as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(key: &str, number: f32, scanlator: Option<&str>) -> ChapterInformation {
+        ChapterInformation {
+            id: ChapterId::from_strings("en.target".into(), "manga".into(), key.into()),
+            title: None,
+            scanlator: scanlator.map(str::to_owned),
+            chapter_number: Some(number),
+            volume_number: None,
+            last_updated: None,
+            thumbnail: None,
+            lang: None,
+            url: None,
+            locked: None,
+        }
+    }
+
+    #[test]
+    fn title_similarity_ignores_case_punctuation_and_spacing() {
+        assert_eq!(title_similarity("One Piece", Some("one-piece!")), 1.0);
+        assert_eq!(
+            title_similarity("ＯＮＥ ＰＩＥＣＥ", Some("One Piece")),
+            1.0
+        );
+        assert_eq!(title_similarity("kitten", Some("sitting")), 1.0 - 3.0 / 7.0);
+        assert_eq!(title_similarity("Naruto", Some("Bleach")), 0.0);
+        assert_eq!(title_similarity("Naruto", None), 0.0);
+        assert_eq!(title_similarity("!!", Some("??")), 0.0);
+    }
+
+    #[test]
+    fn pick_chapter_prefers_the_preferred_scanlator() {
+        let chapters = [
+            chapter("1-a", 1.0, Some("A")),
+            chapter("2-a", 2.0, Some("A")),
+            chapter("2-b", 2.0, Some("B")),
+            chapter("3", 3.0, None),
+        ];
+        let pick = |number, scanlator| {
+            pick_chapter(&chapters, number, scanlator).map(|id| id.value().clone())
+        };
+
+        assert_eq!(pick(2.0, Some("B")), Some("2-b".into()));
+        assert_eq!(pick(2.0, Some("C")), Some("2-a".into()));
+        assert_eq!(pick(2.0, None), Some("2-a".into()));
+        assert_eq!(pick(3.0, Some("B")), Some("3".into()));
+        assert_eq!(pick(4.0, None), None);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use log::{info, warn};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    chapter_storage::ChapterStorage,
+    database::Database,
+    model::{ChapterId, MangaId, SourceId},
+    source::Source,
+};
+
+/// Asks a source which was just updated for the current keys of its mangas and chapters the
+/// user keeps something about (see `Database::find_source_keys`) and of its stored chapters, in
+/// case the new version changed how they're built. Everything stored under an old key is moved
+/// to the new one, and so are the stored chapter files. Returns how many keys changed.
+///
+/// Keys the source fails to migrate are left as they are. The database is only locked to look
+/// up the keys and to rewrite them, not while the source migrates them.
+pub async fn migrate_source_keys(
+    db: &Mutex<Database>,
+    chapter_storage: &ChapterStorage,
+    source: &Source,
+    cancellation_token: CancellationToken,
+) -> Result<usize> {
+    if !source.1.handle_key_migration {
+        return Ok(0);
+    }
+
+    let source_id = source.manifest().info.id;
+    let (mut manga_ids, mut chapter_ids, cached_chapter_ids) = db
+        .lock()
+        .await
+        .find_source_keys(&SourceId::new(source_id.clone()))
+        .await?;
+    chapter_ids.extend(
+        cached_chapter_ids
+            .into_iter()
+            .filter(|chapter_id| chapter_storage.get_stored_chapter(chapter_id).is_some()),
+    );
+    // Chapters are migrated along with their manga
+    manga_ids.extend(
+        chapter_ids
+            .iter()
+            .map(|chapter_id| chapter_id.manga_id().clone()),
+    );
+
+    let mut mangas = HashMap::new();
+    for manga_id in manga_ids {
+        match source
+            .handle_key_migration_next(cancellation_token.clone(), manga_id.value().clone(), None)
+            .await
+        {
+            Ok(key) if &key != manga_id.value() => {
+                let new_manga_id = MangaId::new(manga_id.source_id().clone(), key);
+                mangas.insert(manga_id, new_manga_id);
+            }
+            Ok(_) => {}
+            Err(e) => warn!(
+                "source {source_id} failed to migrate the key of manga {}: {e}",
+                manga_id.value()
+            ),
+        }
+    }
+
+    let mut chapters = HashMap::new();
+    for chapter_id in chapter_ids {
+        let new_manga_id = mangas
+            .get(chapter_id.manga_id())
+            .unwrap_or(chapter_id.manga_id())
+            .clone();
+        let key = match source
+            .handle_key_migration_next(
+                cancellation_token.clone(),
+                chapter_id.manga_id().value().clone(),
+                Some(chapter_id.value().clone()),
+            )
+            .await
+        {
+            Ok(key) => key,
+            Err(e) => {
+                warn!(
+                    "source {source_id} failed to migrate the key of chapter {}: {e}",
+                    chapter_id.value()
+                );
+                chapter_id.value().clone()
+            }
+        };
+
+        let new_chapter_id = ChapterId::new(new_manga_id, key);
+        if new_chapter_id != chapter_id {
+            chapters.insert(chapter_id, new_chapter_id);
+        }
+    }
+
+    if mangas.is_empty() && chapters.is_empty() {
+        return Ok(0);
+    }
+
+    db.lock()
+        .await
+        .rewrite_source_keys(&mangas, &chapters)
+        .await?;
+
+    // Stored chapters are named after their keys
+    for (old, new) in &chapters {
+        if let Err(e) = chapter_storage.rename_chapter(old, new) {
+            warn!(
+                "failed to move stored chapter {} of source {source_id}: {e}",
+                old.value()
+            );
+        }
+    }
+
+    info!(
+        "migrated the keys of {} mangas and {} chapters of source {source_id}",
+        mangas.len(),
+        chapters.len()
+    );
+
+    Ok(mangas.len() + chapters.len())
+}
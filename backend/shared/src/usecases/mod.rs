@@ -3,32 +3,54 @@ pub mod check_mangas_update;
 pub mod check_update;
 pub mod clear_notifications;
 pub mod delete_notification;
+pub mod enqueue_chapter_downloads;
 pub mod fetch_manga_chapter;
 pub mod fetch_manga_chapters_in_batch;
 pub mod find_orphan_or_read_files;
 pub mod get_cached_manga_chapters;
 pub mod get_cached_manga_details;
+pub mod get_connectivity;
 pub mod get_count_notifications;
+pub mod get_download_queue;
+pub mod get_manga_auto_download;
 pub mod get_manga_library;
+pub mod get_manga_pinned;
 pub mod get_manga_preferred_scanlator;
+pub mod get_notification_groups;
 pub mod get_notifications;
+pub mod get_pinned;
+pub mod get_source_crashes;
+pub mod get_source_policy;
+pub mod get_source_requests;
 pub mod get_source_setting_definitions;
 pub mod get_source_stored_settings;
+pub mod get_storage_report;
+pub mod get_update_health;
+pub mod get_update_runs;
+pub mod get_update_schedule;
 pub mod install_source;
 pub mod install_update;
 pub mod list_available_sources;
 pub mod list_installed_sources;
 pub mod mark_chapter_as_read;
 pub mod mark_chapters_as_read;
+pub mod mark_notifications_as_read;
+pub mod migrate_manga;
+pub mod migrate_source_keys;
+pub mod notify_source_updates;
 pub mod refresh_manga_chapters;
 pub mod refresh_manga_details;
 pub mod remove_manga_from_library;
 pub mod revoke_manga_chapter;
 pub mod search_mangas;
+pub mod set_chapter_pinned;
+pub mod set_manga_auto_download;
+pub mod set_manga_pinned;
 pub mod set_manga_preferred_scanlator;
 pub mod set_source_stored_settings;
 pub mod sync_database;
 pub mod uninstall_source;
+pub mod update_download_queue;
 pub mod update_last_read_chapter;
 pub mod update_settings;
 
@@ -38,31 +60,55 @@ pub use check_mangas_update::run_manga_cron;
 pub use check_update::check_update;
 pub use clear_notifications::clear_notifications;
 pub use delete_notification::delete_notification;
+pub use enqueue_chapter_downloads::enqueue_chapter_downloads;
 pub use fetch_manga_chapter::fetch_manga_chapter;
 pub use fetch_manga_chapters_in_batch::fetch_manga_chapters_in_batch;
 pub use find_orphan_or_read_files::find_orphan_or_read_files;
 pub use get_cached_manga_chapters::get_cached_manga_chapters;
 pub use get_cached_manga_details::get_cached_manga_details;
+pub use get_connectivity::get_connectivity;
 pub use get_count_notifications::get_count_notifications;
+pub use get_download_queue::get_download_queue;
+pub use get_manga_auto_download::get_manga_auto_download;
 pub use get_manga_library::get_manga_library;
+pub use get_manga_pinned::get_manga_pinned;
 pub use get_manga_preferred_scanlator::get_manga_preferred_scanlator;
+pub use get_notification_groups::get_notification_groups;
 pub use get_notifications::get_notifications;
+pub use get_pinned::get_pinned;
+pub use get_source_crashes::get_source_crashes;
+pub use get_source_policy::get_source_policy;
+pub use get_source_requests::get_source_requests;
 pub use get_source_setting_definitions::get_source_setting_definitions;
 pub use get_source_stored_settings::get_source_stored_settings;
+pub use get_storage_report::get_storage_report;
+pub use get_update_health::get_update_health;
+pub use get_update_runs::{get_update_run, get_update_runs};
+pub use get_update_schedule::get_update_schedule;
 pub use install_source::install_source;
 pub use install_update::install_update;
 pub use list_available_sources::list_available_sources;
 pub use list_installed_sources::list_installed_sources;
 pub use mark_chapter_as_read::mark_chapter_as_read;
 pub use mark_chapters_as_read::mark_chapters_as_read;
+pub use mark_notifications_as_read::{mark_notification_as_read, mark_notifications_as_read};
+pub use migrate_manga::{find_migration_candidates, migrate_manga};
+pub use migrate_source_keys::migrate_source_keys;
+pub use notify_source_updates::notify_source_updates;
 pub use refresh_manga_chapters::refresh_manga_chapters;
 pub use refresh_manga_details::refresh_manga_details;
 pub use remove_manga_from_library::remove_manga_from_library;
 pub use revoke_manga_chapter::revoke_manga_chapter;
 pub use search_mangas::search_mangas;
+pub use set_chapter_pinned::set_chapter_pinned;
+pub use set_manga_auto_download::set_manga_auto_download;
+pub use set_manga_pinned::set_manga_pinned;
 pub use set_manga_preferred_scanlator::set_manga_preferred_scanlator;
 pub use set_source_stored_settings::set_source_stored_settings;
 pub use sync_database::sync_database;
 pub use uninstall_source::uninstall_source;
+pub use update_download_queue::{
+    remove_queued_download, set_download_queue_paused, set_queued_download_paused,
+};
 pub use update_last_read_chapter::update_last_read_chapter;
 pub use update_settings::update_settings;
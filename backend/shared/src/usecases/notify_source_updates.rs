@@ -0,0 +1,52 @@
+use anyhow::Result;
+
+use crate::{
+    database::Database,
+    model::{NotificationKind, NotificationSubject, SourceInformation},
+};
+
+/// Creates a notification for every installed source which has a newer version available,
+/// unless there's already an unread one about that version.
+pub async fn notify_source_updates(
+    db: &Database,
+    installed_sources: &[SourceInformation],
+    available_sources: &[SourceInformation],
+) -> Result<()> {
+    for installed in installed_sources {
+        let Some(available) = available_sources
+            .iter()
+            .filter(|available| available.id == installed.id)
+            .max_by_key(|available| available.version)
+        else {
+            continue;
+        };
+
+        if available.version <= installed.version {
+            continue;
+        }
+
+        let message = format!(
+            "{} can be updated to version {}",
+            available.name, available.version
+        );
+        if db
+            .has_unread_source_notification(
+                NotificationKind::SourceUpdateAvailable,
+                &installed.id,
+                &message,
+            )
+            .await?
+        {
+            continue;
+        }
+
+        db.insert_event_notification(
+            NotificationKind::SourceUpdateAvailable,
+            NotificationSubject::Source(&installed.id),
+            Some(&message),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use tokio::time::{timeout, Duration};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
+    connectivity::connectivity,
     database::Database,
     model::{ChapterInformation, MangaId},
     source::Source,
@@ -15,6 +16,10 @@ pub async fn refresh_manga_chapters<'a>(
     id: &'a MangaId,
     seconds: u64,
 ) -> Result<Vec<ChapterInformation>> {
+    if connectivity().is_offline() {
+        bail!("can't refresh chapters while offline");
+    }
+
     let duration = Duration::from_secs(seconds);
 
     let fetch_task = async {
@@ -1,9 +1,10 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use tokio::time::{timeout, Duration};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
     chapter_storage::ChapterStorage,
+    connectivity::connectivity,
     database::Database,
     model::MangaId,
     source::{model::PublishingStatus, Source},
@@ -17,6 +18,10 @@ pub async fn refresh_manga_details(
     id: &MangaId,
     seconds: u64,
 ) -> Result<PublishingStatus> {
+    if connectivity().is_offline() {
+        bail!("can't refresh manga details while offline");
+    }
+
     let duration = Duration::from_secs(seconds);
 
     let fetch_task = async {
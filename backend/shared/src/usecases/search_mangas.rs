@@ -1,7 +1,10 @@
 use crate::{
+    connectivity::connectivity,
     database::Database,
     model::{Manga, MangaInformation, MangaState, SourceInformation},
+    source::SourceTimedOutError,
     source_collection::SourceCollection,
+    usecases::get_manga_library,
 };
 use futures::{stream, StreamExt};
 use log::warn;
@@ -26,6 +29,15 @@ pub async fn search_mangas(
     exclude: &Option<Vec<String>>,
     seconds: u64,
 ) -> Result<(Vec<Manga>, Vec<SearchError>), Error> {
+    // Sources can't be reached while offline, so only the library is searched
+    if connectivity().is_offline() {
+        let mangas = search_library(source_collection, db, &query, exclude)
+            .await
+            .map_err(Error::DatabaseError)?;
+
+        return Ok((mangas, vec![]));
+    }
+
     // FIXME this looks awful
     let query = &query;
 
@@ -82,11 +94,18 @@ pub async fn search_mangas(
                                     e
                                 );
 
+                                // Sources stopped for running too long timed out, like the slow ones below
+                                let reason = if e.downcast_ref::<SourceTimedOutError>().is_some() {
+                                    "timeout".to_string()
+                                } else {
+                                    e.to_string()
+                                };
+
                                 (
                                     vec![],
                                     Some(SearchError {
                                         source_id: source.manifest().info.id.clone(),
-                                        reason: e.to_string(),
+                                        reason,
                                     }),
                                 )
                             }
@@ -166,24 +185,48 @@ pub async fn search_mangas(
         })
         .collect();
 
-    mangas.sort_by_cached_key(|manga| {
-        manga
-            .information
-            .title
-            .clone()
-            .unwrap_or_default()
-            .nfkc()
-            .flat_map(char::to_lowercase)
-            .collect::<String>()
-    });
+    mangas.sort_by_cached_key(|manga| normalize(manga.information.title.as_deref()));
 
     Ok((mangas, errors))
 }
 
+async fn search_library(
+    source_collection: &impl SourceCollection,
+    db: &Database,
+    query: &str,
+    exclude: &Option<Vec<String>>,
+) -> anyhow::Result<Vec<Manga>> {
+    let query = normalize(Some(query));
+    let mut mangas: Vec<_> = get_manga_library(db, source_collection, &Default::default())
+        .await?
+        .into_iter()
+        .filter(|manga| {
+            !exclude
+                .as_ref()
+                .is_some_and(|exclude| exclude.contains(manga.source_information.id.value()))
+        })
+        .filter(|manga| normalize(manga.information.title.as_deref()).contains(&query))
+        .collect();
+
+    mangas.sort_by_cached_key(|manga| normalize(manga.information.title.as_deref()));
+
+    Ok(mangas)
+}
+
+pub(crate) fn normalize(title: Option<&str>) -> String {
+    title
+        .unwrap_or_default()
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("an error occurred while fetching search results from the source")]
     SourceError(#[source] anyhow::Error),
+    #[error("an error occurred while searching the library")]
+    DatabaseError(#[source] anyhow::Error),
 }
 
 type ResultManga = (MangaInformation, Option<(Option<usize>, Option<i64>, bool)>);
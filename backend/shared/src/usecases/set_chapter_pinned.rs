@@ -0,0 +1,8 @@
+use crate::{database::Database, model::ChapterId};
+use anyhow::Result;
+
+/// Pins (or unpins) a single chapter, so that its download is never evicted from the storage
+/// or listed for cleanup.
+pub async fn set_chapter_pinned(db: &Database, chapter_id: &ChapterId, pinned: bool) -> Result<()> {
+    db.set_chapter_pinned(chapter_id, pinned).await
+}
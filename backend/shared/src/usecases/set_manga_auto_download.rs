@@ -0,0 +1,19 @@
+use crate::{
+    database::Database,
+    model::{AutoDownloadMode, MangaId},
+};
+use anyhow::Result;
+
+pub async fn set_manga_auto_download(
+    db: &Database,
+    manga_id: MangaId,
+    auto_download: AutoDownloadMode,
+) -> Result<()> {
+    let mut updated_manga_state = db.find_manga_state(&manga_id).await?.unwrap_or_default();
+    updated_manga_state.auto_download = auto_download;
+
+    db.upsert_manga_state(&manga_id, updated_manga_state)
+        .await?;
+
+    Ok(())
+}
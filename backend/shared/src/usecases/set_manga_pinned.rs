@@ -0,0 +1,8 @@
+use crate::{database::Database, model::MangaId};
+use anyhow::Result;
+
+/// Pins (or unpins) a manga, so that none of its downloaded chapters are evicted from the
+/// storage or listed for cleanup.
+pub async fn set_manga_pinned(db: &Database, manga_id: &MangaId, pinned: bool) -> Result<()> {
+    db.set_manga_pinned(manga_id, pinned).await
+}
@@ -1,7 +1,4 @@
-use crate::{
-    database::Database,
-    model::{MangaId, MangaState},
-};
+use crate::{database::Database, model::MangaId};
 use anyhow::Result;
 
 pub async fn set_manga_preferred_scanlator(
@@ -9,9 +6,8 @@ pub async fn set_manga_preferred_scanlator(
     manga_id: MangaId,
     preferred_scanlator: Option<String>,
 ) -> Result<()> {
-    let updated_manga_state = MangaState {
-        preferred_scanlator,
-    };
+    let mut updated_manga_state = db.find_manga_state(&manga_id).await?.unwrap_or_default();
+    updated_manga_state.preferred_scanlator = preferred_scanlator;
 
     db.upsert_manga_state(&manga_id, updated_manga_state)
         .await?;
@@ -2,20 +2,31 @@ use anyhow::{bail, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use tokio::time::sleep;
 
-use crate::{database::Database, settings::Settings};
+use crate::{
+    connectivity::connectivity,
+    database::Database,
+    events::{self, Event},
+    model::{NotificationKind, NotificationSubject, PendingAction},
+    settings::Settings,
+};
 
 const URL_CDN_TRACE: &str = "https://www.cloudflare.com/cdn-cgi/trace";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SyncResult {
     UpToDate,
     UpdateRequired,
     Updated,
     UpdatedToServer,
+    /// Offline: the sync will be made once the connection comes back.
+    Queued,
 }
 
 pub async fn sync_database(
@@ -23,6 +34,31 @@ pub async fn sync_database(
     settings: &mut Settings,
     accept_migrate_local: bool,
     accept_replace_remote: bool,
+) -> Result<SyncResult> {
+    let result = if connectivity().is_offline() {
+        db.queue_pending_action(PendingAction::SyncDatabase {
+            accept_migrate_local,
+            accept_replace_remote,
+        })
+        .await
+        .map(|_| SyncResult::Queued)
+    } else {
+        sync(db, settings, accept_migrate_local, accept_replace_remote).await
+    };
+
+    events::publish(Event::SyncStatus {
+        result: result.as_ref().ok().cloned(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    });
+
+    result
+}
+
+async fn sync(
+    db: &mut Database,
+    settings: &mut Settings,
+    accept_migrate_local: bool,
+    accept_replace_remote: bool,
 ) -> Result<SyncResult> {
     let url = settings
         .api_sync
@@ -47,16 +83,18 @@ pub async fn sync_database(
 
     t = std::time::Instant::now();
     let remote_sha_opt = dav_read(&client, &dav_base, &user, &password, "database.sha256").await?;
-    if let Some(remote_sha_bytes) = remote_sha_opt {
-        let remote_sha = String::from_utf8_lossy(&remote_sha_bytes)
+    let remote_sha = remote_sha_opt.map(|remote_sha_bytes| {
+        String::from_utf8_lossy(&remote_sha_bytes)
             .trim()
-            .to_string();
+            .to_string()
+    });
+    println!("SHA256 checked in {:?}", t.elapsed());
 
-        println!("SHA256 checked in {:?}", t.elapsed());
-        // --- SHA256 が同じなら → 最新 ---
-        if remote_sha == local_sha {
-            return Ok(SyncResult::UpToDate);
-        }
+    // --- SHA256 が同じなら → 最新 ---
+    if remote_sha.as_ref() == Some(&local_sha) {
+        write_last_synced_sha(db, &local_sha);
+
+        return Ok(SyncResult::UpToDate);
     }
 
     t = std::time::Instant::now();
@@ -77,6 +115,9 @@ pub async fn sync_database(
 
                 if let Some(buf) = buf {
                     db.hot_replace(&buf).await?;
+                    if let Some(remote_sha) = &remote_sha {
+                        write_last_synced_sha(db, remote_sha);
+                    }
 
                     return Ok(SyncResult::Updated);
                 } else {
@@ -85,6 +126,26 @@ pub async fn sync_database(
             }
             // --- サーバーの DB が新しい場合 ---
             else if remote_time > now {
+                // Only a conflict if both databases changed since the last sync, as replacing
+                // either one then loses changes
+                let last_synced_sha = read_last_synced_sha(db);
+                let diverged =
+                    last_synced_sha.as_ref() != Some(&local_sha) && remote_sha != last_synced_sha;
+                if diverged
+                    && !db
+                        .has_unread_notification(NotificationKind::SyncConflict)
+                        .await
+                        .unwrap_or(true)
+                {
+                    let _ = db
+                        .insert_event_notification(
+                            NotificationKind::SyncConflict,
+                            NotificationSubject::None,
+                            Some("The local and the synced database both changed"),
+                        )
+                        .await;
+                }
+
                 return Ok(SyncResult::UpdateRequired);
             }
         }
@@ -129,10 +190,29 @@ pub async fn sync_database(
     )
     .await?;
     println!("SHA256 updated in {:?}", t.elapsed());
+    write_last_synced_sha(db, &local_sha);
 
     Ok(SyncResult::UpdatedToServer)
 }
 
+// The SHA256 of the database as of the last sync is kept next to it (not in it, as that would
+// change it), to tell which side changed since.
+fn last_synced_sha_path(db: &Database) -> PathBuf {
+    db.filename.with_extension("synced.sha256")
+}
+
+fn read_last_synced_sha(db: &Database) -> Option<String> {
+    fs::read_to_string(last_synced_sha_path(db))
+        .ok()
+        .map(|sha| sha.trim().to_string())
+}
+
+fn write_last_synced_sha(db: &Database, sha: &str) {
+    if let Err(e) = fs::write(last_synced_sha_path(db), sha) {
+        eprintln!("Failed to store the SHA256 of the synced database: {}", e);
+    }
+}
+
 async fn get_current_time() -> Result<i64> {
     let client = Client::new();
     let text = client.get(URL_CDN_TRACE).send().await?.text().await?;
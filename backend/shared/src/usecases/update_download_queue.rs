@@ -0,0 +1,31 @@
+use anyhow::Result;
+
+use crate::{database::Database, download_queue::notify_queue_changed};
+
+/// Pauses or resumes the whole download queue. The chapter being downloaded when the
+/// queue is paused is still finished.
+pub async fn set_download_queue_paused(db: &Database, paused: bool) -> Result<()> {
+    db.set_download_queue_paused(paused).await?;
+    notify_queue_changed();
+
+    Ok(())
+}
+
+/// Pauses or resumes a single queued chapter, returning whether it could be changed.
+pub async fn set_queued_download_paused(db: &Database, id: i64, paused: bool) -> Result<bool> {
+    let changed = db.set_download_paused(id, paused).await?;
+    if changed {
+        notify_queue_changed();
+    }
+
+    Ok(changed)
+}
+
+pub async fn remove_queued_download(db: &Database, id: i64) -> Result<bool> {
+    let removed = db.remove_queued_download(id).await?;
+    if removed {
+        notify_queue_changed();
+    }
+
+    Ok(removed)
+}
@@ -4,7 +4,16 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use size::{consts, Size};
 
-use crate::settings::{ChapterSortingMode, LibrarySortingMode, Settings, StorageSizeLimit};
+use crate::{
+    connectivity::connectivity,
+    download_scheduler::download_scheduler,
+    http_cache::http_cache,
+    settings::{
+        ChapterSortingMode, ConnectivitySettings, DownloadLimits, HttpCacheSettings,
+        LibrarySortingMode, Settings, SourceLimitsSettings, StorageSizeLimit, UpdatePolicy,
+    },
+    source::limits::source_limits,
+};
 
 pub fn update_settings(
     settings: &mut Settings,
@@ -16,6 +25,11 @@ pub fn update_settings(
     let mut updated_settings = settings.clone();
     settings_to_update.apply_updates(&mut updated_settings);
     updated_settings.save_to_file(settings_path)?;
+    download_scheduler().configure(&updated_settings.download_limits);
+    http_cache().configure(&updated_settings.http_cache);
+    connectivity().set_forced_offline(updated_settings.offline_mode);
+    connectivity().configure(&updated_settings.connectivity);
+    source_limits().configure(&updated_settings.source_limits);
 
     *settings = updated_settings;
 
@@ -34,6 +48,20 @@ pub struct UpdateableSettings {
     source_skip_cron: Option<String>,
     preload_chapters: usize,
     optimize_image: bool,
+    #[serde(default)]
+    update_policy: Option<UpdatePolicy>,
+    #[serde(default)]
+    job_history_retention_minutes: Option<u64>,
+    #[serde(default)]
+    download_limits: Option<DownloadLimits>,
+    #[serde(default)]
+    http_cache: Option<HttpCacheSettings>,
+    #[serde(default)]
+    offline_mode: Option<bool>,
+    #[serde(default)]
+    connectivity: Option<ConnectivitySettings>,
+    #[serde(default)]
+    source_limits: Option<SourceLimitsSettings>,
 }
 
 impl UpdateableSettings {
@@ -48,6 +76,27 @@ impl UpdateableSettings {
         settings.enabled_cron_check_mangas_update = self.enabled_cron_check_mangas_update;
         settings.source_skip_cron = self.source_skip_cron;
         settings.preload_chapters = self.preload_chapters;
+        if let Some(update_policy) = self.update_policy {
+            settings.update_policy = update_policy;
+        }
+        if let Some(job_history_retention_minutes) = self.job_history_retention_minutes {
+            settings.job_history_retention_minutes = job_history_retention_minutes;
+        }
+        if let Some(download_limits) = self.download_limits {
+            settings.download_limits = download_limits;
+        }
+        if let Some(http_cache) = self.http_cache {
+            settings.http_cache = http_cache;
+        }
+        if let Some(offline_mode) = self.offline_mode {
+            settings.offline_mode = offline_mode;
+        }
+        if let Some(connectivity) = self.connectivity {
+            settings.connectivity = connectivity;
+        }
+        if let Some(source_limits) = self.source_limits {
+            settings.source_limits = source_limits;
+        }
     }
 }
 
@@ -66,6 +115,13 @@ impl From<&Settings> for UpdateableSettings {
             source_skip_cron: value.source_skip_cron.clone(),
             preload_chapters: value.preload_chapters,
             optimize_image: value.optimize_image,
+            update_policy: Some(value.update_policy.clone()),
+            job_history_retention_minutes: Some(value.job_history_retention_minutes),
+            download_limits: Some(value.download_limits.clone()),
+            http_cache: Some(value.http_cache.clone()),
+            offline_mode: Some(value.offline_mode),
+            connectivity: Some(value.connectivity.clone()),
+            source_limits: Some(value.source_limits.clone()),
         }
     }
 }
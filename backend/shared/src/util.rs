@@ -1,7 +1,4 @@
-use std::{
-    collections::{HashMap, HashSet},
-    net::SocketAddr,
-};
+use std::collections::{HashMap, HashSet};
 
 use anyhow::{anyhow, bail, Context, Result};
 use base64::engine::general_purpose::STANDARD as BASE64;
@@ -10,28 +7,29 @@ use dom_query::Document;
 #[cfg(feature = "all")]
 use futures::{stream, StreamExt};
 use reqwest::{Client, Request};
-use tokio::net::TcpStream;
 use tokio_util::sync::CancellationToken;
 use url::Url;
 
-use crate::source::{model::Page, Source};
-
-pub async fn has_internet_connection() -> bool {
-    try_connecting_to_cloudflare().await.is_ok()
-}
-
-async fn try_connecting_to_cloudflare() -> Result<()> {
-    let addrs = [
-        SocketAddr::from(([1, 0, 0, 1], 80)),
-        SocketAddr::from(([1, 1, 1, 1], 80)),
-    ];
-
-    TcpStream::connect(&addrs[..]).await?;
+use crate::{
+    chapter_downloader::{DownloadProgress, ProgressTracker},
+    download_scheduler::download_scheduler,
+    http_fixtures::http_fixtures,
+    source::{model::Page, Source},
+};
 
-    Ok(())
+pub async fn request_with_forced_referer_from_request(
+    client: &Client,
+    req: Request,
+    max_redirects: usize,
+) -> Result<reqwest::Response, anyhow::Error> {
+    http_fixtures()
+        .send_image(req, |req| {
+            send_with_forced_referer(client, req, max_redirects)
+        })
+        .await
 }
 
-pub async fn request_with_forced_referer_from_request(
+async fn send_with_forced_referer(
     client: &Client,
     mut req: Request,
     max_redirects: usize,
@@ -295,12 +293,20 @@ pub async fn download_image(
             .map_err(|err| anyhow!(format!("failed WASM modify request {err}")))?;
         let req_url = request.url().clone();
 
+        let scheduler = download_scheduler();
+        let source_rate_limit = source.declared_rate_limit().await?;
+        let _permit = scheduler
+            .acquire(&source.manifest().info.id, &req_url, source_rate_limit)
+            .await;
+
+        // Novel images keep their own client: the scheduler's one accepts invalid certificates
+        // and doesn't follow redirects, which is only meant for manga pages
         let client = Client::builder().build()?;
         let response = request_with_forced_referer_from_request(&client, request, 10)
             .await
             .map_err(|err| anyhow!(format!("Request error: {err}")))?;
 
-        response
+        let bytes = response
             .bytes()
             .await
             .map_err(|err| {
@@ -309,7 +315,10 @@ pub async fn download_image(
                     index, req_url, err
                 ))
             })?
-            .to_vec()
+            .to_vec();
+        scheduler.consume_bandwidth(bytes.len()).await;
+
+        bytes
     };
 
     let (ext, mime) = match image::guess_format(&bytes_vec) {
@@ -373,14 +382,8 @@ pub async fn download_all_images(
     pages: Vec<Page>,
     source: &Source,
     token: &CancellationToken,
-    #[cfg(not(feature = "all"))] on_progress: impl FnMut(usize, f32, f32) + Send + 'static,
+    mut on_progress: impl FnMut(DownloadProgress) + Send + 'static,
 ) -> anyhow::Result<HashMap<String, anyhow::Result<(Vec<u8>, String, String)>>> {
-    #[cfg(not(feature = "all"))]
-    use std::sync::{
-        atomic::{AtomicUsize, Ordering},
-        Mutex,
-    };
-
     let mut seen = HashSet::<String>::new();
     type Task = std::pin::Pin<
         Box<
@@ -388,9 +391,6 @@ pub async fn download_all_images(
                 + Send,
         >,
     >;
-    #[cfg(not(feature = "all"))]
-    let mut tasks: Vec<(usize, Task)> = Vec::new();
-    #[cfg(feature = "all")]
     let mut tasks: Vec<Task> = Vec::new();
 
     for page in &pages {
@@ -408,9 +408,6 @@ pub async fn download_all_images(
                     (url.to_string(), result)
                 });
 
-                #[cfg(not(feature = "all"))]
-                tasks.push((index, task));
-                #[cfg(feature = "all")]
                 tasks.push(task);
             }
         }
@@ -431,9 +428,6 @@ pub async fn download_all_images(
                             (url, result)
                         });
 
-                        #[cfg(not(feature = "all"))]
-                        tasks.push((index, task));
-                        #[cfg(feature = "all")]
                         tasks.push(task);
                     }
                 }
@@ -441,38 +435,27 @@ pub async fn download_all_images(
         }
     }
 
+    let mut progress_tracker = ProgressTracker::new(tasks.len());
+
     #[cfg(not(feature = "all"))]
     let store: HashMap<String, anyhow::Result<(Vec<u8>, String, String)>> = {
         use std::sync::Arc;
         use tokio::sync::{mpsc, Semaphore};
 
-        let total = tasks.len() as f32;
-        let progress = Arc::new(AtomicUsize::new(0));
-        let on_progress = Arc::new(Mutex::new(on_progress));
-
         let semaphore = Arc::new(Semaphore::new(4));
         let mut results = HashMap::new();
-        if tasks.len() > 0 {
+        if !tasks.is_empty() {
             let (tx, mut rx) = mpsc::channel(tasks.len());
 
-            for (index, fut) in tasks {
+            for fut in tasks {
                 let tx = tx.clone();
                 let semaphore = Arc::clone(&semaphore);
-                let progress = Arc::clone(&progress);
-                let on_progress = Arc::clone(&on_progress);
 
                 tokio::spawn(async move {
                     let _permit = semaphore.acquire().await.unwrap();
 
                     let out = fut.await;
 
-                    let cur = progress.fetch_add(1, Ordering::SeqCst) + 1;
-                    let cur_f = cur as f32;
-                    {
-                        let mut cb = on_progress.lock().unwrap();
-                        cb(index, cur_f, total);
-                    }
-
                     let _ = tx.send(out).await;
                 });
             }
@@ -480,6 +463,7 @@ pub async fn download_all_images(
             drop(tx);
 
             while let Some((url, res)) = rx.recv().await {
+                on_progress(progress_tracker.page_done(downloaded_size(&res), res.is_err()));
                 results.insert(url, res);
             }
         }
@@ -487,7 +471,20 @@ pub async fn download_all_images(
     };
 
     #[cfg(feature = "all")]
-    let store: HashMap<_, _> = stream::iter(tasks).buffer_unordered(4).collect().await;
+    let store: HashMap<_, _> = stream::iter(tasks)
+        .buffer_unordered(4)
+        .inspect(|(_, res)| {
+            on_progress(progress_tracker.page_done(downloaded_size(res), res.is_err()))
+        })
+        .collect()
+        .await;
 
     Ok(store)
 }
+
+fn downloaded_size(result: &anyhow::Result<(Vec<u8>, String, String)>) -> usize {
+    result
+        .as_ref()
+        .map(|(bytes, _, _)| bytes.len())
+        .unwrap_or(0)
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use serde_json::{json, Value};
+use shared::{
+    http_cache::http_cache,
+    http_fixtures::{http_fixtures, FixturesMode},
+    settings::{Settings, SourceSettingValue},
+    source::{
+        limits::source_limits,
+        model::{Chapter, Page},
+        request_observer::{observe_requests, ObservedRequest},
+        Source,
+    },
+    source_manager::SourceManager,
+};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+/// Loads a source from its `.aix` file and runs one of its operations, printing the decoded
+/// result as JSON along with timings and the requests the source made.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Path to the .aix file
+    source_path: PathBuf,
+
+    /// Reads the settings from a rakuyomi `settings.json`, instead of using the defaults
+    #[arg(long)]
+    settings_file: Option<PathBuf>,
+
+    /// Overrides a stored setting of the source. The value is parsed as JSON, and used as a
+    /// string if that fails
+    #[arg(long = "setting", value_name = "KEY=VALUE")]
+    setting_overrides: Vec<String>,
+
+    /// Saves the requests made, along with their responses, as fixtures to the given folder
+    #[arg(long, value_name = "FOLDER", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Serves responses from the fixtures in the given folder, without using the network
+    #[arg(long, value_name = "FOLDER")]
+    replay: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Searches mangas by title
+    Search { query: String },
+    /// Fetches the details of a manga
+    Details { manga_id: String },
+    /// Fetches the chapters of a manga
+    Chapters { manga_id: String },
+    /// Fetches the pages of a chapter
+    Pages {
+        manga_id: String,
+        chapter_id: String,
+        #[arg(long)]
+        chapter_num: Option<f32>,
+    },
+    /// Lists the listings of the source, or the mangas of one of them
+    Listings {
+        /// The ID of a listing, as listed without it
+        listing_id: Option<String>,
+    },
+    /// Fetches the home layout of the source
+    Home,
+    /// Builds the request the source would use to fetch an image
+    ImageRequest { url: Url },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut settings = match &args.settings_file {
+        Some(path) => Settings::from_file(path)
+            .with_context(|| format!("couldn't read settings file at {}", path.display()))?,
+        None => Settings::default(),
+    };
+    http_cache().configure(&settings.http_cache);
+    source_limits().configure(&settings.source_limits);
+    if let Some(folder) = args.record.clone() {
+        http_fixtures().set_mode(FixturesMode::Record(folder));
+    } else if let Some(folder) = args.replay.clone() {
+        http_fixtures().set_mode(FixturesMode::Replay(folder));
+    }
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    observe_requests({
+        let requests = requests.clone();
+
+        move |request| requests.lock().unwrap().push(request_json(request))
+    });
+
+    let started_at = Instant::now();
+    let mut source = load_source(&args.source_path, settings.clone())?;
+    if !args.setting_overrides.is_empty() {
+        // The settings are stored by source ID, which is only known once the source is loaded
+        let overrides = parse_setting_overrides(&args.setting_overrides)?;
+        settings
+            .source_settings
+            .entry(source.manifest().info.id)
+            .or_default()
+            .extend(overrides);
+
+        source = load_source(&args.source_path, settings)?;
+    }
+    let load_time = started_at.elapsed();
+
+    let started_at = Instant::now();
+    let result = run(&source, args.command).await;
+    let run_time = started_at.elapsed();
+
+    let manifest = source.manifest();
+    let output = json!({
+        "source": {
+            "id": manifest.info.id,
+            "name": manifest.info.name,
+            "version": manifest.info.version,
+        },
+        "result": result.as_ref().ok(),
+        "error": result.as_ref().err().map(|error| format!("{error:#}")),
+        "timings": {
+            "load_ms": millis(load_time),
+            "run_ms": millis(run_time),
+        },
+        "requests": *requests.lock().unwrap(),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    if result.is_err() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn load_source(path: &Path, settings: Settings) -> Result<Source> {
+    let sources_folder = path
+        .parent()
+        .map(Path::to_owned)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let manager = SourceManager::new(sources_folder, HashMap::new(), settings);
+    let arc_manager = Arc::new(tokio::sync::Mutex::new(manager.clone()));
+
+    Source::from_aix_file(path, &manager, &arc_manager)
+        .with_context(|| format!("couldn't load source from {}", path.display()))
+}
+
+fn parse_setting_overrides(overrides: &[String]) -> Result<HashMap<String, SourceSettingValue>> {
+    overrides
+        .iter()
+        .map(|setting| {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected KEY=VALUE, found {setting}"))?;
+            let value = serde_json::from_str(value)
+                .unwrap_or_else(|_| SourceSettingValue::String(value.to_owned()));
+
+            Ok((key.to_owned(), value))
+        })
+        .collect()
+}
+
+async fn run(source: &Source, command: Command) -> Result<Value> {
+    let cancellation_token = CancellationToken::new();
+
+    let result = match command {
+        Command::Search { query } => {
+            serde_json::to_value(source.search_mangas(cancellation_token, query).await?)?
+        }
+        Command::Details { manga_id } => serde_json::to_value(
+            source
+                .get_manga_details(cancellation_token, manga_id)
+                .await?,
+        )?,
+        Command::Chapters { manga_id } => source
+            .get_chapter_list(cancellation_token, manga_id)
+            .await?
+            .iter()
+            .map(chapter_json)
+            .collect(),
+        Command::Pages {
+            manga_id,
+            chapter_id,
+            chapter_num,
+        } => source
+            .get_page_list(cancellation_token, manga_id, chapter_id, chapter_num)
+            .await?
+            .iter()
+            .map(page_json)
+            .collect(),
+        Command::Listings { listing_id: None } => {
+            serde_json::to_value(source.get_listings_next(cancellation_token).await?)?
+        }
+        Command::Listings {
+            listing_id: Some(listing_id),
+        } => {
+            let Some(listing) = source
+                .get_listings_next(cancellation_token.clone())
+                .await?
+                .into_iter()
+                .find(|listing| listing.id == listing_id)
+            else {
+                bail!("the source has no listing with ID {listing_id}");
+            };
+
+            serde_json::to_value(source.get_manga_list(cancellation_token, listing).await?)?
+        }
+        Command::Home => serde_json::to_value(source.get_home_next(cancellation_token).await?)?,
+        Command::ImageRequest { url } => {
+            let request = source.get_image_request(url, None).await?;
+            let headers: HashMap<&str, &str> = request
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)))
+                .collect();
+
+            json!({
+                "method": request.method().as_str(),
+                "url": request.url().as_str(),
+                "headers": headers,
+            })
+        }
+    };
+
+    Ok(result)
+}
+
+fn chapter_json(chapter: &Chapter) -> Value {
+    json!({
+        "id": chapter.id,
+        "manga_id": chapter.manga_id,
+        "title": chapter.title,
+        "scanlator": chapter.scanlator,
+        "url": chapter.url.as_ref().map(Url::as_str),
+        "lang": chapter.lang,
+        "chapter_num": chapter.chapter_num,
+        "volume_num": chapter.volume_num,
+        "date_uploaded": chapter.date_uploaded.map(|date| date.to_rfc3339()),
+        "thumbnail": chapter.thumbnail.as_ref().map(Url::as_str),
+        "locked": chapter.locked,
+    })
+}
+
+fn page_json(page: &Page) -> Value {
+    json!({
+        "index": page.index,
+        "image_url": page.image_url.as_ref().map(Url::as_str),
+        // The image itself would drown everything else
+        "base64_length": page.base64.as_ref().map(String::len),
+        "text": page.text,
+        "context": page.ctx,
+    })
+}
+
+fn request_json(request: &ObservedRequest) -> Value {
+    json!({
+        "method": request.method.as_ref().map(|method| method.as_str()),
+        "url": request.url.as_ref().map(Url::as_str),
+        "status": request.status.map(|status| status.as_u16()),
+        "size": request.size,
+        "elapsed_ms": millis(request.elapsed),
+        "error": request.error,
+    })
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
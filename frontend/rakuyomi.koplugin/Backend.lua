@@ -169,6 +169,7 @@ end
 --- @field read boolean If this chapter was read to its end.
 --- @field last_read number? The timestamp (in seconds since epoch) of when this chapter was last read to its end.
 --- @field downloaded boolean If this chapter was already downloaded to the storage.
+--- @field pinned boolean If this chapter (or its whole manga) is pinned, so its download is never removed.
 --- @field title string? The title of this chapter, if any.
 --- @field locked boolean The locked
 --- @field lang string? The language code
@@ -303,6 +304,52 @@ function Backend.removeMangaFromLibrary(source_id, manga_id)
   })
 end
 
+--- @class MigrationCandidate
+--- @field manga Manga
+--- @field similarity number How similar its title is to the one of the migrated manga, from 0 to 1.
+
+--- @class MigrationReport
+--- @field from { source_id: string, manga_id: string }
+--- @field to { source_id: string, manga_id: string }
+--- @field matched_chapters number
+--- @field unmatched_chapters number
+--- @field read_chapters number
+--- @field enqueued_downloads number
+
+--- Searches the given sources for mangas to migrate a manga to, the most similar ones first.
+--- @param cancel_id number|nil
+--- @param source_ids string[]
+--- @return SuccessfulResponse<MigrationCandidate[]>|ErrorResponse
+function Backend.getMigrationCandidates(cancel_id, source_id, manga_id, source_ids)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/migration-candidates",
+    query_params = {
+      source_ids = table.concat(source_ids, ","),
+      cancel_id = cancel_id,
+    }
+  })
+end
+
+--- Migrates a manga of the library to another source, carrying over its read chapters and
+--- state. The target is either the given manga, or the most similar one from `source_ids`.
+--- @param target { source_id: string, manga_id: string }|nil
+--- @param source_ids string[]|nil
+--- @param redownload boolean Whether to download again from the target the chapters that were stored
+--- @return SuccessfulResponse<MigrationReport>|ErrorResponse
+function Backend.migrateManga(cancel_id, source_id, manga_id, target, source_ids, redownload)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/migrate",
+    method = "POST",
+    body = {
+      cancel_id = cancel_id,
+      target_source_id = target and target.source_id or nil,
+      target_manga_id = target and target.manga_id or nil,
+      source_ids = source_ids and #source_ids > 0 and source_ids or nil,
+      redownload = redownload,
+    }
+  })
+end
+
 --- @class SearchError
 --- @field source_id string
 --- @field reason string
@@ -460,6 +507,31 @@ function Backend.updateLastReadChapter(source_id, manga_id, chapter_id)
   })
 end
 
+--- Starts downloading the chapters after the given one in the background, replacing any
+--- prefetch already running. The amount of chapters comes from the `preload_chapters` setting.
+--- @param languages string[]|nil Languages of the chapters to prefetch; defaults to the language of the given chapter.
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.prefetchChapters(source_id, manga_id, chapter_id, languages)
+  return Backend.requestJson({
+    path = "/mangas/" ..
+        source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters/" .. util.urlEncode(chapter_id) .. "/prefetch",
+    method = "POST",
+    body = {
+      -- empty tables are encoded as objects, so leave the field out instead
+      languages = languages and #languages > 0 and languages or nil,
+    },
+  })
+end
+
+--- Cancels the prefetch of the manga's chapters, if any.
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.cancelPrefetch(source_id, manga_id)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/prefetch",
+    method = "DELETE",
+  })
+end
+
 --- Marks the chapter as read.
 --- @param value boolean|nil
 --- @return SuccessfulResponse<nil>|ErrorResponse
@@ -480,6 +552,14 @@ function Backend.listInstalledSources()
   })
 end
 
+--- Lists how many times each installed source crashed (and was recreated) since the server started.
+--- @return SuccessfulResponse<{ source_id: string, crashes: number, recoveries: number, last_error: string|nil, last_crashed_at: number|nil }[]>|ErrorResponse
+function Backend.getSourceCrashes()
+  return Backend.requestJson({
+    path = "/source-crashes",
+  })
+end
+
 --- Lists information about sources available via our source lists.
 --- @return SuccessfulResponse<SourceInformation[]>|ErrorResponse
 function Backend.listAvailableSources()
@@ -535,6 +615,22 @@ function Backend.getSourceStoredSettings(source_id)
   })
 end
 
+--- Finds what a source is allowed to do, and what it recently tried to do that it wasn't.
+--- @return SuccessfulResponse<{ source_id: string, policy: table, violations: { action: string, at: number }[] }>|ErrorResponse
+function Backend.getSourcePolicy(source_id)
+  return Backend.requestJson({
+    path = "/installed-sources/" .. source_id .. "/policy",
+  })
+end
+
+--- Lists the latest requests made by (or for) a source, oldest first, to help debugging it.
+--- @return SuccessfulResponse<{ method: string|nil, url: string|nil, status: number|nil, size: number|nil, elapsed_ms: number, error: string|nil, operation: string, manga_id: string|nil, chapter_id: string|nil, logged_at: number }[]>|ErrorResponse
+function Backend.getSourceRequests(source_id)
+  return Backend.requestJson({
+    path = "/installed-sources/" .. source_id .. "/requests",
+  })
+end
+
 function Backend.setSourceStoredSettings(source_id, stored_settings)
   return Backend.requestJson({
     path = "/installed-sources/" .. source_id .. "/stored-settings",
@@ -564,6 +660,73 @@ function Backend.setPreferredScanlator(source_id, manga_id, preferred_scanlator)
   })
 end
 
+--- @alias AutoDownloadMode { mode: 'off'|'all_new_chapters'|'preferred_scanlator' }|{ mode: 'languages', languages: string[] }
+
+--- Gets the auto-download rule for a manga.
+--- @return SuccessfulResponse<AutoDownloadMode>|ErrorResponse
+function Backend.getAutoDownload(source_id, manga_id)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/auto-download",
+    method = "GET"
+  })
+end
+
+--- Sets the auto-download rule for a manga.
+--- @param auto_download AutoDownloadMode
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.setAutoDownload(source_id, manga_id, auto_download)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/auto-download",
+    method = "POST",
+    body = auto_download
+  })
+end
+
+--- Whether the manga is pinned, which keeps all of its downloaded chapters in the storage.
+--- @return SuccessfulResponse<boolean>|ErrorResponse
+function Backend.getMangaPinned(source_id, manga_id)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/pinned",
+    method = "GET"
+  })
+end
+
+--- Pins or unpins a manga. The downloaded chapters of a pinned manga are never evicted from
+--- the storage nor listed for cleanup.
+--- @param pinned boolean
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.setMangaPinned(source_id, manga_id, pinned)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/pinned",
+    method = "POST",
+    body = {
+      pinned = pinned,
+    },
+  })
+end
+
+--- Pins or unpins a single chapter.
+--- @param pinned boolean
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.setChapterPinned(source_id, manga_id, chapter_id, pinned)
+  return Backend.requestJson({
+    path = "/mangas/" ..
+        source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters/" .. util.urlEncode(chapter_id) .. "/pinned",
+    method = "POST",
+    body = {
+      pinned = pinned,
+    },
+  })
+end
+
+--- Lists the pinned manga and chapters, along with how much of the storage they take.
+--- @return SuccessfulResponse<{ pinned_bytes: number, limit_bytes: number, mangas: table[], chapters: table[] }>|ErrorResponse
+function Backend.getPinned()
+  return Backend.requestJson({
+    path = "/pinned",
+  })
+end
+
 --- @alias ChapterSortingMode 'chapter_ascending'|'chapter_descending'
 --- @class Settings: { chapter_sorting_mode: ChapterSortingMode, preload_chapters: number }
 
@@ -633,11 +796,50 @@ function Backend.createDownloadScanlatorChaptersJob(source_id, manga_id, scanlat
   })
 end
 
+--- Creates a job migrating every manga of the library from a source to the most similar manga
+--- found in the target sources. Returns the job's UUID.
+--- @return SuccessfulResponse<string>|ErrorResponse
+function Backend.createMigrateSourceJob(source_id, target_source_ids, redownload)
+  return Backend.requestJson({
+    path = "/jobs/migrate-source",
+    method = 'POST',
+    body = {
+      source_id = source_id,
+      target_source_ids = target_source_ids,
+      redownload = redownload,
+    }
+  })
+end
+
 --- @class PendingJob<T>: { type: 'PENDING', data: T }
 --- @class CompletedJob<T>: { type: 'COMPLETED', data: T }
 --- @class ErroredJob: { type: 'ERROR', data: ErrorResponse }
 
---- @alias DownloadChapterJobDetails PendingJob<nil>|CompletedJob<[string, DownloadError[]]>|ErroredJob
+--- @class DownloadProgress
+--- @field pages_done number
+--- @field pages_total number
+--- @field pages_failed number
+--- @field bytes_downloaded number
+--- @field bytes_per_second number Average download speed since the chapter started downloading
+
+--- @alias DownloadChapterJobDetails PendingJob<DownloadProgress>|CompletedJob<[string, DownloadError[]]>|ErroredJob
+
+--- @class JobSummary
+--- @field id string
+--- @field kind string
+--- @field manga_id { source_id: string, manga_id: string }|nil
+--- @field created_at number
+--- @field finished_at number|nil
+--- @field detail PendingJob<any>|CompletedJob<any>|ErroredJob
+
+--- Lists running jobs and recently finished ones, newest first.
+--- @return SuccessfulResponse<JobSummary[]>|ErrorResponse
+function Backend.getJobs()
+  return Backend.requestJson({
+    path = "/jobs",
+    method = 'GET'
+  })
+end
 
 --- Gets details about a job.
 --- @return SuccessfulResponse<DownloadChapterJobDetails>|ErrorResponse
@@ -693,6 +895,152 @@ function Backend.cleanup()
   end
 end
 
+--- Lists the most recent update check runs, with per-source aggregates.
+--- @return SuccessfulResponse<table[]>|ErrorResponse
+function Backend.getUpdateRuns(limit)
+  return Backend.requestJson({
+    path = "/update-runs",
+    query_params = {
+      limit = limit,
+    },
+  })
+end
+
+--- Gets a single update check run, including the result for each checked manga.
+--- @return SuccessfulResponse<table>|ErrorResponse
+function Backend.getUpdateRun(id)
+  return Backend.requestJson({
+    path = "/update-runs/" .. id,
+  })
+end
+
+--- Lists the library manga that failed to update `min_failures` times in a row, grouped by source.
+--- @return SuccessfulResponse<table[]>|ErrorResponse
+function Backend.getUpdateHealth(min_failures)
+  return Backend.requestJson({
+    path = "/update-health",
+    query_params = {
+      min_failures = min_failures,
+    },
+  })
+end
+
+--- @class DownloadQueueItem
+--- @field id number
+--- @field chapter_id ChapterId
+--- @field chapter_title string|nil
+--- @field chapter_number number|nil
+--- @field manga_title string|nil
+--- @field priority number
+--- @field status 'queued'|'downloading'|'paused'|'failed'
+--- @field attempts number
+--- @field last_error string|nil
+
+--- @return SuccessfulResponse<{ paused: boolean, items: DownloadQueueItem[] }>|ErrorResponse
+function Backend.getDownloadQueue()
+  return Backend.requestJson({
+    path = "/download-queue",
+  })
+end
+
+--- Adds chapters to the persistent download queue, which keeps going across restarts.
+--- @param source_id string
+--- @param manga_id string
+--- @param chapter_ids string[]
+--- @param priority number|nil Chapters with a higher priority are downloaded first.
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.enqueueChapterDownloads(source_id, manga_id, chapter_ids, priority)
+  return Backend.requestJson({
+    path = "/download-queue",
+    method = "POST",
+    body = {
+      source_id = source_id,
+      manga_id = manga_id,
+      chapter_ids = chapter_ids,
+      priority = priority or 0,
+    },
+  })
+end
+
+--- Pauses or resumes the whole download queue.
+--- @param paused boolean
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.setDownloadQueuePaused(paused)
+  return Backend.requestJson({
+    path = paused and "/download-queue/pause" or "/download-queue/resume",
+    method = "POST",
+  })
+end
+
+--- Pauses or resumes a single queued chapter.
+--- @param id number
+--- @param paused boolean
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.setQueuedDownloadPaused(id, paused)
+  return Backend.requestJson({
+    path = "/download-queue/" .. id .. (paused and "/pause" or "/resume"),
+    method = "POST",
+  })
+end
+
+--- @param id number
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.removeQueuedDownload(id)
+  return Backend.requestJson({
+    path = "/download-queue/" .. id,
+    method = "DELETE",
+  })
+end
+
+--- @class MangaStorageUsage
+--- @field manga_id { source_id: string, manga_id: string }
+--- @field manga_title string|nil
+--- @field in_library boolean
+--- @field bytes number
+--- @field chapters number
+--- @field read_chapters number
+
+--- Reports how much of the storage is used, per source and per manga (biggest first).
+--- @return SuccessfulResponse<{ used_bytes: number, limit_bytes: number, posters_bytes: number, unknown_chapters_bytes: number, sources: { source_id: string, bytes: number, chapters: number }[], mangas: MangaStorageUsage[] }>|ErrorResponse
+function Backend.getStorageReport()
+  return Backend.requestJson({
+    path = "/storage",
+  })
+end
+
+--- Whether the backend is offline (forced through the settings, or detected), and the actions
+--- waiting for the connection to come back.
+--- @return SuccessfulResponse<{ offline: boolean, forced: boolean, last_check: { checked_at: number, online: boolean, reached: string|nil }|nil, pending_actions: { action: string, queued_at: number, accept_migrate_local: boolean|nil, accept_replace_remote: boolean|nil }[] }>|ErrorResponse
+function Backend.getConnectivity()
+  return Backend.requestJson({
+    path = "/connectivity",
+  })
+end
+
+--- Lists when each library manga is due to be checked for updates, and whether the checks
+--- are currently deferred by the update policy.
+--- @return SuccessfulResponse<table>|ErrorResponse
+function Backend.getUpdateSchedule()
+  return Backend.requestJson({
+    path = "/update-schedule",
+  })
+end
+
+--- Reports the device's network and power state, used by the update policy.
+--- @param unmetered boolean|nil
+--- @param charging boolean|nil
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.setDeviceConditions(unmetered, charging)
+  return Backend.requestJson({
+    path = "/update-schedule/device-conditions",
+    method = "POST",
+    body = {
+      unmetered = unmetered,
+      charging = charging,
+    },
+  })
+end
+
 --- @return SuccessfulResponse<number>|ErrorResponse
 function Backend.getCountNotification()
   return Backend.requestJson({
@@ -709,16 +1057,34 @@ end
 --- @field manga_id MangaId
 --- @field chapter_id string
 
+--- @alias NotificationKind 'new_chapter'|'source_update_available'|'download_finished'|'download_failed'|'sync_conflict'
+
 --- @class Notification
 --- @field id number
---- @field chapter_id ChapterId
+--- @field kind NotificationKind
+--- @field source_id string|nil
+--- @field manga_id MangaId|nil
+--- @field chapter_id ChapterId|nil
 --- @field manga_title string
 --- @field manga_cover string|nil
 --- @field manga_status number|nil
 --- @field chapter_title string|nil
 --- @field chapter_number number
+--- @field message string|nil
+--- @field is_read boolean
 --- @field created_at number
 
+--- @class NotificationGroup
+--- @field kind NotificationKind
+--- @field source_id string|nil
+--- @field manga_id MangaId|nil
+--- @field manga_title string|nil
+--- @field manga_cover string|nil
+--- @field summary string
+--- @field unread_count number
+--- @field latest_at number
+--- @field notifications Notification[]
+
 --- @return SuccessfulResponse<Notification[]>|ErrorResponse
 function Backend.getNotifications()
   return Backend.requestJson({
@@ -727,6 +1093,37 @@ function Backend.getNotifications()
   })
 end
 
+--- @return SuccessfulResponse<NotificationGroup[]>|ErrorResponse
+function Backend.getNotificationGroups()
+  return Backend.requestJson({
+    path = "/notification-groups",
+    method = 'GET'
+  })
+end
+
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.markNotificationAsRead(id)
+  return Backend.requestJson({
+    path = "/notifications/" .. id .. "/read",
+    method = 'POST'
+  })
+end
+
+--- Marks all notifications as read, or only the ones about the given manga.
+--- @param source_id string|nil
+--- @param manga_id string|nil
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.markAllNotificationsAsRead(source_id, manga_id)
+  return Backend.requestJson({
+    path = "/notifications/read-all",
+    method = 'POST',
+    query_params = {
+      source_id = source_id,
+      manga_id = manga_id,
+    },
+  })
+end
+
 --- @return SuccessfulResponse<nil>|ErrorResponse
 function Backend.removeNotification(id)
   return Backend.requestJson({
@@ -70,9 +70,6 @@ local ChapterListing = Menu:extend {
   -- scanlator filtering
   selected_scanlator = nil,
   available_scanlators = {},
-  -- keep track of preloads
-  preload_count = 0,
-  preload_jobs = nil,
 }
 
 function ChapterListing:init()
@@ -96,8 +93,6 @@ function ChapterListing:init()
 
   -- we need to do this after updating
   self:updateChapterList()
-
-  self.preload_jobs = {}
 end
 
 function ChapterListing:onClose(call_return)
@@ -390,6 +385,10 @@ function ChapterListing:generateItemTableFromChapters(chapters)
       mandatory = mandatory .. Icons.FA_DOWNLOAD
     end
 
+    if chapter.pinned then
+      mandatory = mandatory .. Icons.FA_THUMBTACK
+    end
+
     local post_text = ""
     if chapter.locked then
       post_text = _("Locked")
@@ -412,6 +411,10 @@ end
 
 --- @private
 function ChapterListing:onReturn()
+  Trapper:wrap(function()
+    Backend.cancelPrefetch(self.manga.source.id, self.manga.id)
+  end)
+
   table.remove(self.paths, 1)
   self:onClose()
 end
@@ -471,7 +474,6 @@ function ChapterListing:fetchAndShow(manga, onReturnCallback, accept_cached_resu
     on_return_callback = onReturnCallback,
     covers_fullscreen = true, -- hint for UIManager:_repaint()
     page = 1,  -- Start on first page (was self.page which is nil)
-    preload_count = settings.preload_chapters,
   }
   ui.on_return_callback = onReturnCallback
   UIManager:show(ui)
@@ -551,6 +553,14 @@ function ChapterListing:onContextMenuChoice(item)
             end)
           end
         end
+      },
+      {
+        text = Icons.FA_THUMBTACK .. " " .. (chapter.pinned and _("Unpin") or _("Pin")),
+        callback = function()
+          UIManager:close(dialog_context_menu)
+
+          self:setChapterPinned(chapter, not chapter.pinned)
+        end
       }
     }
   }
@@ -589,6 +599,50 @@ function ChapterListing:revokeChapter(chapter, hide_notify)
   end)
 end
 
+--- @private
+--- @param chapter Chapter
+--- @param pinned boolean
+function ChapterListing:setChapterPinned(chapter, pinned)
+  Trapper:wrap(function()
+    local response = Backend.setChapterPinned(self.manga.source.id, self.manga.id, chapter.id, pinned)
+
+    if response.type == 'ERROR' then
+      ErrorDialog:show(response.message)
+
+      return
+    end
+
+    self:updateChapterList()
+  end)
+end
+
+--- @private
+function ChapterListing:toggleMangaPinned()
+  Trapper:wrap(function()
+    local response = Backend.getMangaPinned(self.manga.source.id, self.manga.id)
+
+    if response.type == 'ERROR' then
+      ErrorDialog:show(response.message)
+
+      return
+    end
+
+    local pinned = not response.body
+    response = Backend.setMangaPinned(self.manga.source.id, self.manga.id, pinned)
+
+    if response.type == 'ERROR' then
+      ErrorDialog:show(response.message)
+
+      return
+    end
+
+    self:updateChapterList()
+    UIManager:show(InfoMessage:new {
+      text = pinned and _("Downloaded chapters of this manga will be kept") or _("Manga unpinned"),
+    })
+  end)
+end
+
 --- @private
 --- @param chapter Chapter
 --- @param value boolean
@@ -821,73 +875,14 @@ function ChapterListing:downloadChapter(chapter, download_job, callback)
   end)
 end
 
---- @private
---- @param chapter Chapter
-function ChapterListing:preloadChapters(chapter)
-  for i = 1, self.preload_count do
-    local preloadChapter = findNextChapter(self.chapters, chapter)
-    if preloadChapter == nil then
-      logger.info("No more chapters to preload.")
-      break
-    end
-
-    if preloadChapter.downloaded or preloadChapter.locked then
-      logger.info("Chapter already downloaded or locked, skipping preload: ", preloadChapter.id)
-      chapter = preloadChapter
-      goto continue
-    end
-
-    if self.preload_jobs[preloadChapter.id] ~= nil then
-      logger.info("Chapter already being preloaded: ", preloadChapter.id)
-    else
-      logger.info("Preloading chapter: ", preloadChapter.id)
-      local preload_job = DownloadChapter:new(
-        preloadChapter.source_id,
-        preloadChapter.manga_id,
-        preloadChapter.id,
-        preloadChapter.chapter_num
-      )
-
-      local job_status = preload_job:start()
-      if job_status.type == 'ERROR' then
-        logger.err("Could not start preload job for chapter ", preloadChapter.id, ": ", job_status.message)
-      else
-        self.preload_jobs[preloadChapter.id] = preload_job
-      end
-    end
-
-    chapter = preloadChapter
-    ::continue::
-  end
-end
-
-function ChapterListing:prunePreloadJobs()
-  for chapter_id, job in pairs(self.preload_jobs) do
-    local status = job:poll()
-    if status.type == 'SUCCESS' or status.type == 'ERROR' then
-      logger.info("Pruning finished preload job for chapter: ", chapter_id)
-      self.preload_jobs[chapter_id] = nil
-
-      if status.type == 'SUCCESS' then
-        for __, chapter in ipairs(self.chapters) do
-          if chapter.id == chapter_id then
-            chapter.downloaded = true
-            break
-          end
-        end
-      end
-    end
-  end
-end
-
 --- @private
 --- @param chapter Chapter
 --- @param download_job DownloadChapter|nil
 function ChapterListing:openChapterOnReader(chapter, download_job)
   self:downloadChapter(chapter, download_job, function(manga_path)
     local onReturnCallback = function()
-      self:updateItems()
-      self:prunePreloadJobs()
+      -- picks up the chapters prefetched while reading
+      self:updateChapterList()
 
       UIManager:show(self)
     end
@@ -896,14 +891,12 @@ function ChapterListing:openChapterOnReader(chapter, download_job)
       Backend.markChapterAsRead(chapter.source_id, chapter.manga_id, chapter.id)
 
       self:updateChapterList()
-      self:prunePreloadJobs()
 
       local nextChapter = findNextChapter(self.chapters, chapter)
-      local nextChapterDownloadJob = nextChapter and self.preload_jobs[nextChapter.id] or nil
 
       if nextChapter ~= nil then
         logger.info("opening next chapter", nextChapter)
-        self:openChapterOnReader(nextChapter, nextChapterDownloadJob)
+        self:openChapterOnReader(nextChapter)
       else
         MangaReader:closeReaderUi(function()
           UIManager:show(self)
@@ -936,11 +929,10 @@ function ChapterListing:openChapterOnReader(chapter, download_job)
       on_return_callback = onReturnCallback,
     })
 
-    if self.preload_count > 0 then
-      Trapper:wrap(function()
-        self:preloadChapters(chapter)
-      end)
-    end
+    -- the backend downloads the next chapters in the background, according to `preload_chapters`
+    Trapper:wrap(function()
+      Backend.prefetchChapters(chapter.source_id, chapter.manga_id, chapter.id, self.langs_selected)
+    end)
 
     self:onClose(false)
   end)
@@ -1035,6 +1027,14 @@ function ChapterListing:openMenu()
 
           self:onDownloadUnreadChapters()
         end
+      },
+      {
+        text = Icons.FA_THUMBTACK .. " " .. _("Pin / unpin manga"),
+        callback = function()
+          UIManager:close(dialog)
+
+          self:toggleMangaPinned()
+        end
       }
     }
   }
@@ -57,6 +57,12 @@ function DownloadUnreadChaptersJobDialog:pollAndCreateTextWidget()
     else
       message = _("Downloading chapters, this will take a while… (") ..
           state.body.downloaded .. '/' .. state.body.total .. ')'
+
+      local chapter = state.body.chapter
+      if chapter ~= nil and chapter.pages_total > 0 then
+        message = message .. '\n' .. _("Current chapter: page ") ..
+            chapter.pages_done .. '/' .. chapter.pages_total
+      end
     end
   elseif state.type == 'ERROR' then
     message = _("An error occurred while downloading chapters") .. ": " .. state.message
@@ -28,5 +28,6 @@ return {
   CHECK_ALL            = "\u{ee29}",
   FA_CLEANER           = "\u{e000}",
   FA_LOCKED            = "\u{f023}",
+  FA_THUMBTACK         = "\u{f08d}",
   LANG                 = "\u{f1ab}"
 }
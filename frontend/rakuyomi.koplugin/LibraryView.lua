@@ -699,6 +699,8 @@ function LibraryView:openMenu()
             msg = _("Database has been synced to the server!")
           elseif response.body == 'updated' then
             msg = _("Local database has been migrated from the server!")
+          elseif response.body == 'queued' then
+            msg = _("You're offline. The database will be synced once the connection comes back.")
           else
             msg = _("Sync completed!")
           end
@@ -83,14 +83,14 @@ function MenuItem:init()
   local screen_width = Screen:getWidth()
   local split_span_width = math.floor(screen_width * 0.05)
 
-  if self.entry.chapter_id == nil then
+  if self.entry.notifications == nil then
     self[1] = FrameContainer:new {
       bordersize = 0,
       padding = 0,
       HorizontalGroup:new {
         align = "center",
         TextBoxWidget:new {
-          text = self.entry.text,
+          text = self.entry.text or self.entry.message,
           -- lang = lang,
           width = screen_width - split_span_width - img_width,
           face = self.face,
@@ -103,8 +103,14 @@ function MenuItem:init()
     return
   end
 
-  --- @type Notification
-  local notify = self.entry
+  --- @type NotificationGroup
+  local group = self.entry
+
+  local details = group.manga_title and group.summary or nil
+  if group.unread_count > 0 then
+    local unread = T(_("%1 unread"), group.unread_count)
+    details = details and (details .. " · " .. unread) or unread
+  end
 
   local text_container = LeftContainer:new {
     dimen = Geom:new { w = self.content_width, h = self.dimen.h },
@@ -115,14 +121,15 @@ function MenuItem:init()
       },
       VerticalGroup:new {
         TextBoxWidget:new {
-          text = notify.manga_title,
+          text = group.manga_title or group.summary,
           -- lang = lang,
           width = screen_width - split_span_width - img_width,
           face = self.face,
           alignment = "left",
+          fgcolor = group.unread_count == 0 and Blitbuffer.COLOR_DARK_GRAY or nil,
         },
         TextBoxWidget:new {
-          text = "Ch." .. (notify.chapter_number or "unknown") .. ": " .. notify.chapter_title,
+          text = details or "",
           width = screen_width - split_span_width - img_width,
           face = self.info_face,
           bold = self.bold,
@@ -154,7 +161,7 @@ function MenuItem:init()
           dimen = Geom:new { w = self.content_width, h = self.dimen.h },
           HorizontalGroup:new {
             TextWidget:new {
-              text = calcLastReadText(notify.created_at),
+              text = calcLastReadText(group.latest_at),
               face = self.info_face,
               bold = self.bold,
               fgcolor = Blitbuffer.COLOR_DARK_GRAY or nil,
@@ -327,7 +334,7 @@ end
 --- @field openMenu fun()
 
 --- @class NotificationView : Menu
---- @field notifications Notification[]
+--- @field groups NotificationGroup[]
 --- @field on_return_callback fun()|nil
 local NotificationView = Menu:extend {
   name = "notification_view",
@@ -337,7 +344,7 @@ local NotificationView = Menu:extend {
   with_context_menu = true,
 
   items_per_page = 10,
-  notifications = nil,
+  groups = nil,
   on_return_callback = nil
 }
 
@@ -357,7 +364,7 @@ function NotificationView:init()
       text = _("Cleared all notifications!")
     })
 
-    self.notifications = {}
+    self.groups = {}
     self:updateItems()
   end
 
@@ -374,8 +381,8 @@ end
 --- @param select_number number|nil
 ---@param no_recalculate_dimen boolean|nil
 function NotificationView:updateItems(select_number, no_recalculate_dimen)
-  if #self.notifications > 0 then
-    self.item_table = self.notifications
+  if #self.groups > 0 then
+    self.item_table = self.groups
     self.multilines_show_more_text = false
     self.items_per_page = nil
   else
@@ -491,18 +498,18 @@ end
 
 --- @param onReturnCallback fun()
 function NotificationView:fetchAndShow(onReturnCallback)
-  local response = Backend.getNotifications()
+  local response = Backend.getNotificationGroups()
   if response.type == 'ERROR' then
     ErrorDialog:show(response.message)
 
     return
   end
 
-  local notifications = response.body
+  local groups = response.body
 
   ---@diagnostic disable-next-line: redundant-parameter
   local widget = NotificationView:new {
-    notifications = notifications,
+    groups = groups,
     covers_fullscreen = true, -- hint for UIManager:_repaint()
     page = self.page,
     on_return_callback = onReturnCallback
@@ -519,14 +526,34 @@ function NotificationView:onMenuSelect(item)
   end
 
   Trapper:wrap(function()
-    --- @type Notification
-    local notify = item
+    --- @type NotificationGroup
+    local group = item
+
+    if group.unread_count > 0 then
+      if group.manga_id ~= nil then
+        Backend.markAllNotificationsAsRead(group.manga_id.source_id, group.manga_id.manga_id)
+      else
+        for _, notification in ipairs(group.notifications) do
+          if not notification.is_read then
+            Backend.markNotificationAsRead(notification.id)
+          end
+        end
+      end
+    end
+
+    if group.manga_id == nil then
+      onReturnCallback()
+      self:onClose(false)
+
+      return
+    end
+
     local manga = {
-      id = notify.chapter_id.manga_id.manga_id,
+      id = group.manga_id.manga_id,
       source = {
-        id = notify.chapter_id.manga_id.source_id
+        id = group.manga_id.source_id
       },
-      title = notify.manga_title
+      title = group.manga_title
     }
 
     if ChapterListing:fetchAndShow(manga, onReturnCallback, true) then
@@ -538,27 +565,30 @@ end
 function NotificationView:onMenuHold(item)
   local confirm_dialog
   confirm_dialog = ConfirmBox:new {
-    text = _("Delete this notification?"),
+    text = #item.notifications == 1 and _("Delete this notification?")
+        or T(_("Delete these %1 notifications?"), #item.notifications),
     ok_text = _("Delete"),
     cancel_text = _("Cancel"),
     ok_callback = function()
       UIManager:close(confirm_dialog)
 
-      local response = Backend.removeNotification(item.id)
-      if response.type == 'ERROR' then
-        ErrorDialog:show(response.message)
+      for _, notification in ipairs(item.notifications) do
+        local response = Backend.removeNotification(notification.id)
+        if response.type == 'ERROR' then
+          ErrorDialog:show(response.message)
 
-        return
+          return
+        end
       end
 
-      local response = Backend.getNotifications()
+      local response = Backend.getNotificationGroups()
       if response.type == 'ERROR' then
         ErrorDialog:show(response.message)
 
         return
       end
 
-      self.notifications = response.body
+      self.groups = response.body
       self:updateItems()
     end,
     cancel_callback = function()
@@ -128,6 +128,14 @@ Settings.setting_value_definitions = {
       title = _("Optimize page images (experimental)"),
       default = false,
     }
+  },
+  {
+    'offline_mode',
+    {
+      type = 'boolean',
+      title = _("Offline mode"),
+      default = false,
+    }
   }
 }
 
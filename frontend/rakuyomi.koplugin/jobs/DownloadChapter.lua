@@ -56,6 +56,11 @@ function DownloadChapter:start()
   return response
 end
 
+--- @return SuccessfulResponse<[string, DownloadError[]]>|PendingResponse<DownloadProgress>|ErrorResponse
+function DownloadChapter:poll()
+  return Job.poll(self)
+end
+
 --- @return SuccessfulResponse<[string, DownloadError[]]>|ErrorResponse
 function DownloadChapter:runUntilCompletion()
   return Job.runUntilCompletion(self)
@@ -76,7 +76,7 @@ function DownloadUnreadChapters:start()
   return true
 end
 
---- @alias PendingState { type: 'INITIALIZING' }|{ type: 'DOWNLOADING', downloaded: number, total: number }
+--- @alias PendingState { type: 'INITIALIZING' }|{ type: 'DOWNLOADING', downloaded: number, total: number, chapter: DownloadProgress|nil }
 
 --- @return SuccessfulResponse<nil>|PendingResponse<PendingState>|ErrorResponse
 function DownloadUnreadChapters:poll()
@@ -3,7 +3,9 @@
 
 local logger = require('logger')
 local ffi = require('ffi')
+local Event = require('ui/event')
 local Paths = require('Paths')
+local UIManager = require('ui/uimanager')
 local rapidjson = require('rapidjson')
 
 -- Load HTTP modules for fetching source lists
@@ -270,6 +272,8 @@ ffi.cdef[[
     char* rakuyomi_get_settings(void);
     int rakuyomi_set_settings(const char* settings_json);
     char* rakuyomi_create_cbz(const char* cbz_path, const char* urls_json);
+    void rakuyomi_set_event_callback(void (*callback)(const char* event_json));
+    void rakuyomi_dispatch_events(void);
     void rakuyomi_free_string(char* s);
 ]]
 
@@ -1549,7 +1553,41 @@ function AndroidFFIServer:request(request)
     return { type = 'SUCCESS', status = 200, body = result_str }
 end
 
+-- Events of background activity are queued by the library until they're dispatched
+local EVENT_DISPATCH_INTERVAL_SECONDS = 2
+
+-- Registers the event callback, and broadcasts every event of the library as a
+-- `RakuyomiBackendEvent` UI event, the counterpart of the server's `/events` stream.
+function AndroidFFIServer:startEventDispatch()
+    self.eventCallback = ffi.cast("void (*)(const char*)", function(event_json)
+        local ok, event = pcall(rapidjson.decode, ffi.string(event_json))
+        if not ok or type(event) ~= "table" then
+            return
+        end
+
+        -- The callback may run in the middle of another library call, so the event is only
+        -- broadcast once it returned
+        UIManager:nextTick(function()
+            UIManager:broadcastEvent(Event:new("RakuyomiBackendEvent", event))
+        end)
+    end)
+    self.lib.rakuyomi_set_event_callback(self.eventCallback)
+
+    self.dispatchEvents = function()
+        self.lib.rakuyomi_dispatch_events()
+        UIManager:scheduleIn(EVENT_DISPATCH_INTERVAL_SECONDS, self.dispatchEvents)
+    end
+    UIManager:scheduleIn(EVENT_DISPATCH_INTERVAL_SECONDS, self.dispatchEvents)
+end
+
 function AndroidFFIServer:stop()
+    if self.eventCallback then
+        UIManager:unschedule(self.dispatchEvents)
+        self.lib.rakuyomi_set_event_callback(nil)
+        self.eventCallback:free()
+        self.eventCallback = nil
+    end
+
     logger.info("Android FFI server stopped")
 end
 
@@ -1573,7 +1611,10 @@ function AndroidFFIPlatform:startServer()
     
     logger.info("Android FFI server initialized successfully")
     
-    return AndroidFFIServer:new(lib)
+    local server = AndroidFFIServer:new(lib)
+    server:startEventDispatch()
+
+    return server
 end
 
 function AndroidFFIPlatform.isAndroid()